@@ -1,8 +1,11 @@
 //! Simple command line program to view the information of the user logged in.
 //!
-//! Logs in using the SCREEPS_API_USERNAME and SCREEPS_API_PASSWORD env variables.
+//! Connects to the server named by the SCREEPS_SERVER env variable in `.screeps.yaml`, or using
+//! the SCREEPS_API_URL and SCREEPS_API_TOKEN env variables.
 use std::borrow::Cow;
 
+use screeps_api::ServerProfile;
+
 /// Set up dotenv and retrieve a specific variable, informatively panicking if it does not exist.
 fn env(var: &str) -> String {
     dotenv::dotenv().ok();
@@ -26,6 +29,22 @@ fn opt_env(var: &str, default: &'static str) -> Cow<'static, str> {
     }
 }
 
+/// Reads the server named by `SCREEPS_SERVER` from the `.screeps.yaml` unified config file, or the
+/// server given by the `SCREEPS_API_URL` and `SCREEPS_API_TOKEN` env variables if it isn't set.
+fn server_profile() -> ServerProfile {
+    dotenv::dotenv().ok();
+    match ::std::env::var("SCREEPS_SERVER") {
+        Ok(name) => ServerProfile::from_unified_config(&name)
+            .unwrap_or_else(|e| panic!("must have server `{}` configured (err: {})", name, e)),
+        Err(_) => ServerProfile::from_url(&*opt_env(
+            "SCREEPS_API_URL",
+            screeps_api::DEFAULT_OFFICIAL_API_URL,
+        ))
+        .expect("expected SCREEPS_API_URL to be a valid http or https url")
+        .with_token(env("SCREEPS_API_TOKEN")),
+    }
+}
+
 fn main() {
     fern::Dispatch::new()
         .level(log::LevelFilter::Warn)
@@ -33,14 +52,7 @@ fn main() {
         .apply()
         .unwrap();
 
-    let mut client = screeps_api::SyncApi::new()
-        .unwrap()
-        .with_url(&opt_env(
-            "SCREEPS_API_URL",
-            screeps_api::DEFAULT_OFFICIAL_API_URL,
-        ))
-        .unwrap()
-        .with_token(env("SCREEPS_API_TOKEN"));
+    let mut client = server_profile().validated_sync_api().unwrap();
 
     let my_info = client.my_info().unwrap();
 
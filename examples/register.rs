@@ -2,6 +2,7 @@ use std::borrow::Cow;
 use std::io::{self, Write};
 
 use log::info;
+use screeps_api::ServerProfile;
 
 fn opt_env(var: &str, default: &'static str) -> Cow<'static, str> {
     dotenv::dotenv().ok();
@@ -36,8 +37,17 @@ fn setup_logging() {
         .unwrap_or(());
 }
 
-fn server_url() -> Cow<'static, str> {
-    opt_env("SCREEPS_API_URL", "http://127.0.0.1:21025/api/")
+/// Reads the server named by `SCREEPS_SERVER` from the `.screeps.yaml` unified config file, or the
+/// server given by the `SCREEPS_API_URL` env variable if it isn't set.
+fn server_profile() -> Result<ServerProfile, Box<dyn std::error::Error>> {
+    dotenv::dotenv().ok();
+    match ::std::env::var("SCREEPS_SERVER") {
+        Ok(name) => Ok(ServerProfile::from_unified_config(&name)?),
+        Err(_) => Ok(ServerProfile::from_url(&*opt_env(
+            "SCREEPS_API_URL",
+            "http://127.0.0.1:21025/api/",
+        ))?),
+    }
 }
 
 fn main() {
@@ -53,9 +63,10 @@ fn main() {
 }
 
 fn perform_registration() -> Result<(), Box<dyn std::error::Error>> {
-    let http_url = server_url();
+    let profile = server_profile()?;
+    let http_url = profile.api_url()?;
 
-    let mut client = screeps_api::SyncApi::new()?.with_url(&http_url)?;
+    let mut client = profile.sync_api()?;
 
     println!("New user registration! Connecting to {}", http_url);
 
@@ -1,9 +1,10 @@
 //! Simple command line program to view the top 10 users in power processing and GCL.
 //!
-//! Logs in using the SCREEPS_API_USERNAME and SCREEPS_API_PASSWORD env variables.
+//! Connects to the server named by the SCREEPS_SERVER env variable in `.screeps.yaml`, or using
+//! the SCREEPS_API_URL and SCREEPS_API_TOKEN env variables.
 use std::borrow::Cow;
 
-use screeps_api::{LeaderboardPage, LeaderboardType::*};
+use screeps_api::{LeaderboardPage, LeaderboardType::*, ServerProfile};
 
 /// Set up dotenv and retrieve a specific variable, informatively panicking if it does not exist.
 fn env(var: &str) -> String {
@@ -28,6 +29,22 @@ fn opt_env(var: &str, default: &'static str) -> Cow<'static, str> {
     }
 }
 
+/// Reads the server named by `SCREEPS_SERVER` from the `.screeps.yaml` unified config file, or the
+/// server given by the `SCREEPS_API_URL` and `SCREEPS_API_TOKEN` env variables if it isn't set.
+fn server_profile() -> ServerProfile {
+    dotenv::dotenv().ok();
+    match ::std::env::var("SCREEPS_SERVER") {
+        Ok(name) => ServerProfile::from_unified_config(&name)
+            .unwrap_or_else(|e| panic!("must have server `{}` configured (err: {})", name, e)),
+        Err(_) => ServerProfile::from_url(&*opt_env(
+            "SCREEPS_API_URL",
+            screeps_api::DEFAULT_OFFICIAL_API_URL,
+        ))
+        .expect("expected SCREEPS_API_URL to be a valid http or https url")
+        .with_token(env("SCREEPS_API_TOKEN")),
+    }
+}
+
 /// Prints to stdout information derived from the leaderboard page result.
 fn print_ranks(result: &LeaderboardPage) {
     for ranked_user in &result.ranks {
@@ -56,18 +73,7 @@ fn main() {
         .apply()
         .unwrap();
 
-    let mut client = screeps_api::SyncApi::new()
-        .unwrap()
-        .with_url(&opt_env(
-            "SCREEPS_API_URL",
-            screeps_api::DEFAULT_OFFICIAL_API_URL,
-        ))
-        .unwrap()
-        .with_token(env("SCREEPS_API_TOKEN"));
-
-    // client
-    //     .login(env("SCREEPS_API_USERNAME"), env("SCREEPS_API_PASSWORD"))
-    //     .unwrap();
+    let mut client = server_profile().validated_sync_api().unwrap();
 
     let top10gcl = client
         .leaderboard_page(GlobalControl, "2017-02", 10, 0)
@@ -8,7 +8,7 @@ use websocket::OwnedMessage;
 use screeps_api::websocket::{
     Channel, ChannelUpdate, ScreepsMessage, SockjsMessage, UserConsoleUpdate,
 };
-use screeps_api::TokenStorage;
+use screeps_api::{ServerProfile, TokenStorage};
 
 static CONSOLE_LOG_TARGET: &'static str = "log:console";
 static CONSOLE_RAW_OUTPUT_TARGET: &'static str = "log:console-raw";
@@ -76,20 +76,30 @@ fn subscribe_with(
     ))
 }
 
-fn server_url() -> Cow<'static, str> {
-    opt_env("SCREEPS_API_URL", screeps_api::DEFAULT_OFFICIAL_API_URL)
+/// Reads the server named by `SCREEPS_SERVER` from the `.screeps.yaml` unified config file, or the
+/// server given by the `SCREEPS_API_URL` and `SCREEPS_API_TOKEN` env variables if it isn't set.
+fn server_profile() -> ServerProfile {
+    dotenv::dotenv().ok();
+    match ::std::env::var("SCREEPS_SERVER") {
+        Ok(name) => ServerProfile::from_unified_config(&name)
+            .unwrap_or_else(|e| panic!("must have server `{}` configured (err: {})", name, e)),
+        Err(_) => ServerProfile::from_url(&*opt_env(
+            "SCREEPS_API_URL",
+            screeps_api::DEFAULT_OFFICIAL_API_URL,
+        ))
+        .expect("expected SCREEPS_API_URL to be a valid http or https url")
+        .with_token(env("SCREEPS_API_TOKEN")),
+    }
 }
 
 fn main() {
     setup_logging();
 
-    let http_url = server_url();
+    let profile = server_profile();
 
-    let mut client = screeps_api::SyncApi::new()
-        .unwrap()
-        .with_url(&http_url)
-        .unwrap()
-        .with_token(env("SCREEPS_API_TOKEN"));
+    let mut client = profile
+        .validated_sync_api()
+        .expect("connecting to server failed");
 
     let tokens = client.token_storage().clone();
 
@@ -97,7 +107,8 @@ fn main() {
 
     info!("connecting - {}", my_info.username);
 
-    let ws_url = screeps_api::websocket::transform_url(&http_url)
+    let ws_url = profile
+        .websocket_url()
         .expect("expected server api url to parse into websocket url");
 
     let connection =
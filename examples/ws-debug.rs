@@ -4,7 +4,7 @@ use futures01::{future, stream, Future, Sink, Stream};
 use log::{debug, info, warn};
 use screeps_api::{
     websocket::{commands, Channel, ChannelUpdate, ScreepsMessage, SockjsMessage},
-    RoomName, TokenStorage,
+    RoomName, ServerProfile, TokenStorage,
 };
 use websocket::OwnedMessage;
 
@@ -52,6 +52,7 @@ struct Config {
     rooms: Vec<RoomName>,
     map_view: Vec<RoomName>,
     url: Cow<'static, str>,
+    server: Option<String>,
 }
 
 impl Config {
@@ -85,9 +86,22 @@ impl Config {
                 .value_of("url")
                 .map(|v| v.to_owned().into())
                 .unwrap_or_else(|| screeps_api::DEFAULT_OFFICIAL_API_URL.into()),
+            server: args.value_of("server").map(ToOwned::to_owned),
         })
     }
 
+    /// The server named in `.screeps.yaml` if given, or the url given with the
+    /// `SCREEPS_API_TOKEN` env variable.
+    fn profile(&self) -> ServerProfile {
+        match self.server {
+            Some(ref name) => ServerProfile::from_unified_config(name)
+                .unwrap_or_else(|e| panic!("must have server `{}` configured (err: {})", name, e)),
+            None => ServerProfile::from_url(&*self.url)
+                .expect("expected server url to be a valid http or https url")
+                .with_token(env("SCREEPS_API_TOKEN")),
+        }
+    }
+
     fn subscribe_with(
         &self,
         id: &str,
@@ -212,6 +226,14 @@ fn setup() -> Config {
                 .help("Server url to connect to")
                 .takes_value(true),
         )
+        .arg(
+            clap::Arg::with_name("server")
+                .long("server")
+                .value_name("SERVER_NAME")
+                .help("Server in .screeps.yaml to connect to, instead of the url")
+                .takes_value(true)
+                .conflicts_with("url"),
+        )
         .get_matches();
 
     setup_logging(cmd_arguments.occurrences_of("verbose"));
@@ -234,11 +256,9 @@ fn main() {
 
     debug!("creating client");
 
-    let mut client = screeps_api::SyncApi::new()
-        .unwrap()
-        .with_url(&config.url)
-        .unwrap()
-        .with_token(env("SCREEPS_API_TOKEN"));
+    let profile = config.profile();
+
+    let mut client = profile.validated_sync_api().unwrap();
 
     let tokens = client.token_storage().clone();
 
@@ -251,7 +271,8 @@ fn main() {
         my_info.username
     );
 
-    let ws_url = screeps_api::websocket::transform_url(&config.url)
+    let ws_url = profile
+        .websocket_url()
         .expect("expected server api url to parse into websocket url.");
 
     let connection =
@@ -468,6 +489,26 @@ impl Handler {
                     user_id, target_user_id, update
                 );
             }
+            ChannelUpdate::UserMemoryPath {
+                user_id,
+                path,
+                update,
+            } => {
+                info!("Memory: [{}] {}: {:?}", user_id, path, update.value);
+            }
+            ChannelUpdate::UserActiveBranch { user_id, update } => {
+                info!("Active branch: [{}] {:?}", user_id, update);
+            }
+            ChannelUpdate::ServerMessage { update } => {
+                info!("Server message: {}", update.message);
+            }
+            ChannelUpdate::MapVisual {
+                user_id,
+                shard_name,
+                update,
+            } => {
+                info!("Map visuals: [{}/{}] {:#?}", user_id, shard_name, update);
+            }
             ChannelUpdate::Other { channel, update } => {
                 warn!(
                     "ChannelUpdate::Other: {}\n{}",
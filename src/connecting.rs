@@ -1,15 +1,19 @@
 //! Semi-internal functionality related to networking.
-use futures::stream::TryStreamExt;
+use futures::future::BoxFuture;
 use url::Url;
 
-use crate::{EndpointResult, Error, TokenStorage};
+use crate::{
+    rate_limit::{self, RateLimit},
+    transport::HttpResponse,
+    EndpointResult, Error, ErrorKind, RateLimitStorage, TokenStorage,
+};
 
-/// Interpret a hyper result as the result from a specific endpoint.
+/// Interpret a transport's response as the result from a specific endpoint.
 ///
 /// The returned future will:
 ///
-/// - Wait for the hyper request to finish
-/// - Wait for hyper request body, collecting it into a single chunk
+/// - Wait for the request to finish
+/// - Record any rate limit headers returned
 /// - Parse JSON body as the given `EndpointResult`, and return result/error.
 ///
 /// All errors returned will have the given `Url` contained as part of the context.
@@ -18,11 +22,15 @@ use crate::{EndpointResult, Error, TokenStorage};
 ///
 /// - `url`: url that is being queried, used only for error and warning messages
 /// - `tokens`: where to put any tokens that were returned, if any
-/// - `response`: actual hyper response that we're interpreting
+/// - `rate_limits`: where to put the endpoint's rate limit, if returned
+/// - `endpoint`: the endpoint path the rate limit applies to
+/// - `response`: actual response that we're interpreting
 pub(crate) async fn interpret<R>(
     tokens: TokenStorage,
+    rate_limits: RateLimitStorage,
+    endpoint: String,
     url: Url,
-    response: hyper::client::ResponseFuture,
+    response: BoxFuture<'static, Result<HttpResponse, Error>>,
 ) -> Result<R, Error>
 where
     R: EndpointResult,
@@ -30,27 +38,35 @@ where
     let response = response
         .await
         .map_err(|e| Error::with_url(e, Some(url.clone())))?;
-    if let Some(token) = response.headers().get("X-Token") {
+    if let Some(token) = response.headers.get("X-Token") {
         debug!(
             "replacing stored auth_token with token returned from API: {:?}",
             token.to_str()
         );
         tokens.set(token.as_bytes().to_owned().into());
     }
-    let status = response.status();
+    if let Some(limit) = RateLimit::from_headers(&response.headers) {
+        rate_limits.set(&endpoint, limit);
+    }
+    let status = response.status;
+    let rate_limited_until = if status == hyper::StatusCode::TOO_MANY_REQUESTS {
+        warn!("rate limit exceeded for endpoint {}", endpoint);
+        Some(rate_limit::rate_limited_until(
+            &response.headers,
+            time::get_time(),
+        ))
+    } else {
+        None
+    };
 
-    let data: Vec<u8> = response
-        .into_body()
-        .try_fold(Vec::new(), |mut data, chunk| async move {
-            data.extend_from_slice(&chunk);
-            Ok(data)
-        })
-        .await
-        .map_err(|e| Error::with_url(e, Some(url.clone())))?;
-    let data = bytes::Bytes::from(data);
+    let data = response.body;
     let json_result = serde_json::from_slice(&data);
 
-    // insert this check here so we can include response body in status errors.
+    // insert these checks here so we can include response body in status errors.
+    if let Some(reset_at) = rate_limited_until {
+        let err = ErrorKind::RateLimited { reset_at };
+        return Err(Error::with_body(err, Some(url), Some(data)));
+    }
     if !status.is_success() {
         if let Ok(json) = json_result {
             return Err(Error::with_json(status, Some(url), Some(json)));
@@ -0,0 +1,299 @@
+//! Logging in again when a session's token expires.
+use std::{fmt, sync::Arc};
+
+use url::Url;
+
+use crate::{
+    connecting,
+    transport::{HttpRequest, Transport},
+    Error, LoggedIn, LoginArgs, RateLimitStorage, RetryPolicy, TokenStorage,
+};
+
+/// A username and password to log in with.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Credentials {
+    /// The username or email to log in with.
+    pub username: String,
+    /// The password to log in with.
+    pub password: String,
+}
+
+impl Credentials {
+    /// Creates credentials with the given username and password.
+    pub fn new<U, P>(username: U, password: P) -> Self
+    where
+        U: Into<String>,
+        P: Into<String>,
+    {
+        Credentials {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"..")
+            .finish()
+    }
+}
+
+/// Supplies the credentials used to log in again when an authenticated call fails with
+/// [`ErrorKind::Unauthorized`], such as when a token from a [screepsmod-auth] server expires.
+///
+/// With a provider set, [`Api`] logs in once, stores the new token in its [`TokenStorage`] and
+/// retries the call. Logging in is retried following the [`RetryPolicy`], if any, and if it still
+/// fails its error is returned instead. The first login is still made with [`Api::login`]: calls
+/// made without any token fail with [`NoToken`] as usual.
+///
+/// [`ErrorKind::Unauthorized`]: enum.ErrorKind.html#variant.Unauthorized
+/// [screepsmod-auth]: https://github.com/ScreepsMods/screepsmod-auth
+/// [`Api`]: struct.Api.html
+/// [`TokenStorage`]: struct.TokenStorage.html
+/// [`RetryPolicy`]: struct.RetryPolicy.html
+/// [`Api::login`]: struct.Api.html#method.login
+/// [`NoToken`]: error/struct.NoToken.html
+#[derive(Clone)]
+pub struct CredentialsProvider(Arc<dyn Fn() -> Option<Credentials> + Send + Sync>);
+
+impl CredentialsProvider {
+    /// Creates a provider which asks the given function for credentials each time it needs to log
+    /// in. Returning `None` gives up, and returns the original `Unauthorized` error.
+    pub fn new<F>(provide: F) -> Self
+    where
+        F: Fn() -> Option<Credentials> + Send + Sync + 'static,
+    {
+        CredentialsProvider(Arc::new(provide))
+    }
+
+    /// Creates a provider which always logs in with the given username and password.
+    pub fn fixed<U, P>(username: U, password: P) -> Self
+    where
+        U: Into<String>,
+        P: Into<String>,
+    {
+        let credentials = Credentials::new(username, password);
+        CredentialsProvider::new(move || Some(credentials.clone()))
+    }
+
+    /// Gets the credentials to log in with, if any.
+    pub fn credentials(&self) -> Option<Credentials> {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for CredentialsProvider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("CredentialsProvider").finish()
+    }
+}
+
+/// Logs in with the given credentials, and stores the new token.
+///
+/// `url` is the url of the `auth/signin` endpoint. Logging in doesn't modify game state, so failed
+/// logins are retried with the given policy like any other call, counting their own attempts.
+pub(crate) async fn log_in_again<C: Transport>(
+    transport: C,
+    tokens: TokenStorage,
+    rate_limits: RateLimitStorage,
+    url: Url,
+    credentials: Credentials,
+    retry: Option<RetryPolicy>,
+) -> Result<(), Error> {
+    let Credentials { username, password } = credentials;
+    let body = serde_json::to_string(&LoginArgs::new(username, password))
+        .expect("expected serde_json::to_string to unfailingly succeed, but it failed.");
+
+    let mut attempt = 1;
+    loop {
+        let response = transport.send(HttpRequest {
+            method: hyper::Method::POST,
+            url: url.clone(),
+            auth_token: None,
+            body: Some(body.clone()),
+        });
+
+        // results may not be `Send`, so make sure this one is gone before waiting.
+        let before_next_attempt = {
+            let result: Result<LoggedIn, Error> = connecting::interpret(
+                tokens.clone(),
+                rate_limits.clone(),
+                "auth/signin".to_owned(),
+                url.clone(),
+                response,
+            )
+            .await;
+
+            let err = match result {
+                Ok(logged_in) => {
+                    logged_in.return_to(&tokens);
+                    return Ok(());
+                }
+                Err(err) => err,
+            };
+            let delay = retry
+                .as_ref()
+                .and_then(|retry| Some((retry.retry_delay(attempt, false, &err)?, retry)));
+            match delay {
+                Some((delay, retry)) => {
+                    warn!(
+                        "logging in again failed on attempt {}, retrying in {:?}: {}",
+                        attempt, delay, err
+                    );
+                    attempt += 1;
+                    retry.sleep(delay)
+                }
+                None => return Err(err),
+            }
+        };
+        before_next_attempt.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use futures::executor::block_on;
+    use hyper::StatusCode;
+
+    use super::*;
+    use crate::{
+        transport::{HttpResponse, MockTransport},
+        Api, ErrorKind, RetryPolicy,
+    };
+
+    /// A server which only accepts the token `"fresh"`, given out for the password `"right"`. The
+    /// first `unavailable` logins fail with `503 Service Unavailable`.
+    fn mock(logins: Arc<AtomicUsize>, unavailable: usize) -> MockTransport {
+        MockTransport::new(move |request| respond(&logins, unavailable, request))
+    }
+
+    fn respond(logins: &AtomicUsize, unavailable: usize, request: &HttpRequest) -> HttpResponse {
+        match request.url.path() {
+            "/api/auth/signin" => {
+                if logins.fetch_add(1, Ordering::SeqCst) < unavailable {
+                    return HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE, "unavailable");
+                }
+                let body: serde_json::Value =
+                    serde_json::from_str(request.body.as_ref().unwrap()).unwrap();
+                if body["password"] == "right" {
+                    HttpResponse::json(&json!({ "ok": 1, "token": "fresh" }))
+                } else {
+                    HttpResponse::new(StatusCode::UNAUTHORIZED, "Unauthorized")
+                }
+            }
+            "/api/user/world-start-room" if request.auth_token.as_deref() == Some(b"fresh") => {
+                HttpResponse::json(&json!({ "ok": 1, "room": ["shard0/W1N1"] }))
+            }
+            _ => HttpResponse::new(StatusCode::UNAUTHORIZED, "Unauthorized"),
+        }
+    }
+
+    fn assert_unauthorized(err: Error) {
+        match *err.kind() {
+            ErrorKind::Unauthorized => (),
+            ref other => panic!("expected unauthorized error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn logs_in_again_once_and_retries() {
+        let logins = Arc::new(AtomicUsize::new(0));
+        let api = Api::new(mock(logins.clone(), 0))
+            .with_token("expired")
+            .with_credentials_provider(CredentialsProvider::fixed("bob", "right"));
+
+        let room = block_on(api.world_start_room().unwrap()).unwrap();
+        assert_eq!(room.room_name, "W1N1");
+        assert_eq!(logins.load(Ordering::SeqCst), 1);
+        assert_eq!(api.token_storage().get().as_deref(), Some(&b"fresh"[..]));
+
+        // the new token is used from then on.
+        block_on(api.world_start_room().unwrap()).unwrap();
+        assert_eq!(logins.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn gives_up_after_failed_login() {
+        let logins = Arc::new(AtomicUsize::new(0));
+        let mut api = Api::new(mock(logins.clone(), 0))
+            .with_token("expired")
+            .with_credentials_provider(CredentialsProvider::fixed("bob", "wrong"));
+
+        assert_unauthorized(block_on(api.world_start_room().unwrap()).unwrap_err());
+        assert_eq!(logins.load(Ordering::SeqCst), 1);
+
+        api.set_credentials_provider(Some(CredentialsProvider::new(|| None)));
+        assert_unauthorized(block_on(api.world_start_room().unwrap()).unwrap_err());
+        assert_eq!(logins.load(Ordering::SeqCst), 1);
+
+        api.set_credentials_provider(None);
+        assert_unauthorized(block_on(api.world_start_room().unwrap()).unwrap_err());
+        assert_eq!(logins.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn retries_unavailable_login() {
+        let logins = Arc::new(AtomicUsize::new(0));
+        let mut policy = RetryPolicy::new(|_| futures::future::ready(()));
+        policy.jitter = false;
+        let api = Api::new(mock(logins.clone(), 1))
+            .with_token("expired")
+            .with_credentials_provider(CredentialsProvider::fixed("bob", "right"))
+            .with_retry_policy(policy);
+
+        let room = block_on(api.world_start_room().unwrap()).unwrap();
+        assert_eq!(room.room_name, "W1N1");
+        assert_eq!(logins.load(Ordering::SeqCst), 2);
+
+        // without a retry policy, the unavailable login's error is returned.
+        let logins = Arc::new(AtomicUsize::new(0));
+        let api = Api::new(mock(logins.clone(), 1))
+            .with_token("expired")
+            .with_credentials_provider(CredentialsProvider::fixed("bob", "right"));
+
+        let err = block_on(api.world_start_room().unwrap()).unwrap_err();
+        match *err.kind() {
+            ErrorKind::StatusCode(StatusCode::SERVICE_UNAVAILABLE) => (),
+            ref other => panic!("expected unavailable error, found {:?}", other),
+        }
+        assert_eq!(logins.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn login_retries_have_their_own_attempts() {
+        let logins = Arc::new(AtomicUsize::new(0));
+        let calls = Arc::new(AtomicUsize::new(0));
+        // the first call with the new token is unavailable too.
+        let transport = {
+            let (logins, calls) = (logins.clone(), calls.clone());
+            MockTransport::new(move |request| {
+                if request.auth_token.as_deref() == Some(b"fresh")
+                    && calls.fetch_add(1, Ordering::SeqCst) == 0
+                {
+                    return HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE, "unavailable");
+                }
+                respond(&logins, 1, request)
+            })
+        };
+        let mut policy = RetryPolicy::new(|_| futures::future::ready(()));
+        policy.jitter = false;
+        policy.max_attempts = 2;
+        let api = Api::new(transport)
+            .with_token("expired")
+            .with_credentials_provider(CredentialsProvider::fixed("bob", "right"))
+            .with_retry_policy(policy);
+
+        let room = block_on(api.world_start_room().unwrap()).unwrap();
+        assert_eq!(room.room_name, "W1N1");
+        assert_eq!(logins.load(Ordering::SeqCst), 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -101,6 +101,15 @@ impl RoomName {
             y_coord: if north { y_pos } else { -y_pos - 1 },
         }
     }
+
+    /// Whether this room is a highway room: a room with a displayed x or y coordinate divisible
+    /// by 10. Power banks and deposits spawn in highway rooms.
+    #[inline]
+    pub fn is_highway(&self) -> bool {
+        let displayed = |coord: i32| if coord >= 0 { coord } else { -coord - 1 };
+
+        displayed(self.x_coord) % 10 == 0 || displayed(self.y_coord) % 10 == 0
+    }
 }
 
 impl ops::Add<(i32, i32)> for RoomName {
@@ -322,4 +331,14 @@ mod tests {
             assert_eq!(&RoomName::new(string).unwrap(), expected);
         }
     }
+
+    #[test]
+    fn highway_rooms() {
+        for name in &["E0N5", "W10S3", "E5N20", "W0N0", "E30S30"] {
+            assert!(RoomName::new(name).unwrap().is_highway(), "{}", name);
+        }
+        for name in &["E1N1", "W9S9", "E11N19", "W5S5"] {
+            assert!(!RoomName::new(name).unwrap().is_highway(), "{}", name);
+        }
+    }
 }
@@ -2,6 +2,7 @@ mod numbers;
 mod timespec;
 
 pub mod null_as_default;
+pub mod vec_or_index_map;
 
 pub use numbers::*;
 pub use timespec::*;
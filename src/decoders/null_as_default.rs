@@ -1,4 +1,4 @@
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 /// Deserializes either a number or a string into an integer.
 pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
@@ -10,3 +10,12 @@ where
     let res: Option<T> = Deserialize::deserialize(deserializer)?;
     Ok(res.unwrap_or_default())
 }
+
+/// Serializes the value directly.
+pub fn serialize<T, S>(data: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    data.serialize(serializer)
+}
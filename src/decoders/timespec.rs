@@ -1,3 +1,10 @@
+use time::Timespec;
+
+/// Converts a millisecond unix timestamp, as the server sends them, into a `Timespec`.
+pub(crate) fn timespec_from_millis(millis: i64) -> Timespec {
+    Timespec::new(millis / 1000, ((millis % 1000) * 1_000_000) as i32)
+}
+
 /// Serialization / deserialization of `time::Timespec`.
 pub mod timespec_seconds {
     use serde::{Deserializer, Serializer};
@@ -230,7 +237,7 @@ pub mod double_optional_timespec_seconds {
 mod tests {
     use time;
 
-    use super::{optional_timespec_seconds, timespec_seconds};
+    use super::{optional_timespec_seconds, timespec_from_millis, timespec_seconds};
 
     #[test]
     fn parse_string_timespec() {
@@ -246,6 +253,13 @@ mod tests {
         assert_eq!(spec, time::Timespec::new(1475538699273i64, 0));
     }
 
+    #[test]
+    fn convert_millis_timespec() {
+        let spec = timespec_from_millis(1474674699273);
+
+        assert_eq!(spec, time::Timespec::new(1474674699, 273_000_000));
+    }
+
     #[test]
     fn parse_string_optional_timepsec() {
         let spec = optional_timespec_seconds::deserialize(&json!("1474674699273")).unwrap();
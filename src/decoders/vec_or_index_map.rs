@@ -0,0 +1,123 @@
+//! Deserializes a `Vec<T>` from either an array, an object with stringified indices as keys, or
+//! null.
+//!
+//! The server sometimes stores arrays on room objects as objects keyed by index, so this accepts
+//! `{"0": a, "1": b}` as well as `[a, b]`. Null is treated as an empty array. Serializing always
+//! produces an array.
+use std::{cmp, fmt, marker::PhantomData};
+
+use serde::{
+    de::{Deserialize, Deserializer, Error, MapAccess, SeqAccess, Unexpected, Visitor},
+    Serialize, Serializer,
+};
+
+struct IndexKey(u32);
+
+impl<'de> Deserialize<'de> for IndexKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct IndexKeyVisitor;
+
+        impl<'de> Visitor<'de> for IndexKeyVisitor {
+            type Value = IndexKey;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a string formatted index")
+            }
+
+            #[inline]
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                value
+                    .parse()
+                    .map(IndexKey)
+                    .map_err(|_| E::invalid_value(Unexpected::Str(value), &self))
+            }
+        }
+
+        deserializer.deserialize_str(IndexKeyVisitor)
+    }
+}
+
+struct VecOrIndexMapVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for VecOrIndexMapVisitor<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array, a map with index keys, or null")
+    }
+
+    #[inline]
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(Vec::new())
+    }
+
+    #[inline]
+    fn visit_none<E>(self) -> Result<Self::Value, E> {
+        Ok(Vec::new())
+    }
+
+    #[inline]
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    #[inline]
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut values = Vec::with_capacity(cmp::min(seq.size_hint().unwrap_or(0), 4096));
+
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+
+        Ok(values)
+    }
+
+    #[inline]
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut values = Vec::with_capacity(cmp::min(map.size_hint().unwrap_or(0), 4096));
+
+        while let Some((IndexKey(index), value)) = map.next_entry()? {
+            values.push((index, value));
+        }
+
+        values.sort_by_key(|&(index, _)| index);
+
+        Ok(values.into_iter().map(|(_, value)| value).collect())
+    }
+}
+
+/// Deserializes a `Vec<T>` from an array, an index-keyed map, or null.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(VecOrIndexMapVisitor(PhantomData))
+}
+
+/// Serializes a `Vec<T>` as an array.
+pub fn serialize<T, S>(data: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    data.serialize(serializer)
+}
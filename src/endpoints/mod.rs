@@ -6,13 +6,16 @@ mod login;
 mod map_stats;
 mod memory_segment;
 mod my_info;
+mod power_creeps;
 mod recent_pvp;
 mod register;
+mod room_history;
 mod room_overview;
 mod room_status;
 mod room_terrain;
 mod set_memory_segment;
 mod shards;
+mod version;
 mod world_start_room;
 
 // don't compile this endpoint template file with regular output, but still compile w/ tests to test for correctness.
@@ -20,9 +23,9 @@ mod world_start_room;
 pub mod template;
 
 pub use self::{
-    leaderboard::*, login::*, map_stats::*, my_info::*, recent_pvp::*, register::*,
-    room_overview::*, room_status::*, room_terrain::*, set_memory_segment::*, shards::*,
-    world_start_room::*,
+    leaderboard::*, login::*, map_stats::*, my_info::*, power_creeps::PowerCreepInfo,
+    recent_pvp::*, register::*, room_history::*, room_overview::*, room_status::*, room_terrain::*,
+    set_memory_segment::*, shards::*, version::*, world_start_room::*,
 };
 
-pub(crate) use self::memory_segment::*;
+pub(crate) use self::{memory_segment::*, power_creeps::*};
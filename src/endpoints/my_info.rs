@@ -24,6 +24,8 @@ pub(crate) struct Response {
     password: bool,
     cpu: i32,
     gcl: u64,
+    #[serde(default)]
+    power: u64,
     money: f64,
     // These can be added if needed
     // lastChargeTime: Option<String>,
@@ -53,6 +55,9 @@ pub struct MyInfo {
     pub cpu: i32,
     /// This user's current total count of GCL points (perform calculation to find actual gcl level).
     pub gcl_points: u64,
+    /// This user's current total count of power points (perform calculation to find actual gpl level).
+    #[serde(default)]
+    pub power_points: u64,
     /// This user's current credit balance.
     pub credits: f64,
     /// Information on per-shard allocation. Unavailable on non-sharded servers.
@@ -84,6 +89,7 @@ impl EndpointResult for MyInfo {
             password,
             cpu,
             gcl,
+            power,
             money,
             cpu_shard,
             cpu_shard_updated_time,
@@ -99,6 +105,7 @@ impl EndpointResult for MyInfo {
             has_password: password,
             cpu: cpu,
             gcl_points: gcl,
+            power_points: power,
             credits: money,
             shard_allocations: cpu_shard.and_then(|allocations| {
                 cpu_shard_updated_time.map(|last_update| UserCpuShardAllocation {
@@ -150,6 +157,7 @@ mod tests {
             },
             "ok": 1,
             "password": true,
+            "power": 1218000,
             "promoPeriodUntil": 1471635211172i64,
             "steam": {
                 "displayName": "daboross",
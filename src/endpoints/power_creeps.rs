@@ -0,0 +1,314 @@
+//! Creating power creep management calls and interpreting their results.
+use std::{borrow::Cow, collections::HashMap};
+
+use serde::{Deserialize, Serialize};
+use time::Timespec;
+
+use crate::{
+    data,
+    decoders::timespec_from_millis,
+    error::{ApiError, Result},
+    websocket::objects::PowerType,
+    EndpointResult,
+};
+
+/// Power creep list raw result.
+#[derive(Deserialize, Clone, Debug)]
+#[doc(hidden)]
+pub(crate) struct ListResponse {
+    ok: i32,
+    list: Vec<PowerCreepResponse>,
+}
+
+/// Power creep creation raw result.
+#[derive(Deserialize, Clone, Debug)]
+#[doc(hidden)]
+pub(crate) struct CreateResponse {
+    ok: i32,
+    creep: PowerCreepResponse,
+}
+
+/// Raw result for power creep calls which return no data.
+#[derive(Deserialize, Clone, Hash, Debug)]
+#[doc(hidden)]
+pub(crate) struct Response {
+    ok: i32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PowerCreepResponse {
+    #[serde(rename = "_id")]
+    id: String,
+    name: String,
+    class_name: String,
+    #[serde(default)]
+    level: u32,
+    #[serde(default)]
+    powers: HashMap<PowerType, PowerLevelResponse>,
+    #[serde(default)]
+    shard: Option<String>,
+    #[serde(default)]
+    spawn_cooldown_time: Option<i64>,
+    #[serde(default)]
+    delete_time: Option<i64>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct PowerLevelResponse {
+    level: u32,
+}
+
+/// Arguments for creating a new power creep.
+#[derive(Serialize, Clone, Hash, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CreatePowerCreepArgs<'a> {
+    pub name: Cow<'a, str>,
+    pub class_name: Cow<'a, str>,
+}
+
+/// Arguments for renaming a power creep.
+#[derive(Serialize, Clone, Hash, Debug)]
+pub(crate) struct RenamePowerCreepArgs<'a> {
+    pub id: Cow<'a, str>,
+    pub name: Cow<'a, str>,
+}
+
+/// Arguments for calls which only identify a power creep: delete and cancel-delete.
+#[derive(Serialize, Clone, Hash, Debug)]
+pub(crate) struct PowerCreepIdArgs<'a> {
+    pub id: Cow<'a, str>,
+}
+
+/// Arguments for upgrading a power creep's powers.
+#[derive(Serialize, Clone, Debug)]
+pub(crate) struct UpgradePowerCreepArgs<'a> {
+    pub id: Cow<'a, str>,
+    pub powers: HashMap<PowerType, u32>,
+}
+
+/// Information on one of the logged in user's power creeps.
+#[derive(Clone, Debug)]
+pub struct PowerCreepInfo {
+    /// The unique ID of this power creep.
+    pub id: String,
+    /// The name of this power creep.
+    pub name: String,
+    /// The class of this power creep, currently always `"operator"`.
+    pub class: String,
+    /// The power creep's level, equal to the total levels of all its powers.
+    pub level: u32,
+    /// The level of each power this creep has learned.
+    pub powers: HashMap<PowerType, u32>,
+    /// The shard this power creep is currently spawned on, if any.
+    pub shard: Option<String>,
+    /// The time before which this power creep can't be spawned again, if it recently died.
+    pub spawn_cooldown_time: Option<Timespec>,
+    /// The time at which this power creep will be permanently deleted, if deletion is pending.
+    pub delete_time: Option<Timespec>,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    _non_exhaustive: (),
+}
+
+impl From<PowerCreepResponse> for PowerCreepInfo {
+    fn from(raw: PowerCreepResponse) -> Self {
+        let PowerCreepResponse {
+            id,
+            name,
+            class_name,
+            level,
+            powers,
+            shard,
+            spawn_cooldown_time,
+            delete_time,
+        } = raw;
+
+        PowerCreepInfo {
+            id,
+            name,
+            class: class_name,
+            level,
+            powers: powers
+                .into_iter()
+                .map(|(power, info)| (power, info.level))
+                .collect(),
+            shard,
+            spawn_cooldown_time: spawn_cooldown_time.map(timespec_from_millis),
+            delete_time: delete_time.map(timespec_from_millis),
+            _non_exhaustive: (),
+        }
+    }
+}
+
+impl EndpointResult for Vec<PowerCreepInfo> {
+    type RequestResult = ListResponse;
+    type ErrorResult = data::ApiError;
+
+    fn from_raw(raw: ListResponse) -> Result<Vec<PowerCreepInfo>> {
+        let ListResponse { ok, list } = raw;
+
+        if ok != 1 {
+            return Err(ApiError::NotOk(ok).into());
+        }
+
+        Ok(list.into_iter().map(Into::into).collect())
+    }
+}
+
+impl EndpointResult for PowerCreepInfo {
+    type RequestResult = CreateResponse;
+    type ErrorResult = data::ApiError;
+
+    fn from_raw(raw: CreateResponse) -> Result<PowerCreepInfo> {
+        let CreateResponse { ok, creep } = raw;
+
+        if ok != 1 {
+            return Err(ApiError::NotOk(ok).into());
+        }
+
+        Ok(creep.into())
+    }
+}
+
+/// Result of a power creep call which returns nothing but success.
+#[derive(Clone, Hash, Debug)]
+pub(crate) struct PowerCreepCallResult {
+    /// Phantom data in order to allow adding any additional fields in the future.
+    _non_exhaustive: (),
+}
+
+impl EndpointResult for PowerCreepCallResult {
+    type RequestResult = Response;
+    type ErrorResult = data::ApiError;
+
+    fn from_raw(raw: Response) -> Result<Self> {
+        let Response { ok } = raw;
+
+        if ok != 1 {
+            return Err(ApiError::NotOk(ok).into());
+        }
+
+        Ok(PowerCreepCallResult {
+            _non_exhaustive: (),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EndpointResult;
+
+    #[test]
+    fn parse_sample_list() {
+        let response = serde_json::from_value(json!({
+            "ok": 1,
+            "list": [
+                {
+                    "_id": "5e3b1ba8c8d8b1e5a4a0a3b1",
+                    "name": "op1",
+                    "className": "operator",
+                    "user": "57874d42d0ae911e3bd15bbc",
+                    "level": 5,
+                    "hitsMax": 6000,
+                    "store": {},
+                    "storeCapacity": 600,
+                    "spawnCooldownTime": null,
+                    "powers": {
+                        "1": { "level": 3 },
+                        "5": { "level": 2 }
+                    },
+                    "shard": "shard2"
+                },
+                {
+                    "_id": "5e3b1ba8c8d8b1e5a4a0a3b2",
+                    "name": "op2",
+                    "className": "operator",
+                    "user": "57874d42d0ae911e3bd15bbc",
+                    "level": 0,
+                    "hitsMax": 1000,
+                    "store": {},
+                    "storeCapacity": 100,
+                    "spawnCooldownTime": 1580932132500i64,
+                    "deleteTime": 1581018532500i64,
+                    "powers": {}
+                }
+            ]
+        }))
+        .unwrap();
+
+        let list = Vec::<PowerCreepInfo>::from_raw(response).unwrap();
+
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].name, "op1");
+        assert_eq!(list[0].class, "operator");
+        assert_eq!(list[0].level, 5);
+        assert_eq!(list[0].powers.get(&PowerType::GenerateOps), Some(&3));
+        assert_eq!(list[0].powers.get(&PowerType::OperateLab), Some(&2));
+        assert_eq!(list[0].shard.as_deref(), Some("shard2"));
+        assert_eq!(list[0].spawn_cooldown_time, None);
+
+        assert!(list[1].powers.is_empty());
+        assert_eq!(list[1].shard, None);
+        assert_eq!(
+            list[1].spawn_cooldown_time,
+            Some(Timespec::new(1580932132, 500_000_000))
+        );
+        assert_eq!(
+            list[1].delete_time,
+            Some(Timespec::new(1581018532, 500_000_000))
+        );
+    }
+
+    #[test]
+    fn parse_sample_create() {
+        let response = serde_json::from_value(json!({
+            "ok": 1,
+            "creep": {
+                "_id": "5e3b1ba8c8d8b1e5a4a0a3b3",
+                "name": "op3",
+                "className": "operator",
+                "user": "57874d42d0ae911e3bd15bbc",
+                "level": 0,
+                "hitsMax": 1000,
+                "store": {},
+                "storeCapacity": 100,
+                "spawnCooldownTime": 0,
+                "powers": {}
+            }
+        }))
+        .unwrap();
+
+        let info = PowerCreepInfo::from_raw(response).unwrap();
+
+        assert_eq!(info.id, "5e3b1ba8c8d8b1e5a4a0a3b3");
+        assert_eq!(info.level, 0);
+        assert_eq!(info.spawn_cooldown_time, Some(Timespec::new(0, 0)));
+    }
+
+    #[test]
+    fn serialize_upgrade_args() {
+        let mut powers = HashMap::new();
+        powers.insert(PowerType::OperateSpawn, 2);
+
+        let args = UpgradePowerCreepArgs {
+            id: "5e3b1ba8c8d8b1e5a4a0a3b1".into(),
+            powers,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&args).unwrap(),
+            json!({
+                "id": "5e3b1ba8c8d8b1e5a4a0a3b1",
+                "powers": { "2": 2 }
+            })
+        );
+    }
+
+    #[test]
+    fn parse_sample_call_result() {
+        let response = serde_json::from_value(json!({ "ok": 1 })).unwrap();
+
+        let _ = PowerCreepCallResult::from_raw(response).unwrap();
+    }
+}
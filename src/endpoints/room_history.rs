@@ -0,0 +1,298 @@
+//! Interpreting room history chunks, and replaying them into full room states.
+use std::collections::{btree_map, hash_map::Entry, BTreeMap, HashMap};
+
+use serde::Deserialize;
+use time::Timespec;
+
+use crate::{
+    data::{self, RoomName},
+    decoders::timespec_from_millis,
+    error::{ApiError, Result},
+    websocket::objects::{KnownRoomObject, RoomObject},
+    EndpointResult,
+};
+
+/// Room history raw result.
+///
+/// Unlike most endpoints, history files are static and have no `ok` field.
+#[derive(Deserialize, Clone, Debug)]
+#[doc(hidden)]
+pub(crate) struct Response {
+    timestamp: Option<i64>,
+    room: String,
+    base: u32,
+    ticks: HashMap<String, Option<serde_json::Map<String, serde_json::Value>>>,
+}
+
+/// A chunk of a room's history, as stored by the server.
+///
+/// The first tick of a chunk holds a full snapshot of every object in the room, and each later tick
+/// holds only the changes since the tick before it, in the same shape as websocket room updates.
+/// Use [`RoomHistory::states`] to reconstruct the full room state at each tick.
+///
+/// [`RoomHistory::states`]: struct.RoomHistory.html#method.states
+#[derive(Clone, Debug)]
+pub struct RoomHistory {
+    /// The room this history is for.
+    pub room_name: RoomName,
+    /// The first tick in this chunk.
+    pub base_tick: u32,
+    /// The time at which this chunk was saved, if given.
+    pub timestamp: Option<Timespec>,
+    /// The raw changes for each tick with any, by game tick. Each maps object ids to either a full
+    /// object, a partial update to an existing object, or `null` for a removed object.
+    pub ticks: BTreeMap<u32, serde_json::Map<String, serde_json::Value>>,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    _non_exhaustive: (),
+}
+
+impl EndpointResult for RoomHistory {
+    type RequestResult = Response;
+    type ErrorResult = data::ApiError;
+
+    fn from_raw(raw: Response) -> Result<RoomHistory> {
+        let Response {
+            timestamp,
+            room,
+            base,
+            ticks,
+        } = raw;
+
+        let ticks = ticks
+            .into_iter()
+            .map(|(tick, changes)| {
+                let tick = tick.parse().map_err(|_| {
+                    ApiError::MalformedResponse(format!(
+                        "expected room history tick to be a number, found {:?}",
+                        tick
+                    ))
+                })?;
+                Ok((tick, changes.unwrap_or_default()))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(RoomHistory {
+            room_name: RoomName::new(&room)?,
+            base_tick: base,
+            timestamp: timestamp.map(timespec_from_millis),
+            ticks,
+            _non_exhaustive: (),
+        })
+    }
+}
+
+impl RoomHistory {
+    /// The last tick with changes in this chunk.
+    pub fn last_tick(&self) -> u32 {
+        self.ticks
+            .keys()
+            .next_back()
+            .cloned()
+            .unwrap_or(self.base_tick)
+    }
+
+    /// Returns an iterator reconstructing the full state of the room at every tick in this chunk,
+    /// from the base tick to the last tick with changes.
+    ///
+    /// Ticks without any changes are still yielded, with the same state as the tick before them.
+    pub fn states(&self) -> RoomHistoryStates<'_> {
+        RoomHistoryStates {
+            changes: self.ticks.iter().peekable(),
+            next_tick: self.base_tick,
+            last_tick: self.last_tick(),
+            objects: HashMap::new(),
+        }
+    }
+}
+
+/// The full state of a room at one tick of its history.
+#[derive(Clone, Debug)]
+pub struct RoomHistoryTick {
+    /// The game tick this state is at.
+    pub tick: u32,
+    /// Every object in the room, by id.
+    pub objects: HashMap<String, RoomObject>,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    _non_exhaustive: (),
+}
+
+impl RoomHistoryTick {
+    /// Iterates over every object in the room of a type this library knows how to parse.
+    pub fn known_objects(&self) -> impl Iterator<Item = &KnownRoomObject> {
+        self.objects.values().filter_map(RoomObject::known)
+    }
+
+    /// Gets a known room object by id.
+    pub fn get(&self, id: &str) -> Option<&KnownRoomObject> {
+        self.objects.get(id).and_then(RoomObject::known)
+    }
+}
+
+/// Iterator over the full state of a room at each tick of a [`RoomHistory`] chunk.
+///
+/// If any object in a tick fails to parse, that tick yields an error, and the room state used for
+/// later ticks will be missing the changes to that object.
+///
+/// [`RoomHistory`]: struct.RoomHistory.html
+#[derive(Debug)]
+pub struct RoomHistoryStates<'a> {
+    changes:
+        std::iter::Peekable<btree_map::Iter<'a, u32, serde_json::Map<String, serde_json::Value>>>,
+    next_tick: u32,
+    last_tick: u32,
+    objects: HashMap<String, RoomObject>,
+}
+
+impl<'a> RoomHistoryStates<'a> {
+    fn apply_tick(&mut self, tick: u32) -> std::result::Result<(), serde_json::Error> {
+        let mut result = Ok(());
+
+        while let Some((_, changes)) = self.changes.next_if(|&(&t, _)| t <= tick) {
+            for (id, value) in changes {
+                let applied = if value.is_null() {
+                    self.objects.remove(id);
+                    Ok(())
+                } else {
+                    match self.objects.entry(id.clone()) {
+                        Entry::Occupied(entry) => entry.into_mut().update(value.clone()),
+                        Entry::Vacant(entry) => RoomObject::from_value(value.clone()).map(|obj| {
+                            entry.insert(obj);
+                        }),
+                    }
+                };
+                if let Err(e) = applied {
+                    debug!("failed to apply history for object {} at tick {}", id, tick);
+                    result = result.and(Err(e));
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl<'a> Iterator for RoomHistoryStates<'a> {
+    type Item = std::result::Result<RoomHistoryTick, serde_json::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_tick > self.last_tick {
+            return None;
+        }
+        let tick = self.next_tick;
+        self.next_tick += 1;
+
+        Some(self.apply_tick(tick).map(|()| RoomHistoryTick {
+            tick,
+            objects: self.objects.clone(),
+            _non_exhaustive: (),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EndpointResult;
+
+    fn test_parse(json: serde_json::Value) -> RoomHistory {
+        let response = serde_json::from_value(json).unwrap();
+
+        RoomHistory::from_raw(response).unwrap()
+    }
+
+    fn sample_history() -> serde_json::Value {
+        json!({
+            "timestamp": 1593999862000i64,
+            "room": "W31N48",
+            "base": 100,
+            "ticks": {
+                "100": {
+                    "577b931b0f9d51615fa47abe": {
+                        "_id": "577b931b0f9d51615fa47abe",
+                        "energy": 2314,
+                        "energyCapacity": 3000,
+                        "invaderHarvested": 66584,
+                        "nextRegenerationTime": 38443952,
+                        "room": "W31N48",
+                        "ticksToRegeneration": 300,
+                        "type": "source",
+                        "x": 33,
+                        "y": 32
+                    },
+                    "5829cc2bba9a9a487c236088": {
+                        "_id": "5829cc2bba9a9a487c236088",
+                        "hits": 5000,
+                        "hitsMax": 5000,
+                        "nextDecayTime": 38444228,
+                        "notifyWhenAttacked": true,
+                        "room": "W31N48",
+                        "type": "road",
+                        "x": 38,
+                        "y": 37
+                    }
+                },
+                "101": {
+                    "577b931b0f9d51615fa47abe": {"energy": 2300, "invaderHarvested": 66598}
+                },
+                "103": {
+                    "5829cc2bba9a9a487c236088": null
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn parse_sample_room_history() {
+        let history = test_parse(sample_history());
+
+        assert_eq!(history.room_name, RoomName::new("W31N48").unwrap());
+        assert_eq!(history.base_tick, 100);
+        assert_eq!(history.timestamp, Some(Timespec::new(1593999862, 0)));
+        assert_eq!(history.ticks.len(), 3);
+        assert_eq!(history.last_tick(), 103);
+    }
+
+    #[test]
+    fn reconstruct_room_history_states() {
+        let history = test_parse(sample_history());
+
+        let states = history
+            .states()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            states.iter().map(|s| s.tick).collect::<Vec<_>>(),
+            vec![100, 101, 102, 103]
+        );
+        let source_energy = |state: &RoomHistoryTick| match state.get("577b931b0f9d51615fa47abe") {
+            Some(KnownRoomObject::Source(source)) => source.energy,
+            other => panic!("expected source, found {:?}", other),
+        };
+        assert_eq!(source_energy(&states[0]), 2314);
+        assert_eq!(source_energy(&states[1]), 2300);
+        assert_eq!(source_energy(&states[2]), 2300);
+        assert_eq!(states[2].known_objects().count(), 2);
+        assert_eq!(states[3].known_objects().count(), 1);
+    }
+
+    #[test]
+    fn bad_history_object_is_an_error() {
+        let history = test_parse(json!({
+            "room": "E1N1",
+            "base": 20,
+            "ticks": {
+                "20": {
+                    "5829cc2bba9a9a487c236088": {"hits": 5000}
+                },
+                "21": null
+            }
+        }));
+
+        let mut states = history.states();
+
+        assert!(states.next().unwrap().is_err());
+        assert_eq!(states.next().unwrap().unwrap().objects.len(), 0);
+        assert!(states.next().is_none());
+    }
+}
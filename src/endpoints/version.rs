@@ -0,0 +1,311 @@
+//! Interpreting server version calls.
+use serde::Deserialize;
+
+use crate::{
+    data,
+    error::{ApiError, Result},
+    EndpointResult,
+};
+
+/// Server version raw result.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Response {
+    ok: i32,
+    protocol: u32,
+    #[serde(default)]
+    package: Option<u32>,
+    #[serde(default)]
+    package_version: Option<String>,
+    #[serde(default)]
+    use_native_auth: bool,
+    #[serde(default)]
+    users: Option<u32>,
+    #[serde(default, with = "crate::decoders::null_as_default")]
+    server_data: ServerDataResponse,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct ServerDataResponse {
+    #[serde(default)]
+    shards: Vec<String>,
+    #[serde(default)]
+    custom_object_types: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    welcome_text: Option<String>,
+    #[serde(default)]
+    history_chunk_size: Option<u32>,
+    #[serde(default)]
+    socket_update_throttle: Option<u32>,
+    /// Mods report features either as plain names or as `{ name, version }` objects.
+    #[serde(default)]
+    features: Vec<serde_json::Value>,
+}
+
+/// How a server expects clients to authenticate.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AuthType {
+    /// Auth tokens generated in the account settings, set with [`Api::set_token`]. Used by the
+    /// official servers.
+    ///
+    /// [`Api::set_token`]: struct.Api.html#method.set_token
+    Token,
+    /// Username and password, exchanged for a short-lived token with [`Api::login`]. Used by
+    /// private servers with the [screepsmod-auth] mod.
+    ///
+    /// [`Api::login`]: struct.Api.html#method.login
+    /// [screepsmod-auth]: https://github.com/ScreepsMods/screepsmod-auth
+    Password,
+    /// Steam authentication through the game client only, which this library can't perform.
+    Steam,
+    /// A private server without [screepsmod-auth], or another mod reported as a feature. Such
+    /// servers usually only allow steam authentication through the game client.
+    ///
+    /// [screepsmod-auth]: https://github.com/ScreepsMods/screepsmod-auth
+    Unknown,
+}
+
+/// A feature a server reports, usually added by a mod.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ServerFeature {
+    /// The feature's name, usually the name of the mod providing it.
+    pub name: String,
+    /// The feature's version, if reported.
+    pub version: Option<String>,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    _non_exhaustive: (),
+}
+
+/// Information a server reports about itself: its versions, shards and features.
+#[derive(Clone, Debug)]
+pub struct ServerVersion {
+    /// The version of the protocol spoken by the server. The websocket reports the same version
+    /// in [`ScreepsMessage::ServerProtocol`] when connecting.
+    ///
+    /// [`ScreepsMessage::ServerProtocol`]: websocket/enum.ScreepsMessage.html#variant.ServerProtocol
+    pub protocol: u32,
+    /// The server's package number. Only reported by the official servers.
+    pub package: Option<u32>,
+    /// The version of the server's npm package. Only reported by private servers.
+    pub package_version: Option<String>,
+    /// Whether the server only allows authentication through steam.
+    pub use_native_auth: bool,
+    /// The number of users registered on the server, if reported.
+    pub users: Option<u32>,
+    /// The names of the server's shards. Empty for non-sharded servers.
+    pub shards: Vec<String>,
+    /// Room object types added by mods, with their rendering information.
+    pub custom_object_types: serde_json::Map<String, serde_json::Value>,
+    /// The welcome text private servers show on the login screen, as HTML.
+    pub welcome_text: Option<String>,
+    /// The number of ticks in each room history file, if reported.
+    pub history_chunk_size: Option<u32>,
+    /// The minimum interval between websocket updates in milliseconds, if reported.
+    pub socket_update_throttle: Option<u32>,
+    /// Features reported by the server's mods.
+    pub features: Vec<ServerFeature>,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    _non_exhaustive: (),
+}
+
+impl ServerVersion {
+    /// Whether the server is sharded, and shard-specific calls should be given shard names.
+    pub fn supports_shards(&self) -> bool {
+        !self.shards.is_empty()
+    }
+
+    /// How the server expects clients to authenticate.
+    ///
+    /// Official servers, which report a package number, use tokens. Private servers use
+    /// passwords if they report the screepsmod-auth feature, unless they only allow steam
+    /// authentication.
+    pub fn auth_type(&self) -> AuthType {
+        if self.use_native_auth {
+            AuthType::Steam
+        } else if self.package.is_some() {
+            AuthType::Token
+        } else if self.has_feature("screepsmod-auth") {
+            AuthType::Password
+        } else {
+            AuthType::Unknown
+        }
+    }
+
+    /// Whether the server reports a feature with the given name.
+    pub fn has_feature(&self, name: &str) -> bool {
+        self.features.iter().any(|feature| feature.name == name)
+    }
+}
+
+fn parse_feature(value: serde_json::Value) -> Option<ServerFeature> {
+    let (name, version) = match value {
+        serde_json::Value::String(name) => (name, None),
+        serde_json::Value::Object(mut map) => {
+            let name = match map.remove("name") {
+                Some(serde_json::Value::String(name)) => name,
+                _ => return None,
+            };
+            let version = match map.remove("version") {
+                Some(serde_json::Value::String(version)) => Some(version),
+                Some(serde_json::Value::Number(version)) => Some(version.to_string()),
+                _ => None,
+            };
+            (name, version)
+        }
+        _ => return None,
+    };
+
+    Some(ServerFeature {
+        name,
+        version,
+        _non_exhaustive: (),
+    })
+}
+
+impl EndpointResult for ServerVersion {
+    type RequestResult = Response;
+    type ErrorResult = data::ApiError;
+
+    fn from_raw(raw: Response) -> Result<ServerVersion> {
+        let Response {
+            ok,
+            protocol,
+            package,
+            package_version,
+            use_native_auth,
+            users,
+            server_data,
+        } = raw;
+
+        if ok != 1 {
+            return Err(ApiError::NotOk(ok).into());
+        }
+
+        let ServerDataResponse {
+            shards,
+            custom_object_types,
+            welcome_text,
+            history_chunk_size,
+            socket_update_throttle,
+            features,
+        } = server_data;
+
+        Ok(ServerVersion {
+            protocol,
+            package,
+            package_version,
+            use_native_auth,
+            users,
+            shards,
+            custom_object_types,
+            welcome_text,
+            history_chunk_size,
+            socket_update_throttle,
+            features: features.into_iter().filter_map(parse_feature).collect(),
+            _non_exhaustive: (),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuthType, ServerVersion};
+    use crate::EndpointResult;
+
+    fn test_parse(json: serde_json::Value) -> ServerVersion {
+        let response = serde_json::from_value(json).unwrap();
+
+        ServerVersion::from_raw(response).unwrap()
+    }
+
+    #[test]
+    fn parse_sample_official() {
+        let result = test_parse(json! ({
+            "ok": 1,
+            "package": 171,
+            "protocol": 14,
+            "serverData": {
+                "historyChunkSize": 100,
+                "shards": ["shard0", "shard1", "shard2", "shard3"]
+            },
+            "users": 48911
+        }));
+
+        assert_eq!(result.protocol, 14);
+        assert_eq!(result.package, Some(171));
+        assert_eq!(result.history_chunk_size, Some(100));
+        assert!(result.supports_shards());
+        assert_eq!(result.auth_type(), AuthType::Token);
+    }
+
+    #[test]
+    fn parse_sample_private() {
+        let result = test_parse(json! ({
+            "ok": 1,
+            "protocol": 14,
+            "useNativeAuth": false,
+            "users": 3,
+            "serverData": {
+                "welcomeText": "<h4>Welcome to your own Screeps private server!</h4>",
+                "customObjectTypes": {
+                    "teleporter": { "name": "teleporter", "sprite": "teleporter.svg" }
+                },
+                "historyChunkSize": 20,
+                "socketUpdateThrottle": 100,
+                "renderer": { "resources": {}, "metadata": {} },
+                "features": [
+                    { "name": "screepsmod-auth", "version": "2.6.0" },
+                    { "name": "screepsmod-admin-utils", "version": 1 },
+                    "screepsmod-market"
+                ]
+            },
+            "packageVersion": "4.2.13"
+        }));
+
+        assert_eq!(result.package_version.as_deref(), Some("4.2.13"));
+        assert!(!result.supports_shards());
+        assert_eq!(result.auth_type(), AuthType::Password);
+        assert!(result.custom_object_types.contains_key("teleporter"));
+        assert!(result.welcome_text.is_some());
+        assert_eq!(result.features.len(), 3);
+        assert_eq!(result.features[1].version.as_deref(), Some("1"));
+        assert!(result.has_feature("screepsmod-market"));
+        assert!(!result.has_feature("screepsmod-mongo"));
+    }
+
+    #[test]
+    fn parse_sample_native_auth() {
+        let result = test_parse(json! ({
+            "ok": 1,
+            "protocol": 13,
+            "useNativeAuth": true,
+            "serverData": null,
+            "packageVersion": "3.4.0"
+        }));
+
+        assert_eq!(result.auth_type(), AuthType::Steam);
+    }
+
+    #[test]
+    fn parse_sample_private_without_auth_mod() {
+        let result = test_parse(json! ({
+            "ok": 1,
+            "protocol": 14,
+            "useNativeAuth": false,
+            "users": 1,
+            "serverData": {
+                "historyChunkSize": 20,
+                "socketUpdateThrottle": 100,
+                "renderer": { "resources": {}, "metadata": {} },
+                "features": [
+                    { "name": "screepsmod-mongo", "version": "2.9.0" }
+                ]
+            },
+            "packageVersion": "4.2.13"
+        }));
+
+        assert!(result.has_feature("screepsmod-mongo"));
+        assert_eq!(result.auth_type(), AuthType::Unknown);
+    }
+}
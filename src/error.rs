@@ -23,6 +23,11 @@ pub enum ErrorKind {
     Io(io::Error),
     /// Error for when the server responds with a non-success HTTP status code.
     StatusCode(hyper::StatusCode),
+    /// The server responded with `429 Too Many Requests`: a rate limit has been exceeded.
+    RateLimited {
+        /// The time at which the server said calls will be allowed again, if it said.
+        reset_at: Option<time::Timespec>,
+    },
     /// API Error: when the server responds with a successful HTTP response, but the returned format is not what we
     /// expected.
     Api(ApiError),
@@ -143,6 +148,39 @@ impl Error {
     pub fn body(&self) -> Option<&bytes::Bytes> {
         self.data.body()
     }
+
+    /// Returns true if this error is likely temporary, and the same call could succeed if retried.
+    ///
+    /// This includes connection failures and resets, `502`, `503` and `504` responses, exceeded
+    /// rate limits and `ApiError::ServerDown`.
+    pub fn is_transient(&self) -> bool {
+        match self.err {
+            Hyper(ref err) => {
+                err.is_connect()
+                    || err.is_closed()
+                    || err.is_incomplete_message()
+                    || err.is_timeout()
+            }
+            Io(ref err) => matches!(
+                err.kind(),
+                io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::TimedOut
+                    | io::ErrorKind::Interrupted
+                    | io::ErrorKind::UnexpectedEof
+            ),
+            StatusCode(code) => matches!(
+                code,
+                hyper::StatusCode::BAD_GATEWAY
+                    | hyper::StatusCode::SERVICE_UNAVAILABLE
+                    | hyper::StatusCode::GATEWAY_TIMEOUT
+            ),
+            RateLimited { .. } => true,
+            Api(ApiError::ServerDown) => true,
+            _ => false,
+        }
+    }
 }
 
 /// Result type for screeps API operations.
@@ -186,6 +224,8 @@ impl From<hyper::StatusCode> for Error {
     fn from(code: hyper::StatusCode) -> Error {
         if code == hyper::StatusCode::UNAUTHORIZED {
             ErrorKind::Unauthorized.into()
+        } else if code == hyper::StatusCode::TOO_MANY_REQUESTS {
+            ErrorKind::RateLimited { reset_at: None }.into()
         } else {
             ErrorKind::StatusCode(code).into()
         }
@@ -220,6 +260,12 @@ impl fmt::Display for Error {
             Url(ref err) => err.fmt(f)?,
             Io(ref err) => err.fmt(f)?,
             StatusCode(ref status) => status.fmt(f)?,
+            RateLimited { reset_at } => {
+                write!(f, "rate limit exceeded")?;
+                if let Some(reset_at) = reset_at {
+                    write!(f, ", resets at unix time {}", reset_at.sec)?;
+                }
+            }
             Api(ref err) => err.fmt(f)?,
             RoomNameParse(ref err) => err.fmt(f)?,
             Unauthorized => {
@@ -255,7 +301,7 @@ impl StdError for Error {
             Io(ref err) => Some(err),
             Api(ref err) => Some(err),
             RoomNameParse(ref err) => Some(err),
-            StatusCode(_) | Unauthorized => None,
+            StatusCode(_) | RateLimited { .. } | Unauthorized => None,
             __Nonexhaustive => unreachable!(),
         }
     }
@@ -67,26 +67,42 @@ extern crate log;
 extern crate serde_json;
 
 mod connecting;
+mod credentials;
 mod data;
 mod decoders;
 #[cfg(feature = "protocol-docs")]
 pub mod docs;
 mod endpoints;
 pub mod error;
+pub mod planning;
+mod rate_limit;
+mod retry;
+mod server;
 #[cfg(feature = "sync")]
 pub mod sync;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod transport;
+mod unified_config;
 pub mod websocket;
 
 #[cfg(feature = "sync")]
 pub use crate::sync::SyncApi;
 pub use crate::{
+    credentials::{Credentials, CredentialsProvider},
     data::*,
     endpoints::*,
     error::{Error, ErrorKind, NoToken},
+    rate_limit::{RateLimit, RateLimitStorage, Throttle},
+    retry::RetryPolicy,
+    server::{ServerProfile, DEFAULT_PRIVATE_SERVER_PORT},
+    transport::Transport,
+    unified_config::{ConfigError, ServerConfig, UnifiedConfig},
 };
 
 use std::{
     borrow::Cow,
+    collections::HashMap,
     convert::AsRef,
     future::Future,
     marker::PhantomData,
@@ -94,9 +110,10 @@ use std::{
 };
 
 use futures::future::{BoxFuture, FutureExt, TryFutureExt};
-use hyper::header::{HeaderValue, CONTENT_TYPE};
 use url::Url;
 
+use crate::{transport::HttpRequest, websocket::objects::PowerType};
+
 /// A trait for each endpoint
 pub(crate) trait EndpointResult: Sized + 'static {
     type RequestResult: for<'de> serde::Deserialize<'de>;
@@ -132,14 +149,26 @@ impl TokenStorage {
 }
 
 /// API Object, stores the current API token and allows access to making requests.
+///
+/// Requests are sent with a [`Transport`], which is usually a `hyper::Client`.
+///
+/// [`Transport`]: transport/trait.Transport.html
 #[derive(Debug)]
 pub struct Api<C> {
     /// The base URL for this API instance.
     pub url: Url,
     /// The authentication token.
     auth_token: TokenStorage,
-    /// The hyper client.
-    client: hyper::client::Client<C>,
+    /// The transport used to send requests, usually a hyper client.
+    client: C,
+    /// The last known rate limits of each endpoint.
+    rate_limits: RateLimitStorage,
+    /// How to wait for exhausted rate limits, if calls should be throttled.
+    throttle: Option<Throttle>,
+    /// How to retry calls which fail with transient errors, if at all.
+    retry: Option<RetryPolicy>,
+    /// Where to get credentials to log in again with when a token expires, if anywhere.
+    credentials: Option<CredentialsProvider>,
 }
 
 impl<C> Clone for Api<C>
@@ -151,6 +180,10 @@ where
             url: self.url.clone(),
             auth_token: self.auth_token.clone(),
             client: self.client.clone(),
+            rate_limits: self.rate_limits.clone(),
+            throttle: self.throttle.clone(),
+            retry: self.retry.clone(),
+            credentials: self.credentials.clone(),
         }
     }
 }
@@ -171,11 +204,15 @@ impl<C> Api<C> {
     /// The returned instance can be used to make anonymous calls. Use [`Api::with_token`] or
     /// [`Api::set_token`] to enable authenticated access.
     #[inline]
-    pub fn new(client: hyper::client::Client<C>) -> Self {
+    pub fn new(client: C) -> Self {
         Api {
             url: default_url(),
             client: client,
             auth_token: TokenStorage::default(),
+            rate_limits: RateLimitStorage::default(),
+            throttle: None,
+            retry: None,
+            credentials: None,
         }
     }
 
@@ -223,11 +260,85 @@ impl<C> Api<C> {
     pub fn token_storage(&self) -> &TokenStorage {
         &self.auth_token
     }
+
+    /// Retrieves the rate limits reported by the server for each endpoint called so far.
+    ///
+    /// When cloned, clients share the same rate limit storage.
+    #[inline]
+    pub fn rate_limits(&self) -> &RateLimitStorage {
+        &self.rate_limits
+    }
+
+    /// Gets the remaining budget for an endpoint, as last reported by the server. Endpoints are
+    /// identified by their path relative to the API url, such as `"game/room-terrain"`.
+    #[inline]
+    pub fn rate_limit(&self, endpoint: &str) -> Option<RateLimit> {
+        self.rate_limits.get(endpoint)
+    }
+
+    /// Enables throttling: calls to an endpoint with no calls left are delayed until its rate
+    /// limit resets, using the given throttle to wait.
+    ///
+    /// See also [`Api::with_throttle`].
+    #[inline]
+    pub fn set_throttle(&mut self, throttle: Option<Throttle>) {
+        self.throttle = throttle;
+    }
+
+    /// Enables throttling with the given throttle, and returns the client.
+    ///
+    /// See also [`Api::set_throttle`].
+    #[inline]
+    pub fn with_throttle(mut self, throttle: Throttle) -> Self {
+        self.set_throttle(Some(throttle));
+        self
+    }
+
+    /// Sets how calls which fail with transient errors are retried, or disables retrying if
+    /// `None`. Retrying is disabled by default.
+    ///
+    /// See also [`Api::with_retry_policy`].
+    #[inline]
+    pub fn set_retry_policy(&mut self, policy: Option<RetryPolicy>) {
+        self.retry = policy;
+    }
+
+    /// Sets how calls which fail with transient errors are retried, and returns the client.
+    ///
+    /// See also [`Api::set_retry_policy`].
+    #[inline]
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.set_retry_policy(Some(policy));
+        self
+    }
+
+    /// Sets where to get credentials to log in again with when an authenticated call fails with
+    /// [`ErrorKind::Unauthorized`], or disables logging in again if `None`. Disabled by default.
+    ///
+    /// See [`CredentialsProvider`] for more information, and also
+    /// [`Api::with_credentials_provider`].
+    ///
+    /// [`ErrorKind::Unauthorized`]: enum.ErrorKind.html#variant.Unauthorized
+    /// [`CredentialsProvider`]: struct.CredentialsProvider.html
+    #[inline]
+    pub fn set_credentials_provider(&mut self, provider: Option<CredentialsProvider>) {
+        self.credentials = provider;
+    }
+
+    /// Sets where to get credentials to log in again with when a token expires, and returns the
+    /// client.
+    ///
+    /// See also [`Api::set_credentials_provider`].
+    #[inline]
+    pub fn with_credentials_provider(mut self, provider: CredentialsProvider) -> Self {
+        self.set_credentials_provider(Some(provider));
+        self
+    }
 }
 
 impl<C> Api<C>
 where
-    C: hyper::client::connect::Connect + Clone + Send + Sync + 'static,
+    C: Transport,
 {
     /// Starts preparing a POST or GET request to the given endpoint URL
     #[inline]
@@ -241,6 +352,7 @@ where
             endpoint,
             post_body: None,
             query_params: None,
+            mutating: false,
             _phantom: PhantomData,
         }
     }
@@ -306,7 +418,7 @@ where
         &self,
         details: RegistrationArgs,
     ) -> impl Future<Output = Result<RegistrationSuccess, Error>> {
-        self.post("register/submit", details).send()
+        self.post("register/submit", details).mutating().send()
     }
 
     /// Gets user information on the user currently logged in, including username and user id.
@@ -410,6 +522,39 @@ where
         }
     }
 
+    /// Gets one chunk of a room's history, starting at the given tick. Use
+    /// [`RoomHistory::states`] to reconstruct the full room state at each tick in the chunk.
+    ///
+    /// History is saved in chunks of a fixed number of ticks (20 on the official server), so `tick`
+    /// must be a multiple of the chunk size. Chunks are only kept for a limited time.
+    ///
+    /// Does not require authentication.
+    ///
+    /// [`RoomHistory::states`]: struct.RoomHistory.html#method.states
+    pub fn room_history<'b, U, V>(
+        &self,
+        room_name: U,
+        shard: Option<V>,
+        tick: u32,
+    ) -> impl Future<Output = Result<RoomHistory, Error>>
+    where
+        U: Into<Cow<'b, str>>,
+        V: Into<Cow<'b, str>>,
+    {
+        // history files are served next to the api, rather than as part of it.
+        let endpoint = match shard {
+            Some(shard) => format!(
+                "../room-history/{}/{}/{}.json",
+                shard.into(),
+                room_name.into(),
+                tick
+            ),
+            None => format!("../room-history/{}/{}.json", room_name.into(), tick),
+        };
+
+        self.get(&endpoint).send()
+    }
+
     /// Gets a list of shards available on this server. Errors with a `404` error when connected to
     /// a non-sharded server.
     ///
@@ -418,6 +563,51 @@ where
         self.get("game/shards/info").send()
     }
 
+    /// Checks that this client's url points at a screeps server, by calling `api/version`.
+    ///
+    /// Fails with the error of the call if the url is wrong, such as a missing `/ptr/` prefix or a
+    /// private server's port left out.
+    ///
+    /// Does not require authentication.
+    pub fn check_server(&self) -> impl Future<Output = Result<(), Error>> {
+        self.version().map_ok(|_| ())
+    }
+
+    /// Gets information the server reports about itself: its protocol version, shards, features
+    /// and how it authenticates users.
+    ///
+    /// Does not require authentication.
+    pub fn version(&self) -> impl Future<Output = Result<ServerVersion, Error>> {
+        self.get("version").send()
+    }
+
+    /// Checks whether the server is sharded, and shard-specific calls should be given shard names.
+    ///
+    /// See [`ServerVersion::supports_shards`] for more information.
+    ///
+    /// [`ServerVersion::supports_shards`]: struct.ServerVersion.html#method.supports_shards
+    pub fn supports_shards(&self) -> impl Future<Output = Result<bool, Error>> {
+        self.version().map_ok(|version| version.supports_shards())
+    }
+
+    /// Checks how the server expects clients to authenticate: with an auth token, or with a
+    /// username and password.
+    ///
+    /// See [`ServerVersion::auth_type`] for more information.
+    ///
+    /// [`ServerVersion::auth_type`]: struct.ServerVersion.html#method.auth_type
+    pub fn auth_type(&self) -> impl Future<Output = Result<AuthType, Error>> {
+        self.version().map_ok(|version| version.auth_type())
+    }
+
+    /// Gets the protocol version the server's websocket will report in
+    /// [`ScreepsMessage::ServerProtocol`] when connecting.
+    ///
+    /// [`ScreepsMessage::ServerProtocol`]: websocket/enum.ScreepsMessage.html#variant.ServerProtocol
+    pub fn protocol_version(&self) -> impl Future<Output = Result<u32, Error>> {
+        self.version().map_ok(|version| version.protocol)
+    }
+
     /// Gets the "status" of a room: if it is open, if it is in a novice area, if it exists.
     pub fn room_status<'b, U>(
         &self,
@@ -587,10 +777,138 @@ where
         };
 
         self.post("user/memory-segment", args)
+            .mutating()
             .auth()
             .send()
             .map(|fut| fut.map_ok(|_: SetMemorySegment| ()))
     }
+
+    /// Gets a list of all of the logged in user's power creeps, including their class, level,
+    /// learned powers and the shard they are spawned on, if any.
+    pub fn power_creeps(
+        &self,
+    ) -> Result<impl Future<Output = Result<Vec<PowerCreepInfo>, Error>>, NoToken> {
+        self.get("game/power-creeps/list").auth().send()
+    }
+
+    /// Creates a new power creep with the given name and class, returning the new creep.
+    ///
+    /// The only class currently available is `"operator"`.
+    pub fn create_power_creep<'b, U, V>(
+        &self,
+        name: U,
+        class_name: V,
+    ) -> Result<impl Future<Output = Result<PowerCreepInfo, Error>>, NoToken>
+    where
+        U: Into<Cow<'b, str>>,
+        V: Into<Cow<'b, str>>,
+    {
+        let args = CreatePowerCreepArgs {
+            name: name.into(),
+            class_name: class_name.into(),
+        };
+
+        self.post("game/power-creeps/create", args)
+            .mutating()
+            .auth()
+            .send()
+    }
+
+    /// Renames a power creep. Only possible while the power creep is not spawned.
+    pub fn rename_power_creep<'b, U, V>(
+        &self,
+        id: U,
+        name: V,
+    ) -> Result<impl Future<Output = Result<(), Error>>, NoToken>
+    where
+        U: Into<Cow<'b, str>>,
+        V: Into<Cow<'b, str>>,
+    {
+        let args = RenamePowerCreepArgs {
+            id: id.into(),
+            name: name.into(),
+        };
+
+        self.post("game/power-creeps/rename", args)
+            .mutating()
+            .auth()
+            .send()
+            .map(|fut| fut.map_ok(|_: PowerCreepCallResult| ()))
+    }
+
+    /// Marks a power creep for deletion. The power creep will be permanently deleted after 24
+    /// hours, unless [`Api::cancel_delete_power_creep`] is called before then.
+    ///
+    /// [`Api::cancel_delete_power_creep`]: #method.cancel_delete_power_creep
+    pub fn delete_power_creep<'b, U>(
+        &self,
+        id: U,
+    ) -> Result<impl Future<Output = Result<(), Error>>, NoToken>
+    where
+        U: Into<Cow<'b, str>>,
+    {
+        let args = PowerCreepIdArgs { id: id.into() };
+
+        self.post("game/power-creeps/delete", args)
+            .mutating()
+            .auth()
+            .send()
+            .map(|fut| fut.map_ok(|_: PowerCreepCallResult| ()))
+    }
+
+    /// Cancels a pending deletion of a power creep.
+    pub fn cancel_delete_power_creep<'b, U>(
+        &self,
+        id: U,
+    ) -> Result<impl Future<Output = Result<(), Error>>, NoToken>
+    where
+        U: Into<Cow<'b, str>>,
+    {
+        let args = PowerCreepIdArgs { id: id.into() };
+
+        self.post("game/power-creeps/cancel-delete", args)
+            .mutating()
+            .auth()
+            .send()
+            .map(|fut| fut.map_ok(|_: PowerCreepCallResult| ()))
+    }
+
+    /// Upgrades a power creep's powers. `powers` maps each power to its new level.
+    ///
+    /// Each level gained uses one global power level, see [`gpl_calc`].
+    ///
+    /// [`gpl_calc`]: fn.gpl_calc.html
+    pub fn upgrade_power_creep<'b, U>(
+        &self,
+        id: U,
+        powers: HashMap<PowerType, u32>,
+    ) -> Result<impl Future<Output = Result<(), Error>>, NoToken>
+    where
+        U: Into<Cow<'b, str>>,
+    {
+        let args = UpgradePowerCreepArgs {
+            id: id.into(),
+            powers,
+        };
+
+        self.post("game/power-creeps/upgrade", args)
+            .mutating()
+            .auth()
+            .send()
+            .map(|fut| fut.map_ok(|_: PowerCreepCallResult| ()))
+    }
+
+    /// Starts an experimentation period, allowing all power creeps to be reset without cooldown
+    /// for the next 24 hours.
+    pub fn power_creep_experimentation(
+        &self,
+    ) -> Result<impl Future<Output = Result<(), Error>>, NoToken> {
+        self.post("game/power-creeps/experimentation", serde_json::Map::new())
+            .mutating()
+            .auth()
+            .send()
+            .map(|fut| fut.map_ok(|_: PowerCreepCallResult| ()))
+    }
 }
 
 trait PartialRequestAuth<T> {
@@ -634,7 +952,7 @@ impl<T> PartialRequestAuth<T> for AuthRequired {
 
 struct PartialRequest<'a, C, R, A = NoAuthRequired, S = &'static str>
 where
-    C: hyper::client::connect::Connect,
+    C: Transport,
     R: EndpointResult,
     S: serde::Serialize + 'a,
 {
@@ -642,12 +960,13 @@ where
     endpoint: &'a str,
     query_params: Option<&'a [(&'static str, String)]>,
     post_body: Option<S>,
+    mutating: bool,
     _phantom: PhantomData<(R, A)>,
 }
 
 impl<'a, C, R, S> PartialRequest<'a, C, R, NoAuthRequired, S>
 where
-    C: hyper::client::connect::Connect + 'static,
+    C: Transport,
     R: EndpointResult,
     S: serde::Serialize,
 {
@@ -658,6 +977,7 @@ where
             endpoint: self.endpoint,
             query_params: self.query_params,
             post_body: self.post_body,
+            mutating: self.mutating,
             _phantom: PhantomData,
         }
     }
@@ -665,7 +985,7 @@ where
 
 impl<'a, C, R, S> PartialRequest<'a, C, R, AuthRequired, S>
 where
-    C: hyper::client::connect::Connect + Clone + Send + Sync + 'static,
+    C: Transport,
     R: EndpointResult,
     S: serde::Serialize,
 {
@@ -678,6 +998,7 @@ where
             endpoint: self.endpoint,
             query_params: self.query_params,
             post_body: self.post_body,
+            mutating: self.mutating,
             _phantom: PhantomData,
         }
     }
@@ -685,7 +1006,7 @@ where
 
 impl<'a, C, R, A, S> PartialRequest<'a, C, R, A, S>
 where
-    C: hyper::client::connect::Connect + Clone + Send + Sync + 'static,
+    C: Transport,
     R: EndpointResult,
     A: PartialRequestAuth<BoxFuture<'static, Result<R, Error>>>,
     S: serde::Serialize,
@@ -702,6 +1023,14 @@ where
         self
     }
 
+    /// Marks this request as modifying game state, so it is only retried if the retry policy
+    /// allows it.
+    #[inline]
+    fn mutating(mut self) -> Self {
+        self.mutating = true;
+        self
+    }
+
     /// Result type here _so hacky!_ Glad this is an internal API.
     ///
     /// Returns either `connecting::impl Future<Output=Result< Error=Error>` or `Result<connecting::FutureResponse<R>, NoToken>`
@@ -712,6 +1041,7 @@ where
             endpoint,
             query_params,
             post_body,
+            mutating,
             _phantom: _,
         } = self;
 
@@ -753,35 +1083,106 @@ where
             temp
         };
 
-        let mut request = hyper::Request::builder();
-
-        request = request.method(method).uri(url.as_str());
-
-        // headers
-        request = request.header(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-
-        if let Some(token) = auth_token {
-            request = request.header(
-                "X-Token",
-                HeaderValue::from_maybe_shared(token.clone())
-                    // TODO: turn this into a non-expect error (how the heck does this function return errors?)
-                    .expect("tokens should always be valid headers"),
-            );
-        }
-
-        let request = if let Some(ref serializable) = post_body {
-            request.body(hyper::Body::from(
-                serde_json::to_string(serializable).expect(
-                    "expected serde_json::to_string to unfailingly succeed, but it failed.",
-                ),
-            ))
-        } else {
-            request.body(hyper::Body::empty())
+        let body = post_body.as_ref().map(|serializable| {
+            serde_json::to_string(serializable)
+                .expect("expected serde_json::to_string to unfailingly succeed, but it failed.")
+        });
+
+        let tokens = client.auth_token.clone();
+        let rate_limits = client.rate_limits.clone();
+        let throttle = client.throttle.clone();
+        let retry = client.retry.clone();
+        // only calls made with a token can have it expire.
+        let credentials = match auth_token {
+            Some(_) => client.credentials.clone(),
+            None => None,
+        };
+        let login_url = client
+            .url
+            .join("auth/signin")
+            .expect("expected pre-set endpoint url text to succeed, but it failed.");
+        let transport = client.client.clone();
+        let endpoint = endpoint.to_owned();
+        let finished = async move {
+            let mut attempt = 1;
+            let mut logged_in_again = false;
+            loop {
+                if let Some(ref throttle) = throttle {
+                    if let Some(wait) = rate_limits.wait_time(&endpoint) {
+                        debug!("throttling call to {} for {:?}", endpoint, wait);
+                        throttle.sleep(wait).await;
+                    }
+                }
+
+                // retries use the latest token, since each response may have replaced it.
+                let token = match auth_token {
+                    Some(ref token) if attempt > 1 || logged_in_again => {
+                        tokens.get().or_else(|| Some(token.clone()))
+                    }
+                    ref token => token.clone(),
+                };
+                let response = transport.send(HttpRequest {
+                    method: method.clone(),
+                    url: url.clone(),
+                    auth_token: token,
+                    body: body.clone(),
+                });
+
+                // results may not be `Send`, so make sure this one is gone before waiting.
+                let before_next_attempt = {
+                    let result = connecting::interpret(
+                        tokens.clone(),
+                        rate_limits.clone(),
+                        endpoint.clone(),
+                        url.clone(),
+                        response,
+                    )
+                    .await;
+
+                    let err = match result {
+                        Err(err) => err,
+                        result => return result,
+                    };
+                    let log_in_with = match (err.kind(), &credentials) {
+                        (ErrorKind::Unauthorized, Some(credentials)) if !logged_in_again => {
+                            credentials.credentials()
+                        }
+                        _ => None,
+                    };
+                    match (log_in_with, &retry) {
+                        (Some(log_in_with), retry) => {
+                            warn!(
+                                "call to {} was unauthorized, logging in again as {}",
+                                endpoint, log_in_with.username
+                            );
+                            logged_in_again = true;
+                            credentials::log_in_again(
+                                transport.clone(),
+                                tokens.clone(),
+                                rate_limits.clone(),
+                                login_url.clone(),
+                                log_in_with,
+                                retry.clone(),
+                            )
+                            .boxed()
+                        }
+                        (None, Some(retry)) => match retry.retry_delay(attempt, mutating, &err) {
+                            Some(delay) => {
+                                warn!(
+                                    "call to {} failed on attempt {}, retrying in {:?}: {}",
+                                    endpoint, attempt, delay, err
+                                );
+                                attempt += 1;
+                                retry.sleep(delay).map(Ok).boxed()
+                            }
+                            None => return Err(err),
+                        },
+                        (None, None) => return Err(err),
+                    }
+                };
+                before_next_attempt.await?;
+            }
         };
-        let request = request.expect("building http request should never fail");
-
-        let hyper_future = client.client.request(request);
-        let finished = connecting::interpret(client.auth_token.clone(), url, hyper_future);
 
         // turns into either `Result<FutureResponse<..>>` or `FutureResponse<..>` depending on
         // if we required auth.
@@ -801,9 +1202,23 @@ pub fn gcl_calc(gcl_points: u64) -> u64 {
         + 1
 }
 
+/// Calculates GPL, given power points.
+///
+/// Unlike GCL, GPL starts at level 0. Each level is one power level which can be spent on
+/// power creeps.
+#[inline]
+pub fn gpl_calc(power_points: u64) -> u64 {
+    const POWER_LEVEL_INV_MULTIPLY: f64 = 1.0 / 1_000f64;
+    const POWER_LEVEL_INV_POW: f64 = 1.0 / 2f64;
+
+    ((power_points as f64) * POWER_LEVEL_INV_MULTIPLY)
+        .powf(POWER_LEVEL_INV_POW)
+        .floor() as u64
+}
+
 #[cfg(test)]
 mod tests {
-    use super::gcl_calc;
+    use super::{gcl_calc, gpl_calc};
 
     #[test]
     fn parse_gcl_1() {
@@ -826,4 +1241,14 @@ mod tests {
     fn parse_gcl_late_15() {
         assert_eq!(gcl_calc(657_254_041), 15);
     }
+
+    #[test]
+    fn parse_gpl() {
+        assert_eq!(gpl_calc(0), 0);
+        assert_eq!(gpl_calc(999), 0);
+        assert_eq!(gpl_calc(1_000), 1);
+        assert_eq!(gpl_calc(3_999), 1);
+        assert_eq!(gpl_calc(4_000), 2);
+        assert_eq!(gpl_calc(1_000_000), 31);
+    }
 }
@@ -0,0 +1,528 @@
+//! Room-planning primitives built on top of room terrain.
+//!
+//! All functions in this module operate on a [`TerrainGrid`] and produce [`CostMatrix`] values
+//! indexed in the same way as the terrain: `matrix[y_pos][x_pos]`. This allows planners to compose
+//! the results - for instance, picking the most open area reachable from a controller by combining
+//! [`distance_transform`] and [`flood_fill`].
+//!
+//! [`TerrainGrid`]: ../type.TerrainGrid.html
+//! [`CostMatrix`]: type.CostMatrix.html
+//! [`distance_transform`]: fn.distance_transform.html
+//! [`flood_fill`]: fn.flood_fill.html
+use std::collections::VecDeque;
+
+use crate::{TerrainGrid, TerrainType};
+
+/// Width and height of a room, in tiles.
+pub const ROOM_SIZE: usize = 50;
+
+/// Value used in cost matrices returned from this module for tiles which cannot be reached.
+pub const UNREACHABLE: u8 = 255;
+
+/// A 50x50 grid of costs. `matrix[y_pos][x_pos]` can be used to access any tile from x 0-49,
+/// y 0-49.
+pub type CostMatrix = [[u8; ROOM_SIZE]; ROOM_SIZE];
+
+/// Offsets to all 8 neighbours of a tile.
+const NEIGHBOURS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Returns true if creeps can move onto the given terrain.
+#[inline]
+pub fn is_walkable(terrain: TerrainType) -> bool {
+    match terrain {
+        TerrainType::Plains | TerrainType::Swamp => true,
+        TerrainType::Wall | TerrainType::SwampyWall => false,
+    }
+}
+
+#[inline]
+fn walkable_at(terrain: &TerrainGrid, x: usize, y: usize) -> bool {
+    match terrain.get(y).and_then(|row| row.get(x)) {
+        Some(&t) => is_walkable(t),
+        None => false,
+    }
+}
+
+/// Iterates over all in-room neighbours of the given tile.
+fn neighbours(x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+    NEIGHBOURS.iter().filter_map(move |&(dx, dy)| {
+        let nx = x as i32 + dx;
+        let ny = y as i32 + dy;
+        if nx >= 0 && ny >= 0 && (nx as usize) < ROOM_SIZE && (ny as usize) < ROOM_SIZE {
+            Some((nx as usize, ny as usize))
+        } else {
+            None
+        }
+    })
+}
+
+#[inline]
+fn is_edge(x: usize, y: usize) -> bool {
+    x == 0 || y == 0 || x == ROOM_SIZE - 1 || y == ROOM_SIZE - 1
+}
+
+/// Finds all exit tiles in a room: walkable tiles on the room's border.
+pub fn exits(terrain: &TerrainGrid) -> Vec<(u32, u32)> {
+    let mut result = Vec::new();
+    for y in 0..ROOM_SIZE {
+        for x in 0..ROOM_SIZE {
+            if is_edge(x, y) && walkable_at(terrain, x, y) {
+                result.push((x as u32, y as u32));
+            }
+        }
+    }
+    result
+}
+
+/// Computes the distance from each tile to the nearest wall, counting diagonal moves as a single
+/// step.
+///
+/// Wall tiles have a distance of `0`. Tiles outside the room are treated as walls, so walkable
+/// tiles on the room's border have a distance of `1`. Distances saturate at `254`.
+pub fn distance_transform(terrain: &TerrainGrid) -> CostMatrix {
+    let mut result = [[UNREACHABLE; ROOM_SIZE]; ROOM_SIZE];
+    let mut queue = VecDeque::new();
+
+    for (y, row) in result.iter_mut().enumerate() {
+        for (x, cell) in row.iter_mut().enumerate() {
+            if !walkable_at(terrain, x, y) {
+                *cell = 0;
+                queue.push_back((x, y));
+            } else if is_edge(x, y) {
+                *cell = 1;
+                queue.push_back((x, y));
+            }
+        }
+    }
+
+    bfs(&mut result, queue, |_, _| true);
+
+    result
+}
+
+/// Computes the walking distance from the closest of the given starting tiles to every other tile.
+///
+/// Starting tiles have a distance of `0`. Walls, and tiles which cannot be reached from any
+/// starting tile, have a value of [`UNREACHABLE`]. Distances saturate at `254`. Starting tiles which
+/// are walls or outside of the room are ignored.
+///
+/// [`UNREACHABLE`]: constant.UNREACHABLE.html
+pub fn flood_fill<I>(terrain: &TerrainGrid, start: I) -> CostMatrix
+where
+    I: IntoIterator<Item = (u32, u32)>,
+{
+    let mut result = [[UNREACHABLE; ROOM_SIZE]; ROOM_SIZE];
+    let mut queue = VecDeque::new();
+
+    for (x, y) in start {
+        let (x, y) = (x as usize, y as usize);
+        if walkable_at(terrain, x, y) && result[y][x] != 0 {
+            result[y][x] = 0;
+            queue.push_back((x, y));
+        }
+    }
+
+    bfs(&mut result, queue, |x, y| walkable_at(terrain, x, y));
+
+    result
+}
+
+/// Computes the walking distance from the closest exit tile to every other tile.
+///
+/// This is equivalent to calling [`flood_fill`] with the result of [`exits`]. Tiles with a value
+/// of [`UNREACHABLE`] cannot be reached from outside the room.
+///
+/// [`flood_fill`]: fn.flood_fill.html
+/// [`exits`]: fn.exits.html
+/// [`UNREACHABLE`]: constant.UNREACHABLE.html
+pub fn distance_from_exits(terrain: &TerrainGrid) -> CostMatrix {
+    flood_fill(terrain, exits(terrain))
+}
+
+/// Breadth-first search which fills in unvisited (`UNREACHABLE`) tiles with one more than the
+/// distance of the tile they were reached from.
+fn bfs<F>(result: &mut CostMatrix, mut queue: VecDeque<(usize, usize)>, can_enter: F)
+where
+    F: Fn(usize, usize) -> bool,
+{
+    while let Some((x, y)) = queue.pop_front() {
+        let next = result[y][x].saturating_add(1).min(UNREACHABLE - 1);
+        for (nx, ny) in neighbours(x, y) {
+            if result[ny][nx] == UNREACHABLE && can_enter(nx, ny) {
+                result[ny][nx] = next;
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+}
+
+/// Finds a minimal set of tiles which, when covered in ramparts or walls, separates every exit of
+/// the room from all of the given protected tiles.
+///
+/// Protected tiles themselves are never part of the cut.
+///
+/// Structures can't be built on the room's border, and ramparts can't be built next to exit tiles,
+/// so the cut will never include tiles within range 1 of an exit. Protected tiles within that range
+/// can never be protected, and are ignored. Walls and tiles outside of the room are also ignored.
+///
+/// Returns the tiles in the cut, sorted by `(y, x)`. If nothing needs protecting, or the protected
+/// tiles are unreachable from any exit, the result is empty.
+pub fn min_cut<I>(terrain: &TerrainGrid, protected: I) -> Vec<(u32, u32)>
+where
+    I: IntoIterator<Item = (u32, u32)>,
+{
+    let exit_tiles = exits(terrain);
+
+    let mut near_exit = [[false; ROOM_SIZE]; ROOM_SIZE];
+    for &(x, y) in &exit_tiles {
+        let (x, y) = (x as usize, y as usize);
+        near_exit[y][x] = true;
+        for (nx, ny) in neighbours(x, y) {
+            near_exit[ny][nx] = true;
+        }
+    }
+
+    let mut graph = FlowGraph::new(ROOM_SIZE * ROOM_SIZE * 2 + 2);
+    let source = ROOM_SIZE * ROOM_SIZE * 2;
+    let sink = source + 1;
+    let tile_in = |x: usize, y: usize| (y * ROOM_SIZE + x) * 2;
+    let tile_out = |x: usize, y: usize| (y * ROOM_SIZE + x) * 2 + 1;
+
+    let mut is_protected = [[false; ROOM_SIZE]; ROOM_SIZE];
+    let mut any_protected = false;
+    for (x, y) in protected {
+        let (x, y) = (x as usize, y as usize);
+        if walkable_at(terrain, x, y) && !near_exit[y][x] && !is_protected[y][x] {
+            graph.add_edge(source, tile_in(x, y), INFINITE_CAPACITY);
+            is_protected[y][x] = true;
+            any_protected = true;
+        }
+    }
+    if !any_protected {
+        return Vec::new();
+    }
+
+    for y in 0..ROOM_SIZE {
+        for x in 0..ROOM_SIZE {
+            if !walkable_at(terrain, x, y) {
+                continue;
+            }
+            if near_exit[y][x] {
+                graph.add_edge(tile_out(x, y), sink, INFINITE_CAPACITY);
+                graph.add_edge(tile_in(x, y), tile_out(x, y), INFINITE_CAPACITY);
+            } else if is_protected[y][x] {
+                graph.add_edge(tile_in(x, y), tile_out(x, y), INFINITE_CAPACITY);
+            } else {
+                graph.add_edge(tile_in(x, y), tile_out(x, y), 1);
+            }
+            for (nx, ny) in neighbours(x, y) {
+                if walkable_at(terrain, nx, ny) {
+                    graph.add_edge(tile_out(x, y), tile_in(nx, ny), INFINITE_CAPACITY);
+                }
+            }
+        }
+    }
+
+    graph.max_flow(source, sink);
+
+    let reachable = graph.reachable_from(source);
+
+    let mut result = Vec::new();
+    for y in 0..ROOM_SIZE {
+        for x in 0..ROOM_SIZE {
+            if walkable_at(terrain, x, y)
+                && !near_exit[y][x]
+                && reachable[tile_in(x, y)]
+                && !reachable[tile_out(x, y)]
+            {
+                result.push((x as u32, y as u32));
+            }
+        }
+    }
+    result
+}
+
+const INFINITE_CAPACITY: u32 = u32::MAX / 2;
+
+struct FlowEdge {
+    to: usize,
+    capacity: u32,
+}
+
+/// A directed graph for computing maximum flow using Dinic's algorithm.
+struct FlowGraph {
+    /// Edges, stored so that edge `i ^ 1` is always the reverse of edge `i`.
+    edges: Vec<FlowEdge>,
+    /// Indices into `edges` for each node.
+    adjacent: Vec<Vec<usize>>,
+    level: Vec<i32>,
+    next_edge: Vec<usize>,
+}
+
+impl FlowGraph {
+    fn new(nodes: usize) -> Self {
+        FlowGraph {
+            edges: Vec::new(),
+            adjacent: vec![Vec::new(); nodes],
+            level: vec![-1; nodes],
+            next_edge: vec![0; nodes],
+        }
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, capacity: u32) {
+        self.adjacent[from].push(self.edges.len());
+        self.edges.push(FlowEdge { to, capacity });
+        self.adjacent[to].push(self.edges.len());
+        self.edges.push(FlowEdge {
+            to: from,
+            capacity: 0,
+        });
+    }
+
+    fn build_levels(&mut self, source: usize, sink: usize) -> bool {
+        for level in &mut self.level {
+            *level = -1;
+        }
+        self.level[source] = 0;
+        let mut queue = VecDeque::new();
+        queue.push_back(source);
+        while let Some(node) = queue.pop_front() {
+            for &edge_index in &self.adjacent[node] {
+                let edge = &self.edges[edge_index];
+                if edge.capacity > 0 && self.level[edge.to] < 0 {
+                    self.level[edge.to] = self.level[node] + 1;
+                    queue.push_back(edge.to);
+                }
+            }
+        }
+        self.level[sink] >= 0
+    }
+
+    /// Finds one augmenting path in the level graph, returning the flow pushed through it.
+    fn augment(&mut self, source: usize, sink: usize) -> u32 {
+        // iterative depth-first search, to avoid overflowing the stack on long paths.
+        let mut path: Vec<usize> = Vec::new();
+        let mut node = source;
+        loop {
+            if node == sink {
+                let flow = path
+                    .iter()
+                    .map(|&edge_index| self.edges[edge_index].capacity)
+                    .min()
+                    .unwrap_or(0);
+                for &edge_index in &path {
+                    self.edges[edge_index].capacity -= flow;
+                    self.edges[edge_index ^ 1].capacity += flow;
+                }
+                return flow;
+            }
+            let mut advanced = false;
+            while self.next_edge[node] < self.adjacent[node].len() {
+                let edge_index = self.adjacent[node][self.next_edge[node]];
+                let edge = &self.edges[edge_index];
+                if edge.capacity > 0 && self.level[edge.to] == self.level[node] + 1 {
+                    path.push(edge_index);
+                    node = edge.to;
+                    advanced = true;
+                    break;
+                }
+                self.next_edge[node] += 1;
+            }
+            if !advanced {
+                // dead end: remove this node from the level graph and retreat.
+                self.level[node] = -1;
+                match path.pop() {
+                    Some(edge_index) => {
+                        node = self.edges[edge_index ^ 1].to;
+                        self.next_edge[node] += 1;
+                    }
+                    None => return 0,
+                }
+            }
+        }
+    }
+
+    fn max_flow(&mut self, source: usize, sink: usize) -> u64 {
+        let mut total = 0;
+        while self.build_levels(source, sink) {
+            for next in &mut self.next_edge {
+                *next = 0;
+            }
+            loop {
+                let flow = self.augment(source, sink);
+                if flow == 0 {
+                    break;
+                }
+                total += u64::from(flow);
+            }
+        }
+        total
+    }
+
+    /// Finds all nodes reachable from the given node through edges with remaining capacity.
+    fn reachable_from(&self, source: usize) -> Vec<bool> {
+        let mut visited = vec![false; self.adjacent.len()];
+        visited[source] = true;
+        let mut queue = VecDeque::new();
+        queue.push_back(source);
+        while let Some(node) = queue.pop_front() {
+            for &edge_index in &self.adjacent[node] {
+                let edge = &self.edges[edge_index];
+                if edge.capacity > 0 && !visited[edge.to] {
+                    visited[edge.to] = true;
+                    queue.push_back(edge.to);
+                }
+            }
+        }
+        visited
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a terrain grid from a list of rows, where `#` is a wall and `.` is plains. Missing
+    /// rows and columns are filled in with plains.
+    fn terrain_from_rows(rows: &[&str]) -> TerrainGrid {
+        (0..ROOM_SIZE)
+            .map(|y| {
+                let row = rows.get(y).map(|r| r.as_bytes()).unwrap_or(&[]);
+                (0..ROOM_SIZE)
+                    .map(|x| match row.get(x) {
+                        Some(b'#') => TerrainType::Wall,
+                        Some(b'~') => TerrainType::Swamp,
+                        _ => TerrainType::Plains,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// A room walled in on all sides, except for a single 3-wide exit on the top edge leading into
+    /// a 5-wide corridor.
+    fn single_exit_room() -> TerrainGrid {
+        let mut rows = vec!["#".repeat(ROOM_SIZE); ROOM_SIZE];
+        rows[0] = format!("{}...{}", "#".repeat(23), "#".repeat(24));
+        for row in rows.iter_mut().skip(1).take(5) {
+            *row = format!("{}.....{}", "#".repeat(22), "#".repeat(23));
+        }
+        for row in rows.iter_mut().skip(6).take(ROOM_SIZE - 7) {
+            *row = format!("#{}#", ".".repeat(ROOM_SIZE - 2));
+        }
+        let rows = rows.iter().map(String::as_str).collect::<Vec<_>>();
+        terrain_from_rows(&rows)
+    }
+
+    #[test]
+    fn distance_transform_open_room() {
+        let terrain = terrain_from_rows(&[]);
+        let dt = distance_transform(&terrain);
+
+        assert_eq!(dt[0][0], 1);
+        assert_eq!(dt[1][1], 2);
+        assert_eq!(dt[0][25], 1);
+        assert_eq!(dt[24][24], 25);
+        assert_eq!(dt[25][25], 25);
+    }
+
+    #[test]
+    fn distance_transform_with_wall() {
+        let terrain = terrain_from_rows(&["", "", "", "", "", "", "", "", "", "", "..........#"]);
+        let dt = distance_transform(&terrain);
+
+        assert_eq!(dt[10][10], 0);
+        assert_eq!(dt[11][11], 1);
+        assert_eq!(dt[12][10], 2);
+        assert_eq!(dt[10][13], 3);
+    }
+
+    #[test]
+    fn flood_fill_stops_at_walls() {
+        let terrain = terrain_from_rows(&["..#..", "..#..", "###.."]);
+        let filled = flood_fill(&terrain, vec![(0, 0)]);
+
+        assert_eq!(filled[0][0], 0);
+        assert_eq!(filled[1][1], 1);
+        assert_eq!(filled[0][2], UNREACHABLE);
+        assert_eq!(filled[0][3], UNREACHABLE);
+        assert_eq!(filled[5][5], UNREACHABLE);
+    }
+
+    #[test]
+    fn flood_fill_ignores_walls_as_start() {
+        let terrain = terrain_from_rows(&["#"]);
+        let filled = flood_fill(&terrain, vec![(0, 0), (60, 3)]);
+
+        assert!(filled
+            .iter()
+            .all(|row| row.iter().all(|&c| c == UNREACHABLE)));
+    }
+
+    #[test]
+    fn distance_from_exits_single_exit() {
+        let terrain = single_exit_room();
+        let exit_tiles = exits(&terrain);
+        assert_eq!(exit_tiles, vec![(23, 0), (24, 0), (25, 0)]);
+
+        let filled = distance_from_exits(&terrain);
+        assert_eq!(filled[0][24], 0);
+        assert_eq!(filled[1][24], 1);
+        assert_eq!(filled[10][24], 10);
+        assert_eq!(filled[1][0], UNREACHABLE);
+    }
+
+    #[test]
+    fn min_cut_single_exit() {
+        let terrain = single_exit_room();
+        let cut = min_cut(&terrain, vec![(25, 25)]);
+
+        // any row of the corridor past its first is a minimal cut. The first row is next to exit
+        // tiles, so it can't be used.
+        assert_eq!(cut.len(), 5);
+        assert!(cut
+            .iter()
+            .all(|&(x, y)| (22..=26).contains(&x) && (2..=5).contains(&y)));
+
+        // verify that the cut actually separates the protected tile from the exit.
+        let mut blocked = terrain.clone();
+        for &(x, y) in &cut {
+            blocked[y as usize][x as usize] = TerrainType::Wall;
+        }
+        assert_eq!(distance_from_exits(&blocked)[25][25], UNREACHABLE);
+    }
+
+    #[test]
+    fn min_cut_unreachable_or_empty() {
+        let terrain = single_exit_room();
+        assert!(min_cut(&terrain, Vec::new()).is_empty());
+        // protected tiles next to exits are ignored.
+        assert!(min_cut(&terrain, vec![(24, 1)]).is_empty());
+        // walls are ignored.
+        assert!(min_cut(&terrain, vec![(0, 25)]).is_empty());
+    }
+
+    #[test]
+    fn min_cut_open_room_encloses_area() {
+        let terrain = terrain_from_rows(&[]);
+        let cut = min_cut(&terrain, vec![(25, 25)]);
+
+        assert!(!cut.is_empty());
+        assert!(!cut.contains(&(25, 25)));
+
+        let mut blocked = terrain.clone();
+        for &(x, y) in &cut {
+            blocked[y as usize][x as usize] = TerrainType::Wall;
+        }
+        assert_eq!(distance_from_exits(&blocked)[25][25], UNREACHABLE);
+    }
+}
@@ -0,0 +1,233 @@
+//! Tracking of the HTTP API's rate limits.
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, PoisonError, RwLock},
+    time::Duration,
+};
+
+use futures::future::{BoxFuture, Future, FutureExt};
+use hyper::header::{HeaderMap, RETRY_AFTER};
+use time::Timespec;
+
+/// The rate limit state of a single endpoint, as last reported by the server.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    /// The number of calls allowed per period.
+    pub limit: u32,
+    /// The number of calls left in the current period.
+    pub remaining: u32,
+    /// The time at which the current period ends and `remaining` is reset to `limit`.
+    pub reset_at: Timespec,
+}
+
+impl RateLimit {
+    /// Reads the `X-RateLimit-Limit`, `X-RateLimit-Remaining` and `X-RateLimit-Reset` headers of a
+    /// response. Returns `None` if any are missing or malformed.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name| headers.get(name)?.to_str().ok().map(str::trim);
+
+        Some(RateLimit {
+            limit: header("X-RateLimit-Limit")?.parse::<u32>().ok()?,
+            remaining: header("X-RateLimit-Remaining")?.parse::<u32>().ok()?,
+            reset_at: Timespec::new(header("X-RateLimit-Reset")?.parse::<i64>().ok()?, 0),
+        })
+    }
+
+    /// Returns true if no calls are left in the current period.
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// How long to wait from `now` before another call will be allowed, if no calls are left.
+    pub fn wait_time(&self, now: Timespec) -> Option<Duration> {
+        if self.is_exhausted() && self.reset_at > now {
+            (self.reset_at - now).to_std().ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// Finds when a `429 Too Many Requests` response says to try again: from `Retry-After` if given,
+/// otherwise from `X-RateLimit-Reset`.
+pub(crate) fn rate_limited_until(headers: &HeaderMap, now: Timespec) -> Option<Timespec> {
+    let retry_after = headers
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<i64>().ok());
+
+    match retry_after {
+        Some(seconds) => Some(Timespec::new(now.sec + seconds, now.nsec)),
+        None => RateLimit::from_headers(headers).map(|limit| limit.reset_at),
+    }
+}
+
+/// Storage for the rate limits of each endpoint called, updated from every response.
+///
+/// Endpoints are identified by the path relative to the API url, such as `"game/room-terrain"`.
+///
+/// When cloned, the clone will share the same underlying synchronized storage.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimitStorage(Arc<RwLock<HashMap<String, RateLimit>>>);
+
+impl RateLimitStorage {
+    /// Gets the last known rate limit of an endpoint.
+    pub fn get(&self, endpoint: &str) -> Option<RateLimit> {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(endpoint)
+            .cloned()
+    }
+
+    /// Gets the last known rate limit of every endpoint called.
+    pub fn all(&self) -> HashMap<String, RateLimit> {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Overwrites the known rate limit of an endpoint.
+    pub fn set(&self, endpoint: &str, limit: RateLimit) {
+        self.0
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(endpoint.to_owned(), limit);
+    }
+
+    /// How long to wait before calling an endpoint, if its known limit is exhausted.
+    pub fn wait_time(&self, endpoint: &str) -> Option<Duration> {
+        self.get(endpoint)?.wait_time(time::get_time())
+    }
+}
+
+/// A function used to delay calls until rate limits reset.
+///
+/// This library doesn't depend on any particular async runtime, so the sleep function is provided
+/// by the user, for instance `Throttle::new(tokio::time::sleep)`.
+#[derive(Clone)]
+pub struct Throttle(Arc<dyn Fn(Duration) -> BoxFuture<'static, ()> + Send + Sync>);
+
+impl Throttle {
+    /// Creates a throttle which waits using the given sleep function.
+    pub fn new<F, T>(sleep: F) -> Self
+    where
+        F: Fn(Duration) -> T + Send + Sync + 'static,
+        T: Future<Output = ()> + Send + 'static,
+    {
+        Throttle(Arc::new(move |duration| sleep(duration).boxed()))
+    }
+
+    pub(crate) fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        (self.0)(duration)
+    }
+}
+
+impl fmt::Debug for Throttle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Throttle").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::header::HeaderValue;
+
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for &(name, value) in pairs {
+            map.insert(name, HeaderValue::from_static(value));
+        }
+        map
+    }
+
+    #[test]
+    fn parse_rate_limit_headers() {
+        let limit = RateLimit::from_headers(&headers(&[
+            ("X-RateLimit-Limit", "360"),
+            ("X-RateLimit-Remaining", "0"),
+            ("X-RateLimit-Reset", "1600000060"),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            limit,
+            RateLimit {
+                limit: 360,
+                remaining: 0,
+                reset_at: Timespec::new(1600000060, 0),
+            }
+        );
+        assert_eq!(
+            limit.wait_time(Timespec::new(1600000000, 0)),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(limit.wait_time(Timespec::new(1600000061, 0)), None);
+        assert_eq!(
+            RateLimit::from_headers(&headers(&[("X-RateLimit-Limit", "360")])),
+            None
+        );
+    }
+
+    #[test]
+    fn negative_rate_limit_headers_are_ignored() {
+        assert_eq!(
+            RateLimit::from_headers(&headers(&[
+                ("X-RateLimit-Limit", "360"),
+                ("X-RateLimit-Remaining", "-1"),
+                ("X-RateLimit-Reset", "1600000060"),
+            ])),
+            None
+        );
+        assert_eq!(
+            RateLimit::from_headers(&headers(&[
+                ("X-RateLimit-Limit", "-360"),
+                ("X-RateLimit-Remaining", "10"),
+                ("X-RateLimit-Reset", "1600000060"),
+            ])),
+            None
+        );
+    }
+
+    #[test]
+    fn rate_limited_until_prefers_retry_after() {
+        let now = Timespec::new(1600000000, 0);
+        let reset = [
+            ("X-RateLimit-Limit", "360"),
+            ("X-RateLimit-Remaining", "0"),
+            ("X-RateLimit-Reset", "1600000060"),
+        ];
+
+        assert_eq!(
+            rate_limited_until(&headers(&reset), now),
+            Some(Timespec::new(1600000060, 0))
+        );
+        let mut with_retry = headers(&reset);
+        with_retry.insert(RETRY_AFTER, HeaderValue::from_static("5"));
+        assert_eq!(
+            rate_limited_until(&with_retry, now),
+            Some(Timespec::new(1600000005, 0))
+        );
+        assert_eq!(rate_limited_until(&HeaderMap::new(), now), None);
+    }
+
+    #[test]
+    fn storage_is_shared_between_clones() {
+        let storage = RateLimitStorage::default();
+        let clone = storage.clone();
+        let limit = RateLimit {
+            limit: 60,
+            remaining: 59,
+            reset_at: Timespec::new(0, 0),
+        };
+        clone.set("game/room-terrain", limit);
+
+        assert_eq!(storage.get("game/room-terrain"), Some(limit));
+        assert_eq!(storage.wait_time("game/room-terrain"), None);
+        assert_eq!(storage.all().len(), 1);
+    }
+}
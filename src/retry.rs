@@ -0,0 +1,234 @@
+//! Retrying calls which fail with transient errors.
+use std::{fmt, sync::Arc, time::Duration};
+
+use futures::future::{BoxFuture, Future};
+use rand::Rng;
+
+use crate::{error::ErrorKind, Error, Throttle};
+
+type Classifier = Arc<dyn Fn(&Error) -> bool + Send + Sync>;
+
+/// Policy for retrying calls which fail with transient errors, such as connection resets, `502`
+/// and `503` responses, or `ApiError::ServerDown`.
+///
+/// Each retry waits exponentially longer than the last, with random jitter so that many clients
+/// don't retry at the same time. Calls which modify game state, such as
+/// [`Api::set_memory_segment`] and [`Api::register`], are only retried if `retry_mutating` is set,
+/// since a failed response does not guarantee the change wasn't made.
+///
+/// [`Api::set_memory_segment`]: struct.Api.html#method.set_memory_segment
+/// [`Api::register`]: struct.Api.html#method.register
+#[derive(Clone)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts made for each call, including the first.
+    pub max_attempts: u32,
+    /// How long to wait before the first retry.
+    pub initial_backoff: Duration,
+    /// The longest to wait before any retry. Rate limited calls which would need to wait longer
+    /// are not retried.
+    pub max_backoff: Duration,
+    /// How much longer to wait before each retry than the one before.
+    pub multiplier: f64,
+    /// Whether to wait a random amount of between half and all of each backoff.
+    pub jitter: bool,
+    /// Whether calls which modify game state are retried.
+    pub retry_mutating: bool,
+    is_retryable: Option<Classifier>,
+    sleep: Throttle,
+}
+
+impl RetryPolicy {
+    /// Creates a policy with the default settings, waiting between attempts using the given sleep
+    /// function, for instance `RetryPolicy::new(tokio::time::sleep)`.
+    ///
+    /// By default, calls are attempted up to 3 times, waiting 500 milliseconds before the first
+    /// retry and doubling each time up to 30 seconds, with jitter. Calls which modify game state
+    /// are not retried.
+    pub fn new<F, T>(sleep: F) -> Self
+    where
+        F: Fn(Duration) -> T + Send + Sync + 'static,
+        T: Future<Output = ()> + Send + 'static,
+    {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: true,
+            retry_mutating: false,
+            is_retryable: None,
+            sleep: Throttle::new(sleep),
+        }
+    }
+
+    /// Sets which errors are retried, replacing [`Error::is_transient`].
+    ///
+    /// [`Error::is_transient`]: error/struct.Error.html#method.is_transient
+    pub fn retry_if<F>(mut self, is_retryable: F) -> Self
+    where
+        F: Fn(&Error) -> bool + Send + Sync + 'static,
+    {
+        self.is_retryable = Some(Arc::new(is_retryable));
+        self
+    }
+
+    /// How long to wait after the given failed attempt, counting from 1, before trying again.
+    /// Returns `None` if the call should not be retried.
+    pub fn retry_delay(&self, attempt: u32, mutating: bool, err: &Error) -> Option<Duration> {
+        if attempt >= self.max_attempts || (mutating && !self.retry_mutating) {
+            return None;
+        }
+        let retryable = match self.is_retryable {
+            Some(ref is_retryable) => is_retryable(err),
+            None => err.is_transient(),
+        };
+        if !retryable {
+            return None;
+        }
+
+        let exponent = attempt.saturating_sub(1).min(64) as i32;
+        let backoff = (self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent))
+            .min(self.max_backoff.as_secs_f64());
+        let backoff = if self.jitter {
+            backoff * rand::thread_rng().gen_range(0.5..=1.0)
+        } else {
+            backoff
+        };
+        let backoff = Duration::from_secs_f64(backoff.max(0.0));
+
+        match *err.kind() {
+            ErrorKind::RateLimited {
+                reset_at: Some(reset_at),
+            } => {
+                let now = time::get_time();
+                let until_reset = if reset_at > now {
+                    (reset_at - now).to_std().unwrap_or_default()
+                } else {
+                    Duration::default()
+                };
+                if until_reset > self.max_backoff {
+                    None
+                } else {
+                    Some(backoff.max(until_reset))
+                }
+            }
+            _ => Some(backoff),
+        }
+    }
+
+    pub(crate) fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        self.sleep.sleep(duration)
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("multiplier", &self.multiplier)
+            .field("jitter", &self.jitter)
+            .field("retry_mutating", &self.retry_mutating)
+            .field("custom_classification", &self.is_retryable.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ApiError;
+
+    fn policy() -> RetryPolicy {
+        let mut policy = RetryPolicy::new(|_| futures::future::ready(()));
+        policy.jitter = false;
+        policy.max_attempts = 4;
+        policy.max_backoff = Duration::from_secs(1);
+        policy
+    }
+
+    #[test]
+    fn backs_off_exponentially_until_max_attempts() {
+        let policy = policy();
+        let err = Error::from(hyper::StatusCode::SERVICE_UNAVAILABLE);
+
+        assert_eq!(
+            policy.retry_delay(1, false, &err),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            policy.retry_delay(2, false, &err),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            policy.retry_delay(3, false, &err),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(policy.retry_delay(4, false, &err), None);
+    }
+
+    #[test]
+    fn classifies_errors() {
+        let policy = policy();
+
+        assert!(policy
+            .retry_delay(1, false, &ApiError::ServerDown.into())
+            .is_some());
+        assert!(policy
+            .retry_delay(1, false, &hyper::StatusCode::BAD_GATEWAY.into())
+            .is_some());
+        assert!(policy
+            .retry_delay(1, false, &ApiError::InvalidRoom.into())
+            .is_none());
+        assert!(policy
+            .retry_delay(1, false, &hyper::StatusCode::UNAUTHORIZED.into())
+            .is_none());
+
+        let custom = policy.retry_if(|err| matches!(err.kind(), ErrorKind::Unauthorized));
+        assert!(custom
+            .retry_delay(1, false, &hyper::StatusCode::UNAUTHORIZED.into())
+            .is_some());
+        assert!(custom
+            .retry_delay(1, false, &ApiError::ServerDown.into())
+            .is_none());
+    }
+
+    #[test]
+    fn mutating_calls_are_opt_in() {
+        let mut policy = policy();
+        let err = Error::from(ApiError::ServerDown);
+
+        assert_eq!(policy.retry_delay(1, true, &err), None);
+        policy.retry_mutating = true;
+        assert!(policy.retry_delay(1, true, &err).is_some());
+    }
+
+    #[test]
+    fn rate_limits_wait_for_reset() {
+        let policy = policy();
+        let now = time::get_time();
+        let soon = Error::from(ErrorKind::RateLimited {
+            reset_at: Some(time::Timespec::new(now.sec + 1, now.nsec)),
+        });
+        let tomorrow = Error::from(ErrorKind::RateLimited {
+            reset_at: Some(time::Timespec::new(now.sec + 86400, now.nsec)),
+        });
+
+        let delay = policy.retry_delay(1, false, &soon).unwrap();
+        assert!(delay > Duration::from_millis(500) && delay <= Duration::from_secs(1));
+        assert_eq!(policy.retry_delay(1, false, &tomorrow), None);
+    }
+
+    #[test]
+    fn jitter_stays_within_backoff() {
+        let mut policy = policy();
+        policy.jitter = true;
+        let err = Error::from(ApiError::ServerDown);
+
+        for _ in 0..20 {
+            let delay = policy.retry_delay(1, false, &err).unwrap();
+            assert!(delay >= Duration::from_millis(250) && delay <= Duration::from_millis(500));
+        }
+    }
+}
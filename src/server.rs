@@ -0,0 +1,485 @@
+//! Server profiles: the HTTP and websocket urls of official and private servers.
+use std::{fmt, future::Future};
+
+use url::Url;
+
+#[cfg(feature = "sync")]
+use crate::sync::{SyncApi, SyncError};
+use crate::{
+    unified_config::{ConfigError, UnifiedConfig},
+    websocket::{transform_url, UrlError},
+    Api, CredentialsProvider, Error, Transport,
+};
+
+/// The host of the official servers.
+const OFFICIAL_HOST: &str = "screeps.com";
+
+/// The port private servers listen on, unless configured otherwise.
+pub const DEFAULT_PRIVATE_SERVER_PORT: u16 = 21025;
+
+/// Description of a screeps server, used to build both its API url and its websocket url.
+///
+/// Start from one of the presets, [`ServerProfile::mmo`], [`ServerProfile::ptr`],
+/// [`ServerProfile::season`] or [`ServerProfile::private`], and adjust it with the `with_*`
+/// methods. Existing API urls can be read with [`ServerProfile::from_url`], and servers configured
+/// in a `.screeps.yaml` file with [`ServerProfile::from_unified_config`].
+///
+/// A profile may also carry an auth token, or a username and password for private servers, which
+/// the clients it creates are set up with.
+///
+/// ```
+/// use screeps_api::ServerProfile;
+///
+/// let ptr = ServerProfile::ptr();
+/// assert_eq!(ptr.api_url().unwrap().as_str(), "https://screeps.com/ptr/api/");
+///
+/// let private = ServerProfile::private("localhost");
+/// assert_eq!(private.api_url().unwrap().as_str(), "http://localhost:21025/api/");
+/// ```
+///
+/// [`ServerProfile::mmo`]: #method.mmo
+/// [`ServerProfile::ptr`]: #method.ptr
+/// [`ServerProfile::season`]: #method.season
+/// [`ServerProfile::private`]: #method.private
+/// [`ServerProfile::from_url`]: #method.from_url
+/// [`ServerProfile::from_unified_config`]: #method.from_unified_config
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ServerProfile {
+    /// Whether to use https and wss rather than http and ws.
+    secure: bool,
+    /// The server's host name or address.
+    host: String,
+    /// The server's port, or `None` for the scheme's default.
+    port: Option<u16>,
+    /// The path before `/api/`, either empty or starting with a slash and without a trailing one.
+    path: String,
+    /// The auth token to use.
+    token: Option<String>,
+    /// The username and password to log in with, if there's no token.
+    login: Option<(String, String)>,
+}
+
+impl ServerProfile {
+    /// Creates a profile for a server on the given host, using https on the default port with no
+    /// path prefix.
+    pub fn new<H: Into<String>>(host: H) -> Self {
+        ServerProfile {
+            secure: true,
+            host: host.into(),
+            port: None,
+            path: String::new(),
+            token: None,
+            login: None,
+        }
+    }
+
+    /// The official MMO server, `https://screeps.com/api/`.
+    pub fn mmo() -> Self {
+        ServerProfile::new(OFFICIAL_HOST)
+    }
+
+    /// The official public test realm, `https://screeps.com/ptr/api/`.
+    pub fn ptr() -> Self {
+        ServerProfile::mmo().with_path("ptr")
+    }
+
+    /// The official seasonal server, `https://screeps.com/season/api/`.
+    pub fn season() -> Self {
+        ServerProfile::mmo().with_path("season")
+    }
+
+    /// A private server on the given host, using http on port 21025.
+    pub fn private<H: Into<String>>(host: H) -> Self {
+        ServerProfile::new(host)
+            .with_secure(false)
+            .with_port(Some(DEFAULT_PRIVATE_SERVER_PORT))
+    }
+
+    /// Reads a profile from an existing API url, such as `"http://localhost:21025/api/"`.
+    ///
+    /// The trailing `api/` may be left out: `"https://screeps.com/ptr"` reads the same as
+    /// `"https://screeps.com/ptr/api/"`.
+    pub fn from_url<U: AsRef<str>>(url: U) -> Result<Self, UrlError> {
+        let parsed = match Url::parse(url.as_ref()) {
+            Ok(v) => v,
+            Err(e) => return Err(UrlError::from_err(e, url.as_ref().to_owned())),
+        };
+
+        let secure = match parsed.scheme() {
+            "http" => false,
+            "https" => true,
+            other => return Err(UrlError::wrong_scheme(other.to_owned(), parsed)),
+        };
+        let host = match parsed.host_str() {
+            Some(v) => v.to_owned(),
+            None => {
+                return Err(UrlError::from_err(
+                    url::ParseError::EmptyHost,
+                    url.as_ref().to_owned(),
+                ))
+            }
+        };
+
+        Ok(ServerProfile {
+            secure,
+            host,
+            port: parsed.port(),
+            path: String::new(),
+            token: None,
+            login: None,
+        }
+        .with_path(parsed.path()))
+    }
+
+    /// Sets whether the server uses https and wss, or plain http and ws.
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Sets the server's host name or address.
+    pub fn with_host<H: Into<String>>(mut self, host: H) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Sets the server's port, or `None` to use the default port for http or https.
+    pub fn with_port(mut self, port: Option<u16>) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Sets the path the server's API is under, such as `"ptr"` or `"/season/"`.
+    ///
+    /// Leading and trailing slashes are ignored, as is a trailing `api` segment, so `"/ptr/api/"`
+    /// is the same as `"ptr"`. Use an empty path for servers serving the API at `/api/`.
+    pub fn with_path<P: AsRef<str>>(mut self, path: P) -> Self {
+        let path = path.as_ref().trim_matches('/');
+        let path = if path == "api" {
+            ""
+        } else {
+            path.strip_suffix("/api").unwrap_or(path)
+        };
+
+        self.path = if path.is_empty() {
+            String::new()
+        } else {
+            format!("/{}", path)
+        };
+        self
+    }
+
+    /// Sets the auth token clients for this server use.
+    ///
+    /// See [the screeps docs page](https://docs.screeps.com/auth-tokens.html) for information on tokens.
+    pub fn with_token<T: Into<String>>(mut self, token: T) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Sets the username and password to log in with, for private servers with the
+    /// [screepsmod-auth] mod. They are used to log in if no token is set, and to log in again
+    /// whenever the token expires.
+    ///
+    /// [screepsmod-auth]: https://github.com/ScreepsMods/screepsmod-auth
+    pub fn with_login<U, P>(mut self, username: U, password: P) -> Self
+    where
+        U: Into<String>,
+        P: Into<String>,
+    {
+        self.login = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Whether the server uses https and wss.
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+
+    /// The server's host name or address.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// The server's port, or `None` if it uses the default port for its scheme.
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// The path the server's API is under: empty, or a path such as `"/ptr"`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The auth token clients for this server use, if set.
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// The username to log in with, if set.
+    pub fn username(&self) -> Option<&str> {
+        self.login.as_ref().map(|(username, _)| &**username)
+    }
+
+    /// Builds the server's API url, such as `"https://screeps.com/ptr/api/"`.
+    pub fn api_url(&self) -> Result<Url, url::ParseError> {
+        let scheme = if self.secure { "https" } else { "http" };
+        let port = match self.port {
+            Some(port) => format!(":{}", port),
+            None => String::new(),
+        };
+
+        Url::parse(&format!(
+            "{}://{}{}{}/api/",
+            scheme, self.host, port, self.path
+        ))
+    }
+
+    /// Builds a websocket url for the server, such as
+    /// `"wss://screeps.com/ptr/socket/123/abcdefgh/websocket"`.
+    ///
+    /// Like [`websocket::transform_url`], this generates a new session id each call, and the
+    /// result should not be reused over multiple connections.
+    ///
+    /// [`websocket::transform_url`]: websocket/fn.transform_url.html
+    pub fn websocket_url(&self) -> Result<Url, url::ParseError> {
+        let api_url = self.api_url()?;
+
+        Ok(transform_url(&api_url)
+            .expect("expected api url with an http or https scheme to transform successfully"))
+    }
+
+    /// Reads the server with the given name from the `.screeps.yaml` unified config file, found
+    /// in the standard locations.
+    ///
+    /// See [`UnifiedConfig::load`] for where the file is looked for.
+    ///
+    /// [`UnifiedConfig::load`]: struct.UnifiedConfig.html#method.load
+    pub fn from_unified_config(name: &str) -> Result<Self, ConfigError> {
+        UnifiedConfig::load()?.profile(name)
+    }
+
+    /// Creates an API client for this server using the given transport, with this profile's token
+    /// set.
+    ///
+    /// The url is not checked until a call is made, and the username and password are only used
+    /// to log in again when a token expires: use [`ServerProfile::validated_api`] to check the url
+    /// and log in up front.
+    ///
+    /// [`ServerProfile::validated_api`]: #method.validated_api
+    pub fn api<C>(&self, client: C) -> Result<Api<C>, url::ParseError> {
+        let mut api = Api::new(client).with_url(self.api_url()?)?;
+        self.configure(&mut api);
+        Ok(api)
+    }
+
+    /// Sets a client's token, and its credentials provider if this profile has a username and
+    /// password.
+    fn configure<C>(&self, api: &mut Api<C>) {
+        if let Some(ref token) = self.token {
+            api.set_token(token.clone());
+        }
+        if let Some((ref username, ref password)) = self.login {
+            api.set_credentials_provider(Some(CredentialsProvider::fixed(
+                &**username,
+                &**password,
+            )));
+        }
+    }
+
+    /// Creates an API client for this server, checks that the server responds to `api/version`,
+    /// and logs in if this profile has a username and password but no token.
+    ///
+    /// See also [`Api::check_server`].
+    ///
+    /// [`Api::check_server`]: struct.Api.html#method.check_server
+    pub fn validated_api<C: Transport>(
+        &self,
+        client: C,
+    ) -> impl Future<Output = Result<Api<C>, Error>> {
+        let api = self.api(client);
+        let login = match self.token {
+            Some(_) => None,
+            None => self.login.clone(),
+        };
+
+        async move {
+            let api = api?;
+            api.check_server().await?;
+            if let Some((username, password)) = login {
+                api.login(username, password)
+                    .await?
+                    .return_to(api.token_storage());
+            }
+            Ok(api)
+        }
+    }
+
+    /// Creates a sync API client for this server using an Https connector, with this profile's
+    /// token set.
+    ///
+    /// Use [`SyncApi::check_server`] to check the url, or [`ServerProfile::validated_sync_api`]
+    /// to also log in.
+    ///
+    /// [`SyncApi::check_server`]: sync/struct.SyncApi.html#method.check_server
+    /// [`ServerProfile::validated_sync_api`]: #method.validated_sync_api
+    #[cfg(feature = "sync")]
+    pub fn sync_api(&self) -> Result<SyncApi, SyncError> {
+        let mut api = SyncApi::new()?.with_url(self.api_url()?)?;
+        self.configure(&mut api);
+        Ok(api)
+    }
+
+    /// Creates a sync API client for this server, checks the url and logs in if needed.
+    ///
+    /// See [`ServerProfile::validated_api`] for more information.
+    ///
+    /// [`ServerProfile::validated_api`]: #method.validated_api
+    #[cfg(feature = "sync")]
+    pub fn validated_sync_api(&self) -> Result<SyncApi, Error> {
+        let mut api = SyncApi::new_with_connector(hyper_tls::HttpsConnector::new())?
+            .with_url(self.api_url()?)?;
+        self.configure(&mut api);
+        api.check_server()?;
+        if let (None, Some((username, password))) = (&self.token, &self.login) {
+            api.login(&**username, &**password)?;
+        }
+        Ok(api)
+    }
+}
+
+impl fmt::Debug for ServerProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // credentials are left out, so profiles can be logged.
+        f.debug_struct("ServerProfile")
+            .field("secure", &self.secure)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("path", &self.path)
+            .field("token", &self.token.as_ref().map(|_| ".."))
+            .field("username", &self.username())
+            .finish()
+    }
+}
+
+impl Default for ServerProfile {
+    /// The official MMO server.
+    fn default() -> Self {
+        ServerProfile::mmo()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ServerProfile;
+
+    #[test]
+    fn presets() {
+        let urls = [
+            (ServerProfile::mmo(), "https://screeps.com/api/"),
+            (ServerProfile::ptr(), "https://screeps.com/ptr/api/"),
+            (ServerProfile::season(), "https://screeps.com/season/api/"),
+            (
+                ServerProfile::private("localhost"),
+                "http://localhost:21025/api/",
+            ),
+            (
+                ServerProfile::private("example.com")
+                    .with_secure(true)
+                    .with_port(None)
+                    .with_path("/custom/"),
+                "https://example.com/custom/api/",
+            ),
+        ];
+        for (profile, url) in &urls {
+            assert_eq!(profile.api_url().unwrap().as_str(), *url);
+            assert_eq!(ServerProfile::from_url(url).unwrap(), *profile);
+        }
+        assert_eq!(
+            ServerProfile::mmo().api_url().unwrap().as_str(),
+            crate::DEFAULT_OFFICIAL_API_URL
+        );
+    }
+
+    #[test]
+    fn normalizes_paths() {
+        for path in &["ptr", "/ptr", "ptr/", "/ptr/api/", "ptr/api"] {
+            assert_eq!(ServerProfile::mmo().with_path(path), ServerProfile::ptr());
+        }
+        for path in &["", "/", "api", "/api/"] {
+            assert_eq!(ServerProfile::ptr().with_path(path), ServerProfile::mmo());
+        }
+        assert_eq!(
+            ServerProfile::from_url("https://screeps.com/ptr").unwrap(),
+            ServerProfile::ptr()
+        );
+        assert_eq!(
+            ServerProfile::from_url("http://localhost:21025").unwrap(),
+            ServerProfile::private("localhost")
+        );
+        assert!(ServerProfile::from_url("ws://localhost:21025/").is_err());
+        assert!(ServerProfile::from_url("localhost:21025").is_err());
+    }
+
+    #[test]
+    fn websocket_urls_match_api_urls() {
+        let url = ServerProfile::season().websocket_url().unwrap();
+        assert_eq!(url.scheme(), "wss");
+        assert!(url.path().starts_with("/season/socket/"), "{}", url);
+        assert!(url.path().ends_with("/websocket"), "{}", url);
+
+        let url = ServerProfile::private("127.0.0.1").websocket_url().unwrap();
+        assert_eq!(url.scheme(), "ws");
+        assert_eq!(url.port(), Some(21025));
+        assert!(url.path().starts_with("/socket/"), "{}", url);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn validates_against_server() {
+        use hyper::client::{Client, HttpConnector};
+
+        use crate::testing::MockServer;
+
+        let server = MockServer::start().unwrap();
+        let profile = ServerProfile::private("127.0.0.1").with_port(Some(server.address().port()));
+        let client = Client::builder().build(HttpConnector::new());
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let api = runtime
+            .block_on(profile.validated_api(client.clone()))
+            .unwrap();
+        assert_eq!(api.url, profile.api_url().unwrap());
+        assert!(runtime
+            .block_on(
+                profile
+                    .clone()
+                    .with_path("ptr")
+                    .validated_api(api.client.clone())
+            )
+            .is_err());
+
+        server.world().add_user("tester", "password");
+        let api = runtime
+            .block_on(
+                profile
+                    .clone()
+                    .with_login("tester", "password")
+                    .validated_api(client),
+            )
+            .unwrap();
+        runtime.block_on(api.my_info().unwrap()).unwrap();
+        // expire the session: the old token stops working, and logging in gives the new one.
+        server.world().user_mut("tester").unwrap().token = "renewed".to_owned();
+        let info = runtime.block_on(api.my_info().unwrap()).unwrap();
+        assert_eq!(info.username, "tester");
+        assert_eq!(api.token_storage().get().as_deref(), Some(&b"renewed"[..]));
+
+        server
+            .world()
+            .set_response("version", json!({ "error": "not a server" }));
+        assert!(runtime
+            .block_on(profile.validated_api(api.client.clone()))
+            .is_err());
+    }
+}
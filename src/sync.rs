@@ -1,6 +1,7 @@
 //! Small wrapper around the asynchronous Api struct providing synchronous access methods.
 use std::{
     borrow::Cow,
+    collections::HashMap,
     io,
     ops::{Deref, DerefMut},
 };
@@ -10,9 +11,10 @@ use hyper::{self, client::Client};
 use hyper_tls::HttpsConnector;
 
 use crate::{
-    error::Error, Api, FoundUserRank, LeaderboardPage, LeaderboardSeason, LeaderboardType,
-    MapStats, MyInfo, RecentPvp, RecentPvpArgs, RegistrationArgs, RegistrationSuccess,
-    RoomOverview, RoomStatus, RoomTerrain, ShardInfo, Token, WorldStartRoom,
+    error::Error, websocket::objects::PowerType, Api, AuthType, FoundUserRank, LeaderboardPage,
+    LeaderboardSeason, LeaderboardType, MapStats, MyInfo, PowerCreepInfo, RecentPvp, RecentPvpArgs,
+    RegistrationArgs, RegistrationSuccess, RetryPolicy, RoomHistory, RoomOverview, RoomStatus,
+    RoomTerrain, ServerVersion, ShardInfo, Throttle, Token, Transport, WorldStartRoom,
 };
 
 type TokioRuntime = tokio::runtime::Runtime;
@@ -66,17 +68,17 @@ pub use self::error::SyncError;
 
 /// API structure mirroring [`Api`], but providing utilities for synchronous connection.
 ///
-/// This structure owns both the hyper client and the tokio runtime. If this is not wanted, please
-/// use [`Api`] instead.
+/// This structure owns both the transport, usually a hyper client, and the tokio runtime. If this
+/// is not wanted, please use [`Api`] instead.
 ///
 /// [`Api`]: ../struct.Api.html
 #[derive(Debug)]
-pub struct SyncApi<C = HttpsConnector<HttpConnector>> {
+pub struct SyncApi<C = Client<HttpsConnector<HttpConnector>>> {
     runtime: TokioRuntime,
     client: Api<C>,
 }
 
-impl SyncApi<HttpsConnector<HttpConnector>> {
+impl SyncApi<Client<HttpsConnector<HttpConnector>>> {
     /// Creates a sync API client using an Https connector.
     ///
     /// Use [`SyncApi::new_with_connector`] to set another backend, such as an HTTP only backend.
@@ -85,17 +87,29 @@ impl SyncApi<HttpsConnector<HttpConnector>> {
     }
 }
 
-impl<C> SyncApi<C>
+impl<C> SyncApi<Client<C>>
 where
     C: hyper::client::connect::Connect + Clone + Send + Sync + 'static,
 {
     /// Creates a sync API client using a custom connector.
     pub fn new_with_connector(connector: C) -> Result<Self, io::Error> {
+        Self::new_with_transport(Client::builder().build(connector))
+    }
+}
+
+impl<C> SyncApi<C>
+where
+    C: Transport,
+{
+    /// Creates a sync API client using a custom transport, such as a
+    /// [`transport::ReplayTransport`] for offline tests.
+    ///
+    /// [`transport::ReplayTransport`]: ../transport/struct.ReplayTransport.html
+    pub fn new_with_transport(transport: C) -> Result<Self, io::Error> {
         let runtime = TokioRuntime::new()?;
-        let hyper = Client::builder().build(connector);
         Ok(SyncApi {
             runtime,
-            client: Api::new(hyper),
+            client: Api::new(transport),
         })
     }
 }
@@ -116,7 +130,7 @@ impl<C> DerefMut for SyncApi<C> {
 
 impl<C> SyncApi<C>
 where
-    C: hyper::client::connect::Connect + Clone + Send + Sync + 'static,
+    C: Transport,
 {
     /// Sets the server url this api client will use, and returns the client.
     ///
@@ -138,6 +152,28 @@ where
         self
     }
 
+    /// Enables throttling: calls to an endpoint with no calls left wait until its rate limit
+    /// resets, rather than failing with [`ErrorKind::RateLimited`].
+    ///
+    /// See also [`Api::set_throttle`].
+    ///
+    /// [`ErrorKind::RateLimited`]: ../enum.ErrorKind.html#variant.RateLimited
+    #[inline]
+    pub fn enable_throttle(&mut self) {
+        self.set_throttle(Some(Throttle::new(tokio::time::sleep)));
+    }
+
+    /// Enables retrying calls which fail with transient errors, using the default
+    /// [`RetryPolicy`].
+    ///
+    /// See also [`Api::set_retry_policy`].
+    ///
+    /// [`RetryPolicy`]: ../struct.RetryPolicy.html
+    #[inline]
+    pub fn enable_retries(&mut self) {
+        self.set_retry_policy(Some(RetryPolicy::new(tokio::time::sleep)));
+    }
+
     /// Logs in with the given username and password and stores the authenticated token in self.
     ///
     /// *Note:* since [the official server implemented auth tokens][blog], this method has only
@@ -243,6 +279,23 @@ where
             .block_on(self.client.room_terrain(shard, room_name))
     }
 
+    /// Gets one chunk of a room's history, starting at the given tick.
+    ///
+    /// See [`Api::room_history`](../struct.Api.html#method.room_history) for more information.
+    pub fn room_history<'b, U, V>(
+        &mut self,
+        room_name: U,
+        shard: Option<V>,
+        tick: u32,
+    ) -> Result<RoomHistory, Error>
+    where
+        U: Into<Cow<'b, str>>,
+        V: Into<Cow<'b, str>>,
+    {
+        self.runtime
+            .block_on(self.client.room_history(room_name, shard, tick))
+    }
+
     /// Gets a list of shards available on this server. Errors with a `404` error when connected to a
     /// non-sharded server.
     ///
@@ -251,6 +304,42 @@ where
         self.runtime.block_on(self.client.shard_list())
     }
 
+    /// Checks that this client's url points at a screeps server.
+    ///
+    /// See [`Api::check_server`](../struct.Api.html#method.check_server) for more information.
+    pub fn check_server(&mut self) -> Result<(), Error> {
+        self.runtime.block_on(self.client.check_server())
+    }
+
+    /// Gets information the server reports about itself.
+    ///
+    /// See [`Api::version`](../struct.Api.html#method.version) for more information.
+    pub fn version(&mut self) -> Result<ServerVersion, Error> {
+        self.runtime.block_on(self.client.version())
+    }
+
+    /// Checks whether the server is sharded.
+    ///
+    /// See [`Api::supports_shards`](../struct.Api.html#method.supports_shards) for more information.
+    pub fn supports_shards(&mut self) -> Result<bool, Error> {
+        self.runtime.block_on(self.client.supports_shards())
+    }
+
+    /// Checks how the server expects clients to authenticate.
+    ///
+    /// See [`Api::auth_type`](../struct.Api.html#method.auth_type) for more information.
+    pub fn auth_type(&mut self) -> Result<AuthType, Error> {
+        self.runtime.block_on(self.client.auth_type())
+    }
+
+    /// Gets the protocol version the server's websocket will speak.
+    ///
+    /// See [`Api::protocol_version`](../struct.Api.html#method.protocol_version) for more
+    /// information.
+    pub fn protocol_version(&mut self) -> Result<u32, Error> {
+        self.runtime.block_on(self.client.protocol_version())
+    }
+
     /// Gets the "status" of a room: if it is open, if it is in a novice area, if it exists.
     ///
     /// See [`Api::room_status`](../struct.Api.html#method.room_status) for more information.
@@ -360,4 +449,87 @@ where
         self.runtime
             .block_on(self.client.set_memory_segment(shard, segment, data)?)
     }
+
+    /// Gets a list of all of the logged in user's power creeps.
+    ///
+    /// See [`Api::power_creeps`](../struct.Api.html#method.power_creeps) for more information.
+    pub fn power_creeps(&mut self) -> Result<Vec<PowerCreepInfo>, Error> {
+        self.runtime.block_on(self.client.power_creeps()?)
+    }
+
+    /// Creates a new power creep with the given name and class.
+    ///
+    /// See [`Api::create_power_creep`](../struct.Api.html#method.create_power_creep) for more information.
+    pub fn create_power_creep<'b, U, V>(
+        &mut self,
+        name: U,
+        class_name: V,
+    ) -> Result<PowerCreepInfo, Error>
+    where
+        U: Into<Cow<'b, str>>,
+        V: Into<Cow<'b, str>>,
+    {
+        self.runtime
+            .block_on(self.client.create_power_creep(name, class_name)?)
+    }
+
+    /// Renames a power creep.
+    ///
+    /// See [`Api::rename_power_creep`](../struct.Api.html#method.rename_power_creep) for more information.
+    pub fn rename_power_creep<'b, U, V>(&mut self, id: U, name: V) -> Result<(), Error>
+    where
+        U: Into<Cow<'b, str>>,
+        V: Into<Cow<'b, str>>,
+    {
+        self.runtime
+            .block_on(self.client.rename_power_creep(id, name)?)
+    }
+
+    /// Marks a power creep for deletion.
+    ///
+    /// See [`Api::delete_power_creep`](../struct.Api.html#method.delete_power_creep) for more information.
+    pub fn delete_power_creep<'b, U>(&mut self, id: U) -> Result<(), Error>
+    where
+        U: Into<Cow<'b, str>>,
+    {
+        self.runtime.block_on(self.client.delete_power_creep(id)?)
+    }
+
+    /// Cancels a pending deletion of a power creep.
+    ///
+    /// See [`Api::cancel_delete_power_creep`] for more information.
+    ///
+    /// [`Api::cancel_delete_power_creep`]: ../struct.Api.html#method.cancel_delete_power_creep
+    pub fn cancel_delete_power_creep<'b, U>(&mut self, id: U) -> Result<(), Error>
+    where
+        U: Into<Cow<'b, str>>,
+    {
+        self.runtime
+            .block_on(self.client.cancel_delete_power_creep(id)?)
+    }
+
+    /// Upgrades a power creep's powers to the given levels.
+    ///
+    /// See [`Api::upgrade_power_creep`](../struct.Api.html#method.upgrade_power_creep) for more information.
+    pub fn upgrade_power_creep<'b, U>(
+        &mut self,
+        id: U,
+        powers: HashMap<PowerType, u32>,
+    ) -> Result<(), Error>
+    where
+        U: Into<Cow<'b, str>>,
+    {
+        self.runtime
+            .block_on(self.client.upgrade_power_creep(id, powers)?)
+    }
+
+    /// Starts a power creep experimentation period.
+    ///
+    /// See [`Api::power_creep_experimentation`] for more information.
+    ///
+    /// [`Api::power_creep_experimentation`]: ../struct.Api.html#method.power_creep_experimentation
+    pub fn power_creep_experimentation(&mut self) -> Result<(), Error> {
+        self.runtime
+            .block_on(self.client.power_creep_experimentation()?)
+    }
 }
@@ -0,0 +1,644 @@
+//! The mock server's HTTP endpoints.
+use std::collections::HashMap;
+
+use hyper::{header::CONTENT_TYPE, Body, Method, Request, Response, StatusCode};
+use serde_json::{json, Value};
+
+use super::{MockUser, Shared, World};
+use crate::LeaderboardType;
+
+/// The number of ticks in each room history chunk, as reported by `version`.
+const HISTORY_CHUNK_SIZE: u32 = 20;
+
+/// A request, read fully and split up for routing.
+struct MockRequest {
+    method: Method,
+    endpoint: String,
+    query: HashMap<String, String>,
+    token: Option<Vec<u8>>,
+    body: Value,
+}
+
+impl MockRequest {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.query.get(name).map(|v| &**v)
+    }
+
+    fn field(&self, name: &str) -> Option<&str> {
+        self.body.get(name).and_then(Value::as_str)
+    }
+}
+
+/// Answers a single HTTP request from the world state.
+pub(super) async fn handle(shared: &Shared, request: Request<Body>) -> Response<Body> {
+    let (parts, body) = request.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("mock server failed to read request body: {}", e);
+            return status(StatusCode::BAD_REQUEST);
+        }
+    };
+
+    let path = parts.uri.path();
+    // endpoints are named as they are passed to `Api`: relative to the API url.
+    let endpoint = match path.strip_prefix("/api/") {
+        Some(endpoint) => endpoint.to_owned(),
+        None => format!("..{}", path),
+    };
+    let query = url::form_urlencoded::parse(parts.uri.query().unwrap_or("").as_bytes())
+        .into_owned()
+        .collect();
+    let token = parts
+        .headers
+        .get("X-Token")
+        .map(|v| v.as_bytes().to_owned());
+    let body = if body.is_empty() {
+        Value::Null
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(v) => v,
+            Err(_) => return error("invalid params"),
+        }
+    };
+
+    let request = MockRequest {
+        method: parts.method,
+        endpoint,
+        query,
+        token,
+        body,
+    };
+    debug!(
+        "mock server answering {} {}",
+        request.method, request.endpoint
+    );
+
+    let mut world = shared.world();
+    route(&mut world, &request)
+}
+
+fn route(world: &mut World, request: &MockRequest) -> Response<Body> {
+    if let Some(response) = world.response(&request.endpoint) {
+        return json(response.clone());
+    }
+
+    let authenticated = request
+        .token
+        .as_ref()
+        .and_then(|token| world.user_by_token(token))
+        .is_some();
+
+    match (&request.method, &*request.endpoint) {
+        (&Method::POST, "auth/signin") => login(world, request),
+        (&Method::POST, "register/submit") => register(world, request),
+        (&Method::GET, "game/room-terrain") => room_terrain(world, request),
+        (&Method::GET, "game/shards/info") => shard_list(world),
+        (&Method::GET, "version") => version(world),
+        (&Method::GET, "experimental/pvp") => recent_pvp(world, request),
+        (&Method::GET, endpoint) if endpoint.starts_with("../room-history/") => {
+            room_history(world, endpoint)
+        }
+        (&Method::GET, "auth/me")
+        | (&Method::GET, "user/world-start-room")
+        | (&Method::GET, "game/room-status")
+        | (&Method::POST, "game/map-stats")
+        | (&Method::GET, "game/room-overview")
+        | (&Method::GET, "game/power-creeps/list")
+        | (&Method::GET, "leaderboard/seasons")
+        | (&Method::GET, "leaderboard/find")
+        | (&Method::GET, "leaderboard/list")
+        | (&Method::GET, "user/memory-segment")
+        | (&Method::POST, "user/memory-segment")
+            if !authenticated =>
+        {
+            status(StatusCode::UNAUTHORIZED)
+        }
+        (&Method::GET, "auth/me") => my_info(world, request),
+        (&Method::GET, "user/world-start-room") => world_start_room(world, request),
+        (&Method::GET, "game/room-status") => room_status(world, request),
+        (&Method::POST, "game/map-stats") => map_stats(world, request),
+        (&Method::GET, "game/room-overview") => room_overview(world, request),
+        (&Method::GET, "game/power-creeps/list") => power_creeps(world, request),
+        (&Method::GET, "leaderboard/seasons") => leaderboard_seasons(world),
+        (&Method::GET, "leaderboard/find") => leaderboard_find(world, request),
+        (&Method::GET, "leaderboard/list") => leaderboard_list(world, request),
+        (&Method::GET, "user/memory-segment") => memory_segment(world, request),
+        (&Method::POST, "user/memory-segment") => set_memory_segment(world, request),
+        _ => status(StatusCode::NOT_FOUND),
+    }
+}
+
+/// The user authenticated by a request. Only called after checking the token.
+fn user<'a>(world: &'a World, request: &MockRequest) -> &'a MockUser {
+    request
+        .token
+        .as_ref()
+        .and_then(|token| world.user_by_token(token))
+        .expect("expected request to be authenticated")
+}
+
+fn login(world: &World, request: &MockRequest) -> Response<Body> {
+    let (email, password) = match (request.field("email"), request.field("password")) {
+        (Some(email), Some(password)) => (email, password),
+        _ => return error("invalid params"),
+    };
+
+    match world.user_by_login(email, password) {
+        Some(user) => ok(json!({ "token": user.token })),
+        None => status(StatusCode::UNAUTHORIZED),
+    }
+}
+
+fn register(world: &mut World, request: &MockRequest) -> Response<Body> {
+    let (username, password) = match (request.field("username"), request.field("password")) {
+        (Some(username), Some(password)) => (username.to_owned(), password.to_owned()),
+        _ => return error("invalid params"),
+    };
+    let email = request.field("email").map(ToOwned::to_owned);
+
+    match world.register(username, email, password) {
+        Some(_) => ok(json!({})),
+        None => error("User already exists"),
+    }
+}
+
+fn my_info(world: &World, request: &MockRequest) -> Response<Body> {
+    let user = user(world, request);
+    let cpu_shard = if world.shards().is_empty() {
+        Value::Null
+    } else {
+        world
+            .shards()
+            .iter()
+            .map(|shard| (shard.name.clone(), json!(user.cpu)))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    };
+
+    ok(json!({
+        "_id": user.id,
+        "username": user.username,
+        "email": user.email,
+        "password": user.password.is_some(),
+        "cpu": user.cpu,
+        "gcl": user.gcl_points,
+        "power": user.power_points,
+        "money": user.money,
+        "cpuShard": cpu_shard,
+        "cpuShardUpdatedTime": Value::Null,
+    }))
+}
+
+fn world_start_room(world: &World, request: &MockRequest) -> Response<Body> {
+    let user = user(world, request);
+    let room = match (request.param("shard"), &user.start_shard) {
+        (None, Some(shard)) => format!("{}/{}", shard, user.start_room),
+        _ => user.start_room.clone(),
+    };
+
+    ok(json!({ "room": [room] }))
+}
+
+fn room_terrain(world: &World, request: &MockRequest) -> Response<Body> {
+    let room_name = match request.param("room") {
+        Some(v) => v,
+        None => return error("invalid params"),
+    };
+
+    match world.room(request.param("shard"), room_name) {
+        Some(room) => ok(json!({
+            "terrain": [{
+                "_id": room_name,
+                "type": "terrain",
+                "room": room_name,
+                "terrain": room.terrain,
+            }]
+        })),
+        None => error("invalid room"),
+    }
+}
+
+fn room_status(world: &World, request: &MockRequest) -> Response<Body> {
+    let room_name = match request.param("room") {
+        Some(v) => v,
+        None => return error("invalid params"),
+    };
+
+    match world.room(request.param("shard"), room_name) {
+        Some(room) => {
+            let mut inner = json!({ "_id": room_name, "status": room.status });
+            if let Some(novice) = room.novice {
+                inner["novice"] = novice.sec.into();
+            }
+            if let Some(open_time) = room.open_time {
+                inner["openTime"] = open_time.sec.into();
+            }
+            ok(json!({ "room": inner }))
+        }
+        None => ok(json!({ "room": Value::Null })),
+    }
+}
+
+fn shard_list(world: &World) -> Response<Body> {
+    if world.shards().is_empty() {
+        return status(StatusCode::NOT_FOUND);
+    }
+
+    let shards = world
+        .shards()
+        .iter()
+        .map(|shard| {
+            json!({
+                "name": shard.name,
+                "rooms": shard.rooms,
+                "users": shard.users,
+                "tick": shard.tick,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    ok(json!({ "shards": shards }))
+}
+
+/// Reports a private server with screepsmod-auth, sharded if the world has shards.
+fn version(world: &World) -> Response<Body> {
+    let mut server_data = json!({
+        "historyChunkSize": HISTORY_CHUNK_SIZE,
+        "socketUpdateThrottle": 100,
+        "customObjectTypes": {},
+        "features": [{ "name": "screepsmod-auth" }],
+    });
+    if !world.shards().is_empty() {
+        let shards = world
+            .shards()
+            .iter()
+            .map(|shard| shard.name.clone())
+            .collect::<Vec<_>>();
+        server_data["shards"] = shards.into();
+    }
+
+    ok(json!({
+        "protocol": 14,
+        "useNativeAuth": false,
+        "users": world.users().len(),
+        "packageVersion": "mock",
+        "serverData": server_data,
+    }))
+}
+
+/// Reads a shard parameter: empty shard names are used for non-sharded servers.
+fn shard(shard: Option<&str>) -> Option<&str> {
+    shard.filter(|shard| !shard.is_empty())
+}
+
+/// Reports each requested room which exists, leaving out others like the official server does.
+fn map_stats(world: &World, request: &MockRequest) -> Response<Body> {
+    let rooms = match request.body.get("rooms").and_then(Value::as_array) {
+        Some(v) => v,
+        None => return error("invalid params"),
+    };
+    let shard = shard(request.field("shard"));
+
+    let mut stats = serde_json::Map::new();
+    let mut users = serde_json::Map::new();
+    for room_name in rooms.iter().filter_map(Value::as_str) {
+        let room = match world.room(shard, room_name) {
+            Some(v) => v,
+            None => continue,
+        };
+        let mut inner = json!({ "status": room.status });
+        if let Some(novice) = room.novice {
+            inner["novice"] = novice.sec.into();
+        }
+        if let Some(open_time) = room.open_time {
+            inner["openTime"] = open_time.sec.into();
+        }
+        if let Some(owner) = room.owner.as_deref().and_then(|name| world.user(name)) {
+            inner["own"] = json!({ "user": owner.id, "level": room.level });
+            users.insert(
+                owner.id.clone(),
+                json!({ "_id": owner.id, "username": owner.username, "badge": owner.badge }),
+            );
+        }
+        stats.insert(room_name.to_owned(), inner);
+    }
+
+    ok(json!({ "stats": stats, "users": users }))
+}
+
+/// Reports any room's owner, with all statistics at zero.
+fn room_overview(world: &World, request: &MockRequest) -> Response<Body> {
+    let (room_name, interval) = match (request.param("room"), request.param("interval")) {
+        (Some(room_name), Some(interval @ "8"))
+        | (Some(room_name), Some(interval @ "180"))
+        | (Some(room_name), Some(interval @ "1440")) => (room_name, interval),
+        _ => return error("invalid params"),
+    };
+    let owner = world
+        .room(shard(request.param("shard")), room_name)
+        .and_then(|room| room.owner.as_deref())
+        .and_then(|name| world.user(name))
+        .map(|owner| json!({ "username": owner.username, "badge": owner.badge }));
+
+    let stats = [
+        "energyHarvested",
+        "energyConstruction",
+        "energyCreeps",
+        "energyControl",
+        "creepsProduced",
+        "creepsLost",
+    ]
+    .iter()
+    .map(|&name| (name.to_owned(), json!([])))
+    .collect::<serde_json::Map<_, _>>();
+    let stats_max = [
+        "energy",
+        "energyConstruction",
+        "energyControl",
+        "energyCreeps",
+        "creepsProduced",
+        "creepsLost",
+    ]
+    .iter()
+    .flat_map(|&name| {
+        ["8", "180", "1440"]
+            .iter()
+            .map(move |period| (format!("{}{}", name, period), json!(0)))
+    })
+    .collect::<serde_json::Map<_, _>>();
+
+    ok(json!({
+        "owner": owner,
+        "stats": stats,
+        "statsMax": stats_max,
+        "interval": interval,
+    }))
+}
+
+fn power_creeps(world: &World, request: &MockRequest) -> Response<Body> {
+    let user = user(world, request);
+    let list = user
+        .power_creeps
+        .iter()
+        .map(|creep| {
+            let powers = creep
+                .powers
+                .iter()
+                .map(|(power, &level)| (power.id().to_string(), json!({ "level": level })))
+                .collect::<serde_json::Map<_, _>>();
+            json!({
+                "_id": creep.id,
+                "name": creep.name,
+                "className": creep.class,
+                "user": user.id,
+                "level": creep.level,
+                "powers": powers,
+                "shard": creep.shard,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    ok(json!({ "list": list }))
+}
+
+/// Reports the rooms with PvP in the requested period, for each shard. Non-sharded servers
+/// report their rooms as `shard0`.
+fn recent_pvp(world: &World, request: &MockRequest) -> Response<Body> {
+    let parse = |name| request.param(name).and_then(|v| v.parse::<u32>().ok());
+    let since = match (parse("interval"), parse("start")) {
+        (Some(interval), None) => world.time().saturating_sub(interval),
+        (None, Some(start)) => start,
+        _ => return error("invalid params"),
+    };
+
+    let shards = if world.shards().is_empty() {
+        vec![(None, "shard0")]
+    } else {
+        world
+            .shards()
+            .iter()
+            .map(|shard| (Some(&*shard.name), &*shard.name))
+            .collect()
+    };
+    let pvp = shards
+        .into_iter()
+        .map(|(shard, shard_name)| {
+            let rooms = world
+                .rooms()
+                .filter(|&(room_shard, _, _)| room_shard == shard)
+                .filter_map(|(_, name, room)| {
+                    let time = room.last_pvp_time.filter(|&time| time >= since)?;
+                    Some(json!({ "_id": name, "lastPvpTime": time }))
+                })
+                .collect::<Vec<_>>();
+            (
+                shard_name.to_owned(),
+                json!({ "rooms": rooms, "time": world.time() }),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>();
+
+    ok(json!({ "pvp": pvp }))
+}
+
+/// Serves a room history chunk, made of the room's objects unchanged since the chunk's first tick.
+/// Like history files, this isn't wrapped in an API response.
+fn room_history(world: &World, endpoint: &str) -> Response<Body> {
+    let parts = endpoint
+        .trim_start_matches("../room-history/")
+        .trim_end_matches(".json")
+        .split('/')
+        .collect::<Vec<_>>();
+    let (shard, room_name, tick) = match parts[..] {
+        [shard, room_name, tick] => (Some(shard), room_name, tick),
+        [room_name, tick] => (None, room_name, tick),
+        _ => return status(StatusCode::NOT_FOUND),
+    };
+    let base = match tick.parse::<u32>() {
+        Ok(v) if v % HISTORY_CHUNK_SIZE == 0 && v <= world.time() => v,
+        _ => return status(StatusCode::NOT_FOUND),
+    };
+    let room = match world.room(shard, room_name) {
+        Some(v) => v,
+        None => return status(StatusCode::NOT_FOUND),
+    };
+
+    let last = (base + HISTORY_CHUNK_SIZE - 1).min(world.time());
+    let ticks = (base..=last)
+        .map(|tick| {
+            let changes = if tick == base {
+                json!(room.objects)
+            } else {
+                json!({})
+            };
+            (tick.to_string(), changes)
+        })
+        .collect::<serde_json::Map<_, _>>();
+
+    json(json!({ "room": room_name, "base": base, "ticks": ticks }))
+}
+
+fn leaderboard_seasons(world: &World) -> Response<Body> {
+    let seasons = world
+        .seasons()
+        .iter()
+        .map(|season| json!({ "_id": season.id, "name": season.name, "date": season.end_date }))
+        .collect::<Vec<_>>();
+
+    ok(json!({ "seasons": seasons }))
+}
+
+fn leaderboard_type(request: &MockRequest) -> Option<LeaderboardType> {
+    match request.param("mode")? {
+        "world" => Some(LeaderboardType::GlobalControl),
+        "power" => Some(LeaderboardType::PowerProcessed),
+        _ => None,
+    }
+}
+
+/// The rank of a user in a season, if they have a score.
+fn rank(
+    world: &World,
+    leaderboard_type: LeaderboardType,
+    season: &str,
+    user: &MockUser,
+) -> Option<Value> {
+    world
+        .leaderboard(leaderboard_type, season)
+        .iter()
+        .position(|&(ranked, _)| ranked.id == user.id)
+        .map(|rank| {
+            let score = user.leaderboard_scores[&(leaderboard_type, season.to_owned())];
+            json!({ "rank": rank, "score": score, "season": season, "user": user.id })
+        })
+}
+
+fn leaderboard_find(world: &World, request: &MockRequest) -> Response<Body> {
+    let (leaderboard_type, username) = match (leaderboard_type(request), request.param("username"))
+    {
+        (Some(leaderboard_type), Some(username)) => (leaderboard_type, username),
+        _ => return error("invalid params"),
+    };
+    let user = match world.user(username) {
+        Some(v) => v,
+        None => return error("user not found"),
+    };
+
+    match request.param("season") {
+        Some(season) => match rank(world, leaderboard_type, season, user) {
+            Some(rank) => ok(rank),
+            None => error("result not found"),
+        },
+        None => {
+            let list = world
+                .seasons()
+                .iter()
+                .filter_map(|season| rank(world, leaderboard_type, &season.id, user))
+                .collect::<Vec<_>>();
+            ok(json!({ "list": list }))
+        }
+    }
+}
+
+/// Lists a page of a season's leaderboard. Like the official server, at most 20 users can be
+/// requested at once.
+fn leaderboard_list(world: &World, request: &MockRequest) -> Response<Body> {
+    let parse = |name| request.param(name).and_then(|v| v.parse::<usize>().ok());
+    let (leaderboard_type, season, limit, offset) = match (
+        leaderboard_type(request),
+        request.param("season"),
+        parse("limit"),
+        parse("offset"),
+    ) {
+        (Some(leaderboard_type), Some(season), Some(limit), Some(offset)) if limit <= 20 => {
+            (leaderboard_type, season, limit, offset)
+        }
+        _ => return error("invalid params"),
+    };
+
+    let ranked = world.leaderboard(leaderboard_type, season);
+    let page = ranked.iter().enumerate().skip(offset).take(limit);
+    let list = page
+        .clone()
+        .map(|(rank, &(user, score))| {
+            json!({ "rank": rank, "score": score, "season": season, "user": user.id })
+        })
+        .collect::<Vec<_>>();
+    let users = page
+        .map(|(_, &(user, _))| {
+            let details = json!({
+                "_id": user.id,
+                "username": user.username,
+                "gcl": user.gcl_points,
+                "badge": user.badge,
+            });
+            (user.id.clone(), details)
+        })
+        .collect::<serde_json::Map<_, _>>();
+
+    ok(json!({ "count": ranked.len(), "list": list, "users": users }))
+}
+
+fn memory_segment(world: &World, request: &MockRequest) -> Response<Body> {
+    let segment = match request.param("segment").and_then(|s| s.parse().ok()) {
+        Some(v) => v,
+        None => return error("invalid params"),
+    };
+    let key = (request.param("shard").map(ToOwned::to_owned), segment);
+    let data = user(world, request)
+        .memory_segments
+        .get(&key)
+        .cloned()
+        .unwrap_or_default();
+
+    ok(json!({ "data": data }))
+}
+
+fn set_memory_segment(world: &mut World, request: &MockRequest) -> Response<Body> {
+    let segment = match request.body.get("segment").and_then(Value::as_u64) {
+        Some(v) if v < 100 => v as u32,
+        _ => return error("invalid params"),
+    };
+    let data = match request.field("data") {
+        Some(v) => v.to_owned(),
+        None => return error("invalid params"),
+    };
+    let key = (request.field("shard").map(ToOwned::to_owned), segment);
+
+    let token = request
+        .token
+        .as_ref()
+        .expect("expected authenticated request");
+    let user = world
+        .user_by_token_mut(token)
+        .expect("expected request to be authenticated");
+    user.memory_segments.insert(key, data);
+
+    ok(json!({}))
+}
+
+/// A successful response: the given object with `"ok": 1` added.
+fn ok(mut body: Value) -> Response<Body> {
+    body["ok"] = 1.into();
+    json(body)
+}
+
+/// An API error, returned by the server as `200 OK` with an error message.
+fn error(message: &str) -> Response<Body> {
+    json(json!({ "error": message }))
+}
+
+fn json(body: Value) -> Response<Body> {
+    Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("expected building a response with valid headers to succeed")
+}
+
+pub(super) fn status(code: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(code)
+        .body(Body::from(code.canonical_reason().unwrap_or("")))
+        .expect("expected building a response with no headers to succeed")
+}
@@ -0,0 +1,434 @@
+//! An in-process mock Screeps server, for testing code which uses this library without network
+//! access or credentials.
+//!
+//! This module is only available with the `testing` cargo feature.
+//!
+//! [`MockServer`] serves the HTTP endpoints wrapped by [`Api`] from an editable in-memory
+//! [`World`], along with a SockJS websocket which sends channel updates scripted by the test:
+//!
+//! - `auth/signin`, `register/submit` and `auth/me` for the world's users
+//! - `user/world-start-room`, `user/memory-segment` (both `GET` and `POST`)
+//! - `game/room-terrain`, `game/room-status`, `game/map-stats`, `game/room-overview`,
+//!   `game/shards/info` and `version`
+//! - `leaderboard/seasons`, `leaderboard/find` and `leaderboard/list`, from each user's scores
+//! - `game/power-creeps/list`, `experimental/pvp` and room history files
+//! - any other endpoint, with responses scripted using [`World::set_response`]
+//!
+//! ```
+//! # #[cfg(feature = "testing")]
+//! # fn main() {
+//! use screeps_api::{testing::MockServer, SyncApi};
+//!
+//! let server = MockServer::start().unwrap();
+//! let token = server.world().add_user("tester", "password").token.clone();
+//!
+//! let mut api = SyncApi::new_with_connector(hyper::client::HttpConnector::new())
+//!     .unwrap()
+//!     .with_url(server.url())
+//!     .unwrap()
+//!     .with_token(token);
+//!
+//! assert_eq!(api.my_info().unwrap().username, "tester");
+//! # }
+//! # #[cfg(not(feature = "testing"))] fn main() {}
+//! ```
+//!
+//! [`MockServer`]: struct.MockServer.html
+//! [`World`]: struct.World.html
+//! [`World::set_response`]: struct.World.html#method.set_response
+//! [`Api`]: ../struct.Api.html
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    fmt, io,
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use futures::FutureExt;
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server,
+};
+use tokio::sync::{broadcast, oneshot};
+use url::Url;
+
+use crate::websocket::{ChannelUpdate, ScreepsMessage};
+
+mod http;
+mod socket;
+mod world;
+
+pub use self::world::{MockPowerCreep, MockRoom, MockSeason, MockShard, MockUser, World};
+
+/// How many updates can be queued for each socket before it starts missing them.
+const UPDATE_BUFFER: usize = 1024;
+
+/// State shared between the server's connections and its handle.
+struct Shared {
+    world: Mutex<World>,
+    /// Messages for sockets: the channel they're for, or `None` for all sockets, and the raw
+    /// screeps message.
+    updates: broadcast::Sender<(Option<String>, String)>,
+    /// How many sockets are subscribed to each channel.
+    subscriptions: Mutex<HashMap<String, usize>>,
+}
+
+impl Shared {
+    fn world(&self) -> MutexGuard<'_, World> {
+        self.world.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn subscribers(&self, channel: &str) -> usize {
+        self.subscriptions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(channel)
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    fn subscribed(&self, channel: &str) {
+        *self
+            .subscriptions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(channel.to_owned())
+            .or_insert(0) += 1;
+    }
+
+    fn unsubscribed(&self, channel: &str) {
+        let mut subscriptions = self
+            .subscriptions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(count) = subscriptions.get_mut(channel) {
+            *count -= 1;
+            if *count == 0 {
+                subscriptions.remove(channel);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Shared {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Shared")
+            .field("world", &self.world)
+            .field("subscriptions", &self.subscriptions)
+            .finish()
+    }
+}
+
+async fn route(shared: Arc<Shared>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if request.uri().path().starts_with("/socket/") {
+        Ok(socket::upgrade(shared, request))
+    } else {
+        Ok(http::handle(&shared, request).await)
+    }
+}
+
+/// A mock Screeps server listening on a local port, run on a background thread.
+///
+/// The server stops when this is dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    shared: Arc<Shared>,
+    address: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Starts a server with an empty world.
+    pub fn start() -> io::Result<Self> {
+        MockServer::start_with(World::new())
+    }
+
+    /// Starts a server with the given world.
+    pub fn start_with(world: World) -> io::Result<Self> {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+
+        let (updates, _) = broadcast::channel(UPDATE_BUFFER);
+        let shared = Arc::new(Shared {
+            world: Mutex::new(world),
+            updates,
+            subscriptions: Mutex::new(HashMap::new()),
+        });
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()?;
+        let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+        let server = {
+            let _context = runtime.enter();
+            let shared = shared.clone();
+            let make_service = make_service_fn(move |_| {
+                let shared = shared.clone();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |request| route(shared.clone(), request)))
+                }
+            });
+
+            Server::from_tcp(listener)
+                .map_err(io::Error::other)?
+                .serve(make_service)
+                .with_graceful_shutdown(shutdown_signal.map(|_| ()))
+        };
+
+        let thread = thread::Builder::new()
+            .name(format!("mock-screeps-server-{}", address.port()))
+            .spawn(move || {
+                if let Err(e) = runtime.block_on(server) {
+                    warn!("mock server stopped with error: {}", e);
+                }
+            })?;
+
+        Ok(MockServer {
+            shared,
+            address,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        })
+    }
+
+    /// The local address the server is listening on.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// The API url of this server, to pass to [`Api::set_url`]. Websocket urls made from it by
+    /// [`websocket::transform_url`] connect to this server's socket.
+    ///
+    /// [`Api::set_url`]: ../struct.Api.html#method.set_url
+    /// [`websocket::transform_url`]: ../websocket/fn.transform_url.html
+    pub fn url(&self) -> Url {
+        Url::parse(&format!("http://{}/api/", self.address))
+            .expect("expected url made from a socket address to parse successfully")
+    }
+
+    /// Locks the server's world for reading or editing. Requests wait until the lock is released.
+    pub fn world(&self) -> MutexGuard<'_, World> {
+        self.shared.world()
+    }
+
+    /// Sends a channel update to every socket subscribed to the channel, as the JSON array
+    /// `[channel, data]`. Returns the number of sockets subscribed.
+    pub fn send_update(&self, channel: &str, data: serde_json::Value) -> usize {
+        self.send_channel_update(&ChannelUpdate::Other {
+            channel: channel.into(),
+            update: data,
+        })
+    }
+
+    /// Sends a typed channel update to every socket subscribed to its channel. Returns the number
+    /// of sockets subscribed.
+    pub fn send_channel_update(&self, update: &ChannelUpdate) -> usize {
+        let channel = update.channel().to_string();
+        let message = ScreepsMessage::ChannelUpdate {
+            update: update.clone(),
+        }
+        .encode();
+        let _ = self.shared.updates.send((Some(channel.clone()), message));
+        self.shared.subscribers(&channel)
+    }
+
+    /// Sends a raw screeps message, such as `"time 1600000000000"`, to every connected socket.
+    pub fn send_message(&self, message: &str) {
+        let _ = self.shared.updates.send((None, message.to_owned()));
+    }
+
+    /// Returns true if any socket is subscribed to the given channel.
+    pub fn is_subscribed(&self, channel: &str) -> bool {
+        self.shared.subscribers(channel) > 0
+    }
+
+    /// Waits until a socket subscribes to the given channel, or the timeout passes. Returns true
+    /// if a socket subscribed in time.
+    ///
+    /// Subscriptions are handled in the background, so tests should wait for them before sending
+    /// updates which should be received.
+    pub fn wait_for_subscription(&self, channel: &str, timeout: Duration) -> bool {
+        let start = Instant::now();
+        while !self.is_subscribed(channel) {
+            if start.elapsed() > timeout {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        true
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{SinkExt, StreamExt};
+    use hyper::client::HttpConnector;
+    use tokio_tungstenite::tungstenite::Message;
+
+    use super::*;
+    use crate::{
+        websocket::{self, commands, Channel, ChannelUpdate, ScreepsMessage, SockjsMessage},
+        ErrorKind, SyncApi,
+    };
+
+    fn api(server: &MockServer) -> SyncApi<hyper::Client<HttpConnector>> {
+        SyncApi::new_with_connector(HttpConnector::new())
+            .unwrap()
+            .with_url(server.url())
+            .unwrap()
+    }
+
+    #[test]
+    fn serves_http_endpoints() {
+        let mut world = World::new();
+        world.add_shard("shard0");
+        world.add_room(Some("shard0"), "W1N1").terrain = "1".repeat(2500);
+        let novice_end = time::Timespec::new(time::get_time().sec + 3600, 0);
+        world.add_room(None, "W2N2").novice = Some(novice_end);
+        let server = MockServer::start_with(world).unwrap();
+        let mut api = api(&server);
+
+        match api.my_info() {
+            Err(e) => assert!(e.to_string().contains("token"), "{}", e),
+            Ok(_) => panic!("expected my_info to require a token"),
+        }
+
+        server.world().add_user("tester", "password").gcl_points = 12345;
+        api.login("tester", "password").unwrap();
+        let info = api.my_info().unwrap();
+        assert_eq!(info.username, "tester");
+        assert_eq!(info.gcl_points, 12345);
+
+        api.set_memory_segment(Some("shard0"), 5, "hello").unwrap();
+        assert_eq!(api.memory_segment(Some("shard0"), 5).unwrap(), "hello");
+        assert_eq!(api.memory_segment(None::<&str>, 5).unwrap(), "");
+
+        let terrain = api.room_terrain(Some("shard0"), "W1N1").unwrap();
+        assert_eq!(terrain.room_name.to_string(), "W1N1");
+        assert!(api.room_terrain(Some("shard0"), "W9N9").is_err());
+
+        assert_eq!(
+            api.room_status("W2N2").unwrap().state,
+            crate::RoomState::Novice {
+                end_time: novice_end
+            }
+        );
+        assert!(api.room_status("W9N9").unwrap().room_name.is_none());
+
+        let shards = api.shard_list().unwrap();
+        assert_eq!(shards.len(), 1);
+        assert_eq!(shards[0].name, "shard0");
+
+        let version = api.version().unwrap();
+        assert_eq!(version.shards, vec!["shard0".to_owned()]);
+        assert_eq!(version.auth_type(), crate::AuthType::Password);
+    }
+
+    #[test]
+    fn rejects_bad_credentials_and_serves_scripted_responses() {
+        let server = MockServer::start().unwrap();
+        server.world().add_user("tester", "password");
+        let mut api = api(&server);
+
+        let err = api.login("tester", "wrong").unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Unauthorized), "{}", err);
+        let err = api.shard_list().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::StatusCode(_)), "{}", err);
+
+        server.world().set_response(
+            "game/shards/info",
+            serde_json::json!({
+                "ok": 1,
+                "shards": [{ "name": "shardX", "rooms": 10, "users": 2, "tick": 3000.0 }],
+            }),
+        );
+        assert_eq!(api.shard_list().unwrap()[0].name, "shardX");
+    }
+
+    async fn next_text<S>(socket: &mut S) -> String
+    where
+        S: futures::Stream<Item = tokio_tungstenite::tungstenite::Result<Message>> + Unpin,
+    {
+        match socket.next().await.unwrap().unwrap() {
+            Message::Text(text) => text,
+            other => panic!("expected text message, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sends_scripted_channel_updates() {
+        let server = MockServer::start().unwrap();
+        let (user_id, token) = {
+            let mut world = server.world();
+            let user = world.add_user("tester", "password");
+            (user.id.clone(), user.token.clone())
+        };
+        let channel = Channel::user_cpu(user_id.clone());
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let (mut socket, _) = runtime
+            .block_on(tokio_tungstenite::connect_async(
+                websocket::transform_url(server.url().as_str()).unwrap(),
+            ))
+            .unwrap();
+        assert_eq!(runtime.block_on(next_text(&mut socket)), "o");
+
+        runtime
+            .block_on(socket.send(Message::Text(commands::authenticate(token.as_bytes()))))
+            .unwrap();
+        match SockjsMessage::parse(&runtime.block_on(next_text(&mut socket))).unwrap() {
+            SockjsMessage::Messages(ref messages) => match messages[..] {
+                [ScreepsMessage::AuthOk { ref new_token }] => assert_eq!(new_token, &token),
+                ref other => panic!("expected auth ok, found {:?}", other),
+            },
+            other => panic!("expected auth ok, found {:?}", other),
+        }
+
+        runtime
+            .block_on(socket.send(Message::Text(commands::subscribe(&channel))))
+            .unwrap();
+        assert!(server.wait_for_subscription(&channel.to_string(), Duration::from_secs(5)));
+        assert_eq!(
+            server.send_update(
+                &channel.to_string(),
+                serde_json::json!({ "cpu": 12, "memory": 3000 }),
+            ),
+            1
+        );
+
+        let text = runtime.block_on(next_text(&mut socket));
+        match SockjsMessage::parse(&text).unwrap() {
+            SockjsMessage::Messages(ref messages) => match messages[..] {
+                [ScreepsMessage::ChannelUpdate {
+                    update:
+                        ChannelUpdate::UserCpu {
+                            user_id: ref id,
+                            ref update,
+                        },
+                }] => {
+                    assert_eq!(id, &user_id);
+                    assert_eq!(update.last_tick_cpu, 12);
+                }
+                ref other => panic!("expected cpu update, found {:?}", other),
+            },
+            other => panic!("expected cpu update, found {:?}", other),
+        }
+    }
+}
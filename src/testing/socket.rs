@@ -0,0 +1,162 @@
+//! The mock server's SockJS websocket.
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use futures::{SinkExt, StreamExt};
+use hyper::{
+    header::{CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, UPGRADE},
+    upgrade::Upgraded,
+    Body, Request, Response, StatusCode,
+};
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tokio_tungstenite::{
+    tungstenite::{handshake::derive_accept_key, protocol::Role, Message},
+    WebSocketStream,
+};
+
+use super::{http::status, Shared};
+use crate::websocket::{ScreepsMessage, SockjsMessage};
+
+/// Accepts a websocket upgrade, and serves the socket once the upgrade completes.
+pub(super) fn upgrade(shared: Arc<Shared>, mut request: Request<Body>) -> Response<Body> {
+    let accept = match request.headers().get(SEC_WEBSOCKET_KEY) {
+        Some(key) => derive_accept_key(key.as_bytes()),
+        None => return status(StatusCode::BAD_REQUEST),
+    };
+
+    let on_upgrade = hyper::upgrade::on(&mut request);
+    tokio::spawn(async move {
+        match on_upgrade.await {
+            Ok(upgraded) => {
+                let socket = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+                serve(shared, socket).await;
+            }
+            Err(e) => warn!("mock server websocket upgrade failed: {}", e),
+        }
+    });
+
+    Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header(CONNECTION, "upgrade")
+        .header(UPGRADE, "websocket")
+        .header(SEC_WEBSOCKET_ACCEPT, accept)
+        .body(Body::empty())
+        .expect("expected building a response with valid headers to succeed")
+}
+
+/// Wraps a raw screeps message in a SockJS array frame.
+fn frame(message: &str) -> String {
+    SockjsMessage::Messages(vec![ScreepsMessage::Other(message.into())]).encode()
+}
+
+async fn serve(shared: Arc<Shared>, socket: WebSocketStream<Upgraded>) {
+    let (mut sink, mut stream) = socket.split();
+    let (sender, mut outgoing) = mpsc::unbounded_channel::<String>();
+    let subscriptions = Arc::new(Mutex::new(HashSet::<String>::new()));
+
+    let writer = tokio::spawn(async move {
+        while let Some(text) = outgoing.recv().await {
+            if sink.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let forwarder = {
+        let mut updates = shared.updates.subscribe();
+        let sender = sender.clone();
+        let subscriptions = subscriptions.clone();
+        tokio::spawn(async move {
+            loop {
+                let (channel, message) = match updates.recv().await {
+                    Ok(v) => v,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("mock server socket skipped {} updates", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                let subscribed = match channel {
+                    Some(ref channel) => subscriptions
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .contains(channel),
+                    None => true,
+                };
+                if subscribed && sender.send(frame(&message)).is_err() {
+                    break;
+                }
+            }
+        })
+    };
+
+    let _ = sender.send(SockjsMessage::Open.encode());
+
+    let mut authenticated = false;
+    while let Some(message) = stream.next().await {
+        let text = match message {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(_)) | Err(_) => break,
+            Ok(_) => continue,
+        };
+        let commands = match serde_json::from_str::<Vec<String>>(&text) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!(
+                    "mock server socket received malformed message {:?}: {}",
+                    text, e
+                );
+                continue;
+            }
+        };
+
+        for command in commands {
+            if let Some(token) = command.strip_prefix("auth ") {
+                let reply = match shared.world().user_by_token(token.as_bytes()) {
+                    Some(user) => format!("auth ok {}", user.token),
+                    None => "auth failed".to_owned(),
+                };
+                authenticated = reply != "auth failed";
+                let _ = sender.send(frame(&reply));
+            } else if let Some(channel) = command.strip_prefix("subscribe ") {
+                if !authenticated {
+                    warn!(
+                        "mock server socket ignored subscribe before auth: {}",
+                        channel
+                    );
+                    continue;
+                }
+                let added = subscriptions
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .insert(channel.to_owned());
+                if added {
+                    shared.subscribed(channel);
+                }
+            } else if let Some(channel) = command.strip_prefix("unsubscribe ") {
+                let removed = subscriptions
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .remove(channel);
+                if removed {
+                    shared.unsubscribed(channel);
+                }
+            } else {
+                warn!("mock server socket ignored unknown command: {}", command);
+            }
+        }
+    }
+
+    forwarder.abort();
+    drop(sender);
+    let _ = writer.await;
+    for channel in subscriptions
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .drain()
+    {
+        shared.unsubscribed(&channel);
+    }
+}
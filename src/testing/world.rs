@@ -0,0 +1,387 @@
+//! The editable game state served by a mock server.
+use std::collections::HashMap;
+
+use rand::{distributions::Alphanumeric, Rng};
+use time::Timespec;
+
+use crate::{data::Badge, websocket::objects::PowerType, LeaderboardType};
+
+/// The state of a mock server: users, shards, rooms, leaderboard seasons and scripted responses.
+///
+/// Edit it through [`MockServer::world`] while the server is running, or build it up front and
+/// pass it to [`MockServer::start_with`].
+///
+/// [`MockServer::world`]: struct.MockServer.html#method.world
+/// [`MockServer::start_with`]: struct.MockServer.html#method.start_with
+#[derive(Clone, Debug, Default)]
+pub struct World {
+    users: Vec<MockUser>,
+    shards: Vec<MockShard>,
+    rooms: HashMap<(Option<String>, String), MockRoom>,
+    seasons: Vec<MockSeason>,
+    time: u32,
+    responses: HashMap<String, serde_json::Value>,
+}
+
+impl World {
+    /// Creates an empty world, with no users, shards or rooms.
+    pub fn new() -> Self {
+        World::default()
+    }
+
+    /// Adds a user who can log in with the given username and password, and returns it for
+    /// further editing.
+    ///
+    /// The user is given a random id and auth token, which can be read from the returned value.
+    pub fn add_user<U, P>(&mut self, username: U, password: P) -> &mut MockUser
+    where
+        U: Into<String>,
+        P: Into<String>,
+    {
+        let user = MockUser::new(username.into(), Some(password.into()));
+        self.users.push(user);
+        self.users.last_mut().expect("just pushed a user")
+    }
+
+    /// Gets a user by username.
+    pub fn user(&self, username: &str) -> Option<&MockUser> {
+        self.users.iter().find(|u| u.username == username)
+    }
+
+    /// Gets a user by username, for editing.
+    pub fn user_mut(&mut self, username: &str) -> Option<&mut MockUser> {
+        self.users.iter_mut().find(|u| u.username == username)
+    }
+
+    /// All users in the world.
+    pub fn users(&self) -> &[MockUser] {
+        &self.users
+    }
+
+    /// Adds a shard, and returns it for further editing.
+    ///
+    /// If no shards are added, the server acts as a non-sharded server: `game/shards/info` responds
+    /// with `404 Not Found`.
+    pub fn add_shard<S: Into<String>>(&mut self, name: S) -> &mut MockShard {
+        self.shards.push(MockShard {
+            name: name.into(),
+            rooms: 0,
+            users: 0,
+            tick: 1000.0,
+            _non_exhaustive: (),
+        });
+        self.shards.last_mut().expect("just pushed a shard")
+    }
+
+    /// All shards in the world.
+    pub fn shards(&self) -> &[MockShard] {
+        &self.shards
+    }
+
+    /// The current game time, used for recent PvP and room history.
+    pub fn time(&self) -> u32 {
+        self.time
+    }
+
+    /// Sets the current game time. Room history is served for chunks starting at or before it.
+    pub fn set_time(&mut self, time: u32) {
+        self.time = time;
+    }
+
+    /// Adds a leaderboard season with the given id, such as `"2017-02"`, and returns it for further
+    /// editing. Scores for the season are set in each user's [`MockUser::leaderboard_scores`].
+    ///
+    /// [`MockUser::leaderboard_scores`]: struct.MockUser.html#structfield.leaderboard_scores
+    pub fn add_season<S: Into<String>>(&mut self, id: S) -> &mut MockSeason {
+        let id = id.into();
+        self.seasons.push(MockSeason {
+            name: id.clone(),
+            id,
+            end_date: String::new(),
+            _non_exhaustive: (),
+        });
+        self.seasons.last_mut().expect("just pushed a season")
+    }
+
+    /// All leaderboard seasons in the world, oldest first.
+    pub fn seasons(&self) -> &[MockSeason] {
+        &self.seasons
+    }
+
+    /// Adds an open room with plain terrain, and returns it for further editing. Replaces any
+    /// existing room with the same name on the same shard.
+    pub fn add_room<S: Into<String>>(
+        &mut self,
+        shard: Option<&str>,
+        room_name: S,
+    ) -> &mut MockRoom {
+        let key = (shard.map(Into::into), room_name.into());
+        let room = MockRoom {
+            status: "normal".to_owned(),
+            novice: None,
+            open_time: None,
+            terrain: "0".repeat(2500),
+            owner: None,
+            level: 0,
+            last_pvp_time: None,
+            objects: HashMap::new(),
+            _non_exhaustive: (),
+        };
+        self.rooms.insert(key.clone(), room);
+        self.rooms.get_mut(&key).expect("just inserted a room")
+    }
+
+    /// Gets a room.
+    pub fn room(&self, shard: Option<&str>, room_name: &str) -> Option<&MockRoom> {
+        self.rooms
+            .get(&(shard.map(Into::into), room_name.to_owned()))
+    }
+
+    /// Gets a room, for editing.
+    pub fn room_mut(&mut self, shard: Option<&str>, room_name: &str) -> Option<&mut MockRoom> {
+        self.rooms
+            .get_mut(&(shard.map(Into::into), room_name.to_owned()))
+    }
+
+    /// Scripts the JSON response to an endpoint, such as `"game/room-overview"` or
+    /// `"leaderboard/list"`. Scripted responses replace the server's own handling of endpoints it
+    /// implements, so they can also be used to return errors.
+    ///
+    /// Endpoints are paths relative to the API url, without query parameters. Room history files,
+    /// which are served next to the API, are scripted with paths like
+    /// `"../room-history/shard0/W1N1/20.json"`.
+    pub fn set_response<E: Into<String>>(&mut self, endpoint: E, response: serde_json::Value) {
+        self.responses.insert(endpoint.into(), response);
+    }
+
+    /// Removes a scripted response.
+    pub fn remove_response(&mut self, endpoint: &str) -> Option<serde_json::Value> {
+        self.responses.remove(endpoint)
+    }
+
+    /// All rooms in the world, with their shards and names.
+    pub(super) fn rooms(&self) -> impl Iterator<Item = (Option<&str>, &str, &MockRoom)> {
+        self.rooms
+            .iter()
+            .map(|((shard, name), room)| (shard.as_deref(), &**name, room))
+    }
+
+    /// The users with a score in a leaderboard season, best first.
+    pub(super) fn leaderboard(
+        &self,
+        leaderboard_type: LeaderboardType,
+        season: &str,
+    ) -> Vec<(&MockUser, u64)> {
+        let mut ranked = self
+            .users
+            .iter()
+            .filter_map(|user| {
+                let key = (leaderboard_type, season.to_owned());
+                user.leaderboard_scores
+                    .get(&key)
+                    .map(|&score| (user, score))
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.username.cmp(&b.0.username)));
+        ranked
+    }
+
+    pub(super) fn response(&self, endpoint: &str) -> Option<&serde_json::Value> {
+        self.responses.get(endpoint)
+    }
+
+    pub(super) fn user_by_login(&self, login: &str, password: &str) -> Option<&MockUser> {
+        self.users.iter().find(|u| {
+            (u.username == login || u.email.as_deref() == Some(login))
+                && u.password.as_deref() == Some(password)
+        })
+    }
+
+    pub(super) fn user_by_token(&self, token: &[u8]) -> Option<&MockUser> {
+        self.users.iter().find(|u| u.token.as_bytes() == token)
+    }
+
+    pub(super) fn user_by_token_mut(&mut self, token: &[u8]) -> Option<&mut MockUser> {
+        self.users.iter_mut().find(|u| u.token.as_bytes() == token)
+    }
+
+    pub(super) fn register(
+        &mut self,
+        username: String,
+        email: Option<String>,
+        password: String,
+    ) -> Option<&mut MockUser> {
+        if self.user(&username).is_some() {
+            return None;
+        }
+        let mut user = MockUser::new(username, Some(password));
+        user.email = email;
+        self.users.push(user);
+        self.users.last_mut()
+    }
+}
+
+/// A user of a mock server.
+#[derive(Clone, Debug)]
+pub struct MockUser {
+    /// The user's unique id.
+    pub id: String,
+    /// The user's username.
+    pub username: String,
+    /// The user's email, which can also be used to log in.
+    pub email: Option<String>,
+    /// The user's password, or `None` if they can only use their token.
+    pub password: Option<String>,
+    /// The token which authenticates as this user.
+    pub token: String,
+    /// The user's CPU limit.
+    pub cpu: i32,
+    /// The user's total GCL points.
+    pub gcl_points: u64,
+    /// The user's total power points.
+    pub power_points: u64,
+    /// The user's credits.
+    pub money: f64,
+    /// The room the user starts viewing, returned by `user/world-start-room`.
+    pub start_room: String,
+    /// The shard of `start_room`, if the server is sharded.
+    pub start_shard: Option<String>,
+    /// The user's memory segments, by shard and segment id.
+    pub memory_segments: HashMap<(Option<String>, u32), String>,
+    /// The user's badge.
+    pub badge: Badge,
+    /// The user's leaderboard scores, by leaderboard and season id.
+    pub leaderboard_scores: HashMap<(LeaderboardType, String), u64>,
+    /// The user's power creeps.
+    pub power_creeps: Vec<MockPowerCreep>,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    _non_exhaustive: (),
+}
+
+impl MockUser {
+    fn new(username: String, password: Option<String>) -> Self {
+        MockUser {
+            id: random_string(24),
+            username,
+            email: None,
+            password,
+            token: random_string(40),
+            cpu: 100,
+            gcl_points: 0,
+            power_points: 0,
+            money: 0.0,
+            start_room: "W5N5".to_owned(),
+            start_shard: None,
+            memory_segments: HashMap::new(),
+            badge: serde_json::from_value(serde_json::json!({
+                "type": 1,
+                "color1": "#260d0d",
+                "color2": "#6b2e41",
+                "color3": "#ffe56d",
+                "param": 0,
+                "flip": false,
+            }))
+            .expect("expected default mock badge to parse"),
+            leaderboard_scores: HashMap::new(),
+            power_creeps: Vec::new(),
+            _non_exhaustive: (),
+        }
+    }
+
+    /// Adds an unspawned level 0 operator power creep, and returns it for further editing.
+    ///
+    /// The power creep is given a random id, which can be read from the returned value.
+    pub fn add_power_creep<S: Into<String>>(&mut self, name: S) -> &mut MockPowerCreep {
+        self.power_creeps.push(MockPowerCreep {
+            id: random_string(24),
+            name: name.into(),
+            class: "operator".to_owned(),
+            level: 0,
+            powers: HashMap::new(),
+            shard: None,
+            _non_exhaustive: (),
+        });
+        self.power_creeps
+            .last_mut()
+            .expect("just pushed a power creep")
+    }
+}
+
+/// A power creep of a mock server user.
+#[derive(Clone, Debug)]
+pub struct MockPowerCreep {
+    /// The power creep's unique id.
+    pub id: String,
+    /// The power creep's name.
+    pub name: String,
+    /// The power creep's class, such as `"operator"`.
+    pub class: String,
+    /// The power creep's level.
+    pub level: u32,
+    /// The level of each power the power creep has learned.
+    pub powers: HashMap<PowerType, u32>,
+    /// The shard the power creep is spawned on, if any.
+    pub shard: Option<String>,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    _non_exhaustive: (),
+}
+
+/// A leaderboard season of a mock server.
+#[derive(Clone, Debug)]
+pub struct MockSeason {
+    /// The season's id, such as `"2017-02"`.
+    pub id: String,
+    /// The season's display name.
+    pub name: String,
+    /// The date the season ends, as reported by the server.
+    pub end_date: String,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    _non_exhaustive: (),
+}
+
+/// A shard of a mock server.
+#[derive(Clone, Debug)]
+pub struct MockShard {
+    /// The shard's name.
+    pub name: String,
+    /// The number of open rooms reported for this shard.
+    pub rooms: u32,
+    /// The number of users reported for this shard.
+    pub users: u32,
+    /// The average tick duration reported for this shard, in milliseconds.
+    pub tick: f64,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    _non_exhaustive: (),
+}
+
+/// A room of a mock server.
+#[derive(Clone, Debug)]
+pub struct MockRoom {
+    /// The room's status. This library only understands `"normal"`.
+    pub status: String,
+    /// The end time of the novice area this room is in.
+    pub novice: Option<Timespec>,
+    /// The time this room opens into the novice area.
+    pub open_time: Option<Timespec>,
+    /// The room's terrain, encoded as 2500 digits: `0` for plains, `1` for walls, `2` for swamps
+    /// and `3` for swampy walls.
+    pub terrain: String,
+    /// The username of the room's owner, if owned.
+    pub owner: Option<String>,
+    /// The level of the room's controller, if owned.
+    pub level: u32,
+    /// The game time PvP last happened in this room, reported by `experimental/pvp`.
+    pub last_pvp_time: Option<u32>,
+    /// The room's objects by id, as JSON. The room's history contains them unchanged.
+    pub objects: HashMap<String, serde_json::Value>,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    _non_exhaustive: (),
+}
+
+fn random_string(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
@@ -0,0 +1,484 @@
+//! Pluggable HTTP transports for [`Api`].
+//!
+//! [`Api`] builds each call into an [`HttpRequest`], and hands it to a [`Transport`] to get an
+//! [`HttpResponse`] back. `hyper::Client` is the transport used to talk to real servers. Others
+//! can answer requests without a network:
+//!
+//! - [`MockTransport`] answers each request with a function, for scripted unit tests.
+//! - [`RecordingTransport`] wraps another transport and records each request and response.
+//!   The recording can be saved as a fixture file.
+//! - [`ReplayTransport`] answers requests from a fixture file, for offline tests.
+//!
+//! [`Api`]: ../struct.Api.html
+//! [`HttpRequest`]: struct.HttpRequest.html
+//! [`HttpResponse`]: struct.HttpResponse.html
+//! [`Transport`]: trait.Transport.html
+//! [`MockTransport`]: struct.MockTransport.html
+//! [`RecordingTransport`]: struct.RecordingTransport.html
+//! [`ReplayTransport`]: struct.ReplayTransport.html
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use futures::future::{self, BoxFuture, FutureExt, TryFutureExt};
+use hyper::{
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE},
+    Method, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{Error, Token};
+
+/// A way of sending HTTP requests for an [`Api`].
+///
+/// [`Api`]: ../struct.Api.html
+pub trait Transport: Clone + Send + Sync + 'static {
+    /// Sends a request, and returns the full response.
+    ///
+    /// Only failures to get a response at all should be errors. Responses with any status code,
+    /// including errors, should be returned as `Ok`.
+    fn send(&self, request: HttpRequest) -> BoxFuture<'static, Result<HttpResponse, Error>>;
+}
+
+/// A single request to the API.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpRequest {
+    /// The HTTP method: `GET`, or `POST` for requests with a body.
+    pub method: Method,
+    /// The full URL, including query parameters.
+    pub url: Url,
+    /// The auth token to send as the `X-Token` header, if any.
+    pub auth_token: Option<Token>,
+    /// The JSON body, for `POST` requests.
+    pub body: Option<String>,
+}
+
+impl HttpRequest {
+    /// Builds a hyper request.
+    pub fn to_hyper(&self) -> hyper::Request<hyper::Body> {
+        let mut request = hyper::Request::builder();
+
+        request = request.method(self.method.clone()).uri(self.url.as_str());
+
+        // headers
+        request = request.header(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+        if let Some(ref token) = self.auth_token {
+            request = request.header(
+                "X-Token",
+                HeaderValue::from_maybe_shared(token.clone())
+                    // TODO: turn this into a non-expect error (how the heck does this function return errors?)
+                    .expect("tokens should always be valid headers"),
+            );
+        }
+
+        let request = match self.body {
+            Some(ref body) => request.body(hyper::Body::from(body.clone())),
+            None => request.body(hyper::Body::empty()),
+        };
+        request.expect("building http request should never fail")
+    }
+}
+
+/// A full response from the API.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpResponse {
+    /// The status code.
+    pub status: StatusCode,
+    /// All response headers.
+    pub headers: HeaderMap,
+    /// The response body.
+    pub body: bytes::Bytes,
+}
+
+impl HttpResponse {
+    /// Creates a response with the given status and body, and no headers.
+    pub fn new<B: Into<bytes::Bytes>>(status: StatusCode, body: B) -> Self {
+        HttpResponse {
+            status,
+            headers: HeaderMap::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Creates a `200 OK` response with the given JSON body.
+    pub fn json(body: &serde_json::Value) -> Self {
+        HttpResponse::new(StatusCode::OK, body.to_string())
+    }
+}
+
+impl<C> Transport for hyper::Client<C>
+where
+    C: hyper::client::connect::Connect + Clone + Send + Sync + 'static,
+{
+    fn send(&self, request: HttpRequest) -> BoxFuture<'static, Result<HttpResponse, Error>> {
+        let response = self.request(request.to_hyper());
+
+        async move {
+            let (parts, body) = response.await?.into_parts();
+            let body = hyper::body::to_bytes(body).await?;
+
+            Ok(HttpResponse {
+                status: parts.status,
+                headers: parts.headers,
+                body,
+            })
+        }
+        .boxed()
+    }
+}
+
+/// A transport answering every request with a function, without any networking.
+#[derive(Clone)]
+pub struct MockTransport(Arc<dyn Fn(&HttpRequest) -> HttpResponse + Send + Sync>);
+
+impl MockTransport {
+    /// Creates a transport answering requests with the given function.
+    pub fn new<F>(handler: F) -> Self
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        MockTransport(Arc::new(handler))
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'static, Result<HttpResponse, Error>> {
+        future::ok((self.0)(&request)).boxed()
+    }
+}
+
+impl fmt::Debug for MockTransport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("MockTransport").finish()
+    }
+}
+
+/// A recorded request and response. Each is stored as one line of JSON in fixture files.
+///
+/// Auth tokens, tokens returned by the server and passwords are never recorded: the `password`
+/// of request bodies and the `token` of response bodies are replaced with `"..."`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecordedExchange {
+    /// The request method.
+    pub method: String,
+    /// The full request URL.
+    pub url: String,
+    /// The request body, if any, with any password redacted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<String>,
+    /// The response status code.
+    pub status: u16,
+    /// The response headers, other than `X-Token`.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// The response body, with any token redacted.
+    pub response_body: String,
+}
+
+impl RecordedExchange {
+    fn new(request: &HttpRequest, response: &HttpResponse) -> Self {
+        let headers = response
+            .headers
+            .iter()
+            .filter(|&(name, _)| name != "x-token")
+            .filter_map(|(name, value)| {
+                Some((name.as_str().to_owned(), value.to_str().ok()?.to_owned()))
+            })
+            .collect();
+
+        RecordedExchange {
+            method: request.method.as_str().to_owned(),
+            url: request.url.as_str().to_owned(),
+            request_body: request.body.as_deref().map(|body| redact(body, "password")),
+            status: response.status.as_u16(),
+            headers,
+            response_body: redact(&String::from_utf8_lossy(&response.body), "token"),
+        }
+    }
+
+    fn matches(&self, request: &HttpRequest) -> bool {
+        self.method == request.method.as_str()
+            && self.url == request.url.as_str()
+            && self.request_body.as_deref()
+                == request
+                    .body
+                    .as_deref()
+                    .map(|body| redact(body, "password"))
+                    .as_deref()
+    }
+
+    /// Rebuilds the recorded response.
+    pub fn response(&self) -> io::Result<HttpResponse> {
+        let invalid = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes()).map_err(|e| invalid(e.to_string()))?,
+                HeaderValue::from_str(value).map_err(|e| invalid(e.to_string()))?,
+            );
+        }
+
+        Ok(HttpResponse {
+            status: StatusCode::from_u16(self.status).map_err(|e| invalid(e.to_string()))?,
+            headers,
+            body: self.response_body.clone().into(),
+        })
+    }
+}
+
+/// Replaces the value of `key` in a JSON object body with `"..."`. Other bodies are returned as is.
+fn redact(body: &str, key: &str) -> String {
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(serde_json::Value::Object(mut map)) if map.contains_key(key) => {
+            map.insert(key.to_owned(), "...".into());
+            serde_json::Value::Object(map).to_string()
+        }
+        _ => body.to_owned(),
+    }
+}
+
+/// Writes exchanges as a fixture file: one JSON object per line.
+fn write_exchanges<W: Write>(exchanges: &[RecordedExchange], mut writer: W) -> io::Result<()> {
+    for exchange in exchanges {
+        serde_json::to_writer(&mut writer, exchange)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// A transport which sends requests with another transport, and records each request along with
+/// its response.
+#[derive(Clone, Debug)]
+pub struct RecordingTransport<T> {
+    inner: T,
+    exchanges: Arc<Mutex<Vec<RecordedExchange>>>,
+}
+
+impl<T: Transport> RecordingTransport<T> {
+    /// Creates a transport recording requests sent with the given transport.
+    pub fn new(inner: T) -> Self {
+        RecordingTransport {
+            inner,
+            exchanges: Arc::default(),
+        }
+    }
+
+    /// All exchanges recorded so far, oldest first.
+    pub fn exchanges(&self) -> Vec<RecordedExchange> {
+        self.exchanges
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Writes all exchanges recorded so far as a fixture.
+    pub fn write_fixture<W: Write>(&self, writer: W) -> io::Result<()> {
+        write_exchanges(&self.exchanges(), writer)
+    }
+
+    /// Saves all exchanges recorded so far as a fixture file at the given path, replacing any
+    /// existing file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_fixture(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<T: Transport> Transport for RecordingTransport<T> {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'static, Result<HttpResponse, Error>> {
+        let exchanges = self.exchanges.clone();
+
+        self.inner
+            .send(request.clone())
+            .map_ok(move |response| {
+                exchanges
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(RecordedExchange::new(&request, &response));
+                response
+            })
+            .boxed()
+    }
+}
+
+/// A transport answering requests from recorded exchanges.
+///
+/// Each request is answered with the first unused exchange with the same method, URL and body.
+/// Once all such exchanges have been used, the last is used again. Requests without any recorded
+/// exchange fail with an `io::ErrorKind::NotFound` error.
+#[derive(Clone, Debug)]
+pub struct ReplayTransport {
+    exchanges: Arc<Mutex<Vec<(RecordedExchange, bool)>>>,
+}
+
+impl ReplayTransport {
+    /// Creates a transport replaying the given exchanges.
+    pub fn new(exchanges: Vec<RecordedExchange>) -> Self {
+        ReplayTransport {
+            exchanges: Arc::new(Mutex::new(
+                exchanges.into_iter().map(|e| (e, false)).collect(),
+            )),
+        }
+    }
+
+    /// Reads a fixture.
+    pub fn read_fixture<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut exchanges = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                exchanges.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(ReplayTransport::new(exchanges))
+    }
+
+    /// Opens a fixture file at the given path.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        ReplayTransport::read_fixture(BufReader::new(File::open(path)?))
+    }
+
+    fn answer(&self, request: &HttpRequest) -> io::Result<HttpResponse> {
+        let mut exchanges = self
+            .exchanges
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let found = match exchanges
+            .iter()
+            .position(|(e, used)| !used && e.matches(request))
+        {
+            Some(index) => Some(index),
+            None => exchanges.iter().rposition(|(e, _)| e.matches(request)),
+        };
+        match found {
+            Some(index) => {
+                exchanges[index].1 = true;
+                exchanges[index].0.response()
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "no recorded response for {} {}",
+                    request.method, request.url
+                ),
+            )),
+        }
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'static, Result<HttpResponse, Error>> {
+        future::ready(self.answer(&request).map_err(Error::from)).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::{Api, ErrorKind};
+
+    fn terrain_response() -> serde_json::Value {
+        json!({
+            "ok": 1,
+            "terrain": [{
+                "_id": "579fa8950700be0674d2d70b",
+                "room": "W0N0",
+                "terrain": "0".repeat(2500),
+                "type": "terrain"
+            }]
+        })
+    }
+
+    fn mock() -> MockTransport {
+        MockTransport::new(|request| {
+            if request.url.path() == "/api/game/room-terrain" {
+                let mut response = HttpResponse::json(&terrain_response());
+                response
+                    .headers
+                    .insert("X-RateLimit-Limit", HeaderValue::from_static("360"));
+                response
+            } else {
+                HttpResponse::new(StatusCode::NOT_FOUND, "not found")
+            }
+        })
+    }
+
+    #[test]
+    fn api_with_mock_transport() {
+        let api = Api::new(mock());
+
+        let terrain = block_on(api.room_terrain(None::<&str>, "W0N0")).unwrap();
+        assert_eq!(terrain.room_name.to_string(), "W0N0");
+
+        let err = block_on(api.shard_list()).unwrap_err();
+        match *err.kind() {
+            ErrorKind::StatusCode(StatusCode::NOT_FOUND) => (),
+            ref other => panic!("expected 404, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn record_and_replay_fixture() {
+        let recording = RecordingTransport::new(mock());
+        let api = Api::new(recording.clone()).with_token("secret");
+        block_on(api.room_terrain(None::<&str>, "W0N0")).unwrap();
+        block_on(api.shard_list()).unwrap_err();
+
+        let mut fixture = Vec::new();
+        recording.write_fixture(&mut fixture).unwrap();
+        assert_eq!(recording.exchanges().len(), 2);
+        assert!(!String::from_utf8_lossy(&fixture).contains("secret"));
+
+        let replay = ReplayTransport::read_fixture(&fixture[..]).unwrap();
+        let api = Api::new(replay);
+        let terrain = block_on(api.room_terrain(None::<&str>, "W0N0")).unwrap();
+        assert_eq!(terrain.room_name.to_string(), "W0N0");
+        // replayed responses can be used more than once.
+        block_on(api.room_terrain(None::<&str>, "W0N0")).unwrap();
+
+        let err = block_on(api.room_terrain(None::<&str>, "E0N0")).unwrap_err();
+        match *err.kind() {
+            ErrorKind::Io(ref e) => assert_eq!(e.kind(), io::ErrorKind::NotFound),
+            ref other => panic!("expected missing fixture, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn recorded_login_has_no_credentials() {
+        let server = MockTransport::new(|request| {
+            if request.url.path() == "/api/auth/signin" {
+                HttpResponse::json(&json!({ "ok": 1, "token": "live-token" }))
+            } else {
+                HttpResponse::new(StatusCode::NOT_FOUND, "not found")
+            }
+        });
+        let recording = RecordingTransport::new(server);
+        let api = Api::new(recording.clone());
+        block_on(api.login("bob@example.com", "hunter2"))
+            .unwrap()
+            .return_to(api.token_storage());
+
+        let mut fixture = Vec::new();
+        recording.write_fixture(&mut fixture).unwrap();
+        let fixture_text = String::from_utf8_lossy(&fixture);
+        assert!(!fixture_text.contains("hunter2"));
+        assert!(!fixture_text.contains("live-token"));
+        assert!(fixture_text.contains("bob@example.com"));
+
+        // the recorded login is still replayed for the same request.
+        let replay = ReplayTransport::read_fixture(&fixture[..]).unwrap();
+        let api = Api::new(replay);
+        let logged_in = block_on(api.login("bob@example.com", "hunter2")).unwrap();
+        assert_eq!(&logged_in.token[..], b"...");
+    }
+}
@@ -0,0 +1,422 @@
+//! Reading the `.screeps.yaml` unified credentials file shared by screeps tools.
+//!
+//! See the [screepers standard] for the format.
+//!
+//! [screepers standard]: https://github.com/screepers/screepers-standards/blob/master/SS3-Unified_Credentials_File.md
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsString,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::server::ServerProfile;
+
+mod error {
+    use std::{error, fmt, io, path::PathBuf};
+
+    /// Error finding or reading a unified config file.
+    #[derive(Debug)]
+    pub enum ConfigError {
+        /// No config file exists in any of the searched locations.
+        NotFound {
+            /// The paths which were searched, in order.
+            searched: Vec<PathBuf>,
+        },
+        /// Error reading the config file.
+        Io {
+            /// The IO error.
+            err: io::Error,
+            /// The file which failed to read.
+            path: PathBuf,
+        },
+        /// The config file isn't valid YAML, or doesn't match the unified config format.
+        Yaml {
+            /// The parse error.
+            err: serde_yaml::Error,
+            /// The file which failed to parse, if read from a file.
+            path: Option<PathBuf>,
+        },
+        /// The config file has no server with the requested name.
+        UnknownServer {
+            /// The requested server name.
+            name: String,
+        },
+    }
+
+    impl fmt::Display for ConfigError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match *self {
+                ConfigError::NotFound { ref searched } => {
+                    write!(f, "no unified config file found, searched:")?;
+                    for path in searched {
+                        write!(f, " {}", path.display())?;
+                    }
+                    Ok(())
+                }
+                ConfigError::Io { ref err, ref path } => {
+                    write!(f, "error reading {}: {}", path.display(), err)
+                }
+                ConfigError::Yaml {
+                    ref err,
+                    path: Some(ref path),
+                } => write!(f, "error parsing {}: {}", path.display(), err),
+                ConfigError::Yaml {
+                    ref err,
+                    path: None,
+                } => {
+                    write!(f, "error parsing unified config: {}", err)
+                }
+                ConfigError::UnknownServer { ref name } => {
+                    write!(f, "no server named {:?} in unified config", name)
+                }
+            }
+        }
+    }
+
+    impl error::Error for ConfigError {
+        fn cause(&self) -> Option<&dyn error::Error> {
+            match *self {
+                ConfigError::Io { ref err, .. } => Some(err),
+                ConfigError::Yaml { ref err, .. } => Some(err),
+                ConfigError::NotFound { .. } | ConfigError::UnknownServer { .. } => None,
+            }
+        }
+    }
+}
+
+pub use self::error::ConfigError;
+
+/// The contents of a unified config file: the servers tools can connect to, and configuration
+/// for each tool.
+///
+/// ```
+/// use screeps_api::UnifiedConfig;
+///
+/// let config = UnifiedConfig::parse(
+///     "
+/// servers:
+///   main:
+///     host: screeps.com
+///     secure: true
+///     token: 3bdd1da7-3002-4aaa-be91-330562f54093
+///   private:
+///     host: 127.0.0.1
+///     port: 21025
+///     username: bob
+///     password: secret
+/// ",
+/// )
+/// .unwrap();
+///
+/// let main = config.profile("main").unwrap();
+/// assert_eq!(main.api_url().unwrap().as_str(), "https://screeps.com/api/");
+/// assert!(main.token().is_some());
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct UnifiedConfig {
+    /// The configured servers, by name.
+    #[serde(default)]
+    pub servers: HashMap<String, ServerConfig>,
+    /// Configuration for each tool, by tool name. The format of each is up to the tool.
+    #[serde(default)]
+    pub configs: HashMap<String, serde_yaml::Value>,
+    /// The file this config was read from, if any.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    #[serde(skip)]
+    _non_exhaustive: (),
+}
+
+/// A single server in a unified config file.
+#[derive(Clone, Default, Deserialize)]
+pub struct ServerConfig {
+    /// The server's host name or address.
+    pub host: String,
+    /// The server's port, or `None` for the default http or https port.
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Whether the server uses https.
+    #[serde(default)]
+    pub secure: bool,
+    /// The auth token to use.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// The username to log in with, for servers without tokens.
+    #[serde(default)]
+    pub username: Option<String>,
+    /// The password to log in with, for servers without tokens.
+    #[serde(default)]
+    pub password: Option<String>,
+    /// The path the server's API is under, such as `/ptr`.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    #[serde(skip)]
+    _non_exhaustive: (),
+}
+
+impl ServerConfig {
+    /// Creates a profile for this server, with its token or username and password.
+    pub fn profile(&self) -> ServerProfile {
+        let mut profile = ServerProfile::new(&*self.host)
+            .with_secure(self.secure)
+            .with_port(self.port)
+            .with_path(self.path.as_deref().unwrap_or(""));
+        if let Some(ref token) = self.token {
+            profile = profile.with_token(&**token);
+        }
+        if let (Some(username), Some(password)) = (&self.username, &self.password) {
+            profile = profile.with_login(&**username, &**password);
+        }
+        profile
+    }
+}
+
+impl fmt::Debug for ServerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // credentials are left out, so configs can be logged.
+        f.debug_struct("ServerConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("secure", &self.secure)
+            .field("token", &self.token.as_ref().map(|_| ".."))
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| ".."))
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl UnifiedConfig {
+    /// Finds and reads the unified config file.
+    ///
+    /// The file given by the `SCREEPS_CONFIG` environment variable is used if set. Otherwise the
+    /// first of these which exists is used, each with either a `.yaml` or `.yml` extension:
+    ///
+    /// - `.screeps.yaml` or `screeps.yaml` in the current directory
+    /// - `$XDG_CONFIG_HOME/screeps/config.yaml`, defaulting to `~/.config/screeps/config.yaml`
+    /// - `~/.screeps.yaml`
+    /// - `%APPDATA%/screeps/config.yaml`, on Windows
+    pub fn load() -> Result<Self, ConfigError> {
+        let current_dir = env::current_dir().unwrap_or_default();
+        let searched = search_paths(|var| env::var_os(var), &current_dir);
+
+        match searched.iter().find(|path| path.is_file()) {
+            Some(path) => UnifiedConfig::from_path(path),
+            None => Err(ConfigError::NotFound { searched }),
+        }
+    }
+
+    /// Reads a unified config file at the given path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|err| ConfigError::Io {
+            err,
+            path: path.to_owned(),
+        })?;
+
+        let mut config: UnifiedConfig =
+            serde_yaml::from_str(&text).map_err(|err| ConfigError::Yaml {
+                err,
+                path: Some(path.to_owned()),
+            })?;
+        config.path = Some(path.to_owned());
+        Ok(config)
+    }
+
+    /// Parses the contents of a unified config file.
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        serde_yaml::from_str(text).map_err(|err| ConfigError::Yaml { err, path: None })
+    }
+
+    /// Gets the server with the given name.
+    pub fn server(&self, name: &str) -> Option<&ServerConfig> {
+        self.servers.get(name)
+    }
+
+    /// Creates a profile for the server with the given name.
+    pub fn profile(&self, name: &str) -> Result<ServerProfile, ConfigError> {
+        match self.server(name) {
+            Some(server) => Ok(server.profile()),
+            None => Err(ConfigError::UnknownServer {
+                name: name.to_owned(),
+            }),
+        }
+    }
+
+    /// Gets the configuration of the tool with the given name.
+    pub fn app_config(&self, app: &str) -> Option<&serde_yaml::Value> {
+        self.configs.get(app)
+    }
+}
+
+/// Lists the paths a unified config file may be at, in order of preference.
+fn search_paths<F>(var: F, current_dir: &Path) -> Vec<PathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    if let Some(path) = var("SCREEPS_CONFIG") {
+        return vec![path.into()];
+    }
+
+    let mut bases = vec![current_dir.join(".screeps"), current_dir.join("screeps")];
+    let home = var("HOME")
+        .or_else(|| var("USERPROFILE"))
+        .map(PathBuf::from);
+    match (var("XDG_CONFIG_HOME"), &home) {
+        (Some(config), _) => bases.push(PathBuf::from(config).join("screeps").join("config")),
+        (None, Some(home)) => bases.push(home.join(".config").join("screeps").join("config")),
+        (None, None) => (),
+    }
+    if let Some(ref home) = home {
+        bases.push(home.join(".screeps"));
+    }
+    if let Some(app_data) = var("APPDATA") {
+        bases.push(PathBuf::from(app_data).join("screeps").join("config"));
+    }
+
+    bases
+        .into_iter()
+        .flat_map(|base| vec![base.with_extension("yaml"), base.with_extension("yml")])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsString, fs, path::PathBuf};
+
+    use super::{search_paths, ConfigError, UnifiedConfig};
+    use crate::ServerProfile;
+
+    const SAMPLE: &str = "
+servers:
+  main:
+    host: screeps.com
+    secure: true
+    token: 3bdd1da7-3002-4aaa-be91-330562f54093
+  ptr:
+    host: screeps.com
+    secure: true
+    token: 3bdd1da7-3002-4aaa-be91-330562f54093
+    path: /ptr
+  splus1:
+    host: server1.screepspl.us
+    port: 443
+    secure: true
+    username: bob
+    password: tsk
+  local:
+    host: 127.0.0.1
+    port: 21025
+configs:
+  screeps-multimeter:
+    console_lines: 100
+";
+
+    #[test]
+    fn parse_sample() {
+        let config = UnifiedConfig::parse(SAMPLE).unwrap();
+
+        assert_eq!(
+            config.profile("main").unwrap(),
+            ServerProfile::mmo().with_token("3bdd1da7-3002-4aaa-be91-330562f54093")
+        );
+        assert_eq!(
+            config.profile("ptr").unwrap(),
+            ServerProfile::ptr().with_token("3bdd1da7-3002-4aaa-be91-330562f54093")
+        );
+        assert_eq!(
+            config
+                .profile("splus1")
+                .unwrap()
+                .api_url()
+                .unwrap()
+                .as_str(),
+            "https://server1.screepspl.us/api/"
+        );
+        assert_eq!(config.profile("splus1").unwrap().username(), Some("bob"));
+        assert_eq!(
+            config.profile("local").unwrap(),
+            ServerProfile::private("127.0.0.1")
+        );
+        match config.profile("season") {
+            Err(ConfigError::UnknownServer { name }) => assert_eq!(name, "season"),
+            other => panic!("expected unknown server error, found {:?}", other),
+        }
+
+        let multimeter = config.app_config("screeps-multimeter").unwrap();
+        assert_eq!(multimeter["console_lines"].as_u64(), Some(100));
+
+        let debug = format!("{:?}", config);
+        assert!(!debug.contains("tsk"), "{}", debug);
+        assert!(!debug.contains("3bdd1da7"), "{}", debug);
+    }
+
+    #[test]
+    fn search_order() {
+        let current_dir = PathBuf::from("/work");
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| OsString::from(value))
+            }
+        };
+
+        assert_eq!(
+            search_paths(
+                env(&[("SCREEPS_CONFIG", "/etc/screeps.yaml")]),
+                &current_dir
+            ),
+            vec![PathBuf::from("/etc/screeps.yaml")]
+        );
+
+        let paths = search_paths(env(&[("HOME", "/home/bob")]), &current_dir);
+        let expected = [
+            "/work/.screeps.yaml",
+            "/work/.screeps.yml",
+            "/work/screeps.yaml",
+            "/work/screeps.yml",
+            "/home/bob/.config/screeps/config.yaml",
+            "/home/bob/.config/screeps/config.yml",
+            "/home/bob/.screeps.yaml",
+            "/home/bob/.screeps.yml",
+        ];
+        assert_eq!(
+            paths,
+            expected.iter().map(PathBuf::from).collect::<Vec<_>>()
+        );
+
+        let paths = search_paths(
+            env(&[("HOME", "/home/bob"), ("XDG_CONFIG_HOME", "/cfg")]),
+            &current_dir,
+        );
+        assert_eq!(paths[4], PathBuf::from("/cfg/screeps/config.yaml"));
+    }
+
+    #[test]
+    fn read_from_path() {
+        let path = std::env::temp_dir().join(format!(
+            "screeps-api-unified-config-{}.yaml",
+            std::process::id()
+        ));
+        fs::write(&path, SAMPLE).unwrap();
+        let result = UnifiedConfig::from_path(&path);
+        fs::remove_file(&path).unwrap();
+
+        let config = result.unwrap();
+        assert_eq!(config.path.as_ref(), Some(&path));
+        assert_eq!(config.servers.len(), 4);
+
+        match UnifiedConfig::from_path(&path) {
+            Err(ConfigError::Io { .. }) => (),
+            other => panic!("expected io error, found {:?}", other),
+        }
+        assert!(UnifiedConfig::parse("servers: [1, 2]").is_err());
+    }
+}
@@ -4,6 +4,7 @@ use std::{borrow::Cow, fmt};
 use crate::RoomName;
 
 /// Different channels one can subscribe to.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Channel<'a> {
     /// Server messages (TODO: find message here).
     ServerMessages,
@@ -46,6 +47,14 @@ pub enum Channel<'a> {
         /// The user ID of the subscription.
         user_id: Cow<'a, str>,
     },
+    /// Map visual updates. Updates at the end of every tick with everything the user's code drew
+    /// with `Game.map.visual` on one shard.
+    MapVisual {
+        /// The user ID of the subscription.
+        user_id: Cow<'a, str>,
+        /// The shard the visuals were drawn on.
+        shard_name: Cow<'a, str>,
+    },
     /// Room overview updates. Updates at the end of every tick with all room positions for each nondescript
     /// type of structure (road, wall, energy, or player owned).
     RoomMapView {
@@ -195,6 +204,18 @@ impl<'a> Channel<'a> {
         }
     }
 
+    /// Creates a channel subscribing to the map visuals a user draws on a shard.
+    pub fn map_visual<T, U>(user_id: T, shard_name: U) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+        U: Into<Cow<'a, str>>,
+    {
+        Channel::MapVisual {
+            user_id: user_id.into(),
+            shard_name: shard_name.into(),
+        }
+    }
+
     /// Creates a channel using the fully specified channel name.
     pub fn other<T: Into<Cow<'a, str>>>(channel: T) -> Self {
         Channel::Other {
@@ -222,6 +243,10 @@ impl<'a> fmt::Display for Channel<'a> {
             Channel::UserActiveBranch { ref user_id } => {
                 write!(f, "user:{}/set-active-branch", user_id)
             }
+            Channel::MapVisual {
+                ref user_id,
+                ref shard_name,
+            } => write!(f, "mapVisual:{}/{}", user_id, shard_name),
             Channel::RoomMapView {
                 ref room_name,
                 ref shard_name,
@@ -0,0 +1,378 @@
+//! Combat records built from room object action logs.
+//!
+//! Creeps and towers record where they attacked and healed each tick in their action logs, but
+//! only as positions. [`CombatLog`] resolves those positions to the objects at them using a
+//! [`RoomState`], and adds records for hits lost found in the state's [`RoomEvent`]s. The records
+//! can be written out as JSON lines for after-action reviews.
+//!
+//! [`CombatLog`]: struct.CombatLog.html
+//! [`RoomState`]: ../struct.RoomState.html
+//! [`RoomEvent`]: ../enum.RoomEvent.html
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::RoomName;
+
+use super::{
+    objects::{ActionLogTarget, KnownRoomObject, RoomObject},
+    RoomEvent, RoomState,
+};
+
+/// An object taking part in combat.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CombatParticipant {
+    /// The object's id.
+    pub id: String,
+    /// The object's type, as the server names it.
+    pub kind: String,
+    /// The user ID of the object's owner, if it is owned.
+    pub user: Option<String>,
+}
+
+impl CombatParticipant {
+    fn new(obj: &RoomObject) -> Self {
+        CombatParticipant {
+            id: obj.id().to_owned(),
+            kind: obj.kind().to_owned(),
+            user: obj
+                .known()
+                .and_then(KnownRoomObject::user)
+                .map(str::to_owned),
+        }
+    }
+}
+
+/// Something that happened in combat.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum CombatAction {
+    /// A creep or tower attacked a position.
+    Attacked {
+        /// The attacking object.
+        attacker: CombatParticipant,
+        /// The object at the attacked position, if any is left.
+        target: Option<CombatParticipant>,
+        /// The x position attacked.
+        x: u32,
+        /// The y position attacked.
+        y: u32,
+        /// Whether this was a ranged attack. Tower attacks are always ranged.
+        ranged: bool,
+    },
+    /// A creep made a ranged mass attack, hitting everything hostile in range.
+    MassAttacked {
+        /// The attacking creep.
+        attacker: CombatParticipant,
+    },
+    /// A creep or tower healed a position.
+    Healed {
+        /// The healing object.
+        healer: CombatParticipant,
+        /// The object at the healed position, if any is left.
+        target: Option<CombatParticipant>,
+        /// The x position healed.
+        x: u32,
+        /// The y position healed.
+        y: u32,
+        /// Whether this was a ranged heal. Tower heals are always ranged.
+        ranged: bool,
+    },
+    /// An object lost hits. This includes natural decay of roads, ramparts and containers.
+    Damaged {
+        /// The damaged object.
+        target: CombatParticipant,
+        /// The number of hits lost.
+        amount: i32,
+    },
+}
+
+/// A single combat record for one tick in one room.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CombatRecord {
+    /// The game time of the update this was found in, if known.
+    pub game_time: Option<u32>,
+    /// The room this happened in.
+    pub room_name: RoomName,
+    /// What happened.
+    #[serde(flatten)]
+    pub action: CombatAction,
+}
+
+/// Log of combat in rooms tracked with [`RoomState`].
+///
+/// After applying each room update to a `RoomState`, pass the state and the events it returned
+/// to [`CombatLog::record_tick`].
+///
+/// [`RoomState`]: ../struct.RoomState.html
+/// [`CombatLog::record_tick`]: struct.CombatLog.html#method.record_tick
+#[derive(Clone, Debug, Default)]
+pub struct CombatLog {
+    records: Vec<CombatRecord>,
+}
+
+impl CombatLog {
+    /// Creates an empty combat log.
+    pub fn new() -> Self {
+        CombatLog::default()
+    }
+
+    /// All records logged so far, oldest first.
+    pub fn records(&self) -> &[CombatRecord] {
+        &self.records
+    }
+
+    /// Removes all records logged so far.
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    /// Logs combat for the tick `state` was just updated to, and returns the new records.
+    ///
+    /// `events` should be the events returned by the update.
+    pub fn record_tick(&mut self, state: &RoomState, events: &[RoomEvent]) -> &[CombatRecord] {
+        let start = self.records.len();
+        self.records.extend(CombatLog::tick_records(state, events));
+        &self.records[start..]
+    }
+
+    /// Builds the combat records for the tick `state` was just updated to, without logging them.
+    pub fn tick_records(state: &RoomState, events: &[RoomEvent]) -> Vec<CombatRecord> {
+        let record = |action| CombatRecord {
+            game_time: state.game_time(),
+            room_name: state.room_name(),
+            action,
+        };
+        let attacked = |attacker: &RoomObject, target: &ActionLogTarget, ranged| {
+            record(CombatAction::Attacked {
+                attacker: CombatParticipant::new(attacker),
+                target: target_at(state, target),
+                x: target.x,
+                y: target.y,
+                ranged,
+            })
+        };
+        let healed = |healer: &RoomObject, target: &ActionLogTarget, ranged| {
+            record(CombatAction::Healed {
+                healer: CombatParticipant::new(healer),
+                target: target_at(state, target),
+                x: target.x,
+                y: target.y,
+                ranged,
+            })
+        };
+
+        let mut actors = state.objects().values().collect::<Vec<_>>();
+        actors.sort_by(|a, b| a.id().cmp(b.id()));
+
+        let mut records = Vec::new();
+        for obj in actors {
+            match obj.known() {
+                Some(KnownRoomObject::Creep(creep)) => {
+                    let log = &creep.action_log;
+                    if let Some(ref target) = log.attack {
+                        records.push(attacked(obj, target, false));
+                    }
+                    if let Some(ref target) = log.ranged_attack {
+                        records.push(attacked(obj, target, true));
+                    }
+                    if log.ranged_mass_attack.is_some() {
+                        records.push(record(CombatAction::MassAttacked {
+                            attacker: CombatParticipant::new(obj),
+                        }));
+                    }
+                    if let Some(ref target) = log.heal {
+                        records.push(healed(obj, target, false));
+                    }
+                    if let Some(ref target) = log.ranged_heal {
+                        records.push(healed(obj, target, true));
+                    }
+                }
+                Some(KnownRoomObject::Tower(tower)) => {
+                    if let Some(ref target) = tower.action_log.attack {
+                        records.push(attacked(obj, target, true));
+                    }
+                    if let Some(ref target) = tower.action_log.heal {
+                        records.push(healed(obj, target, true));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        for event in events {
+            if let RoomEvent::HitsChanged { ref id, old, new } = *event {
+                if new < old {
+                    if let Some(obj) = state.objects().get(id) {
+                        records.push(record(CombatAction::Damaged {
+                            target: CombatParticipant::new(obj),
+                            amount: old - new,
+                        }));
+                    }
+                }
+            }
+        }
+
+        records
+    }
+
+    /// Writes all records logged so far as JSON, one record per line.
+    pub fn write_json_lines<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for record in &self.records {
+            serde_json::to_writer(&mut writer, record)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds the object most likely targeted at a position: a creep if there is one, then a rampart,
+/// then any other object with hits.
+fn target_at(state: &RoomState, target: &ActionLogTarget) -> Option<CombatParticipant> {
+    state
+        .objects()
+        .values()
+        .filter(|obj| obj.x() == target.x && obj.y() == target.y)
+        .filter_map(|obj| {
+            let priority = match obj.known()? {
+                KnownRoomObject::Creep(_) | KnownRoomObject::PowerCreep(_) => 0,
+                KnownRoomObject::Rampart(_) => 1,
+                other => {
+                    other.hits()?;
+                    2
+                }
+            };
+            Some((priority, obj))
+        })
+        .min_by(|(p1, a), (p2, b)| p1.cmp(p2).then_with(|| a.id().cmp(b.id())))
+        .map(|(_, obj)| CombatParticipant::new(obj))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::websocket::RoomUpdate;
+
+    fn update(json: serde_json::Value) -> RoomUpdate {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn creep(id: &str, user: &str, x: u32, y: u32) -> serde_json::Value {
+        json!({
+            "_id": id, "type": "creep", "room": "E1N1", "x": x, "y": y,
+            "name": id, "user": user, "body": [], "hits": 100,
+            "hitsMax": 100, "fatigue": 0, "spawning": false, "notifyWhenAttacked": true,
+            "ageTime": 1610, "store": {}, "storeCapacity": 0, "actionLog": {}
+        })
+    }
+
+    fn combat_state() -> RoomState {
+        let mut state = RoomState::new(RoomName::new("E1N1").unwrap(), Some("shard0"));
+        state.apply(&update(json!({
+            "gameTime": 500,
+            "info": {"mode": "world"},
+            "objects": {
+                "attacker": creep("attacker", "hostile", 10, 10),
+                "defender": creep("defender", "owner", 11, 10),
+                "rampart": {
+                    "_id": "rampart", "type": "rampart", "room": "E1N1", "x": 11, "y": 10,
+                    "user": "owner", "hits": 10000, "hitsMax": 300000,
+                    "notifyWhenAttacked": true, "isPublic": false, "nextDecayTime": 600
+                },
+                "tower": {
+                    "_id": "tower", "type": "tower", "room": "E1N1", "x": 15, "y": 15,
+                    "user": "owner", "hits": 3000, "hitsMax": 3000, "notifyWhenAttacked": true,
+                    "off": false, "store": {"energy": 1000},
+                    "storeCapacityResource": {"energy": 1000},
+                    "actionLog": {"attack": null, "heal": null, "repair": null}
+                }
+            }
+        })));
+        state
+    }
+
+    #[test]
+    fn resolves_action_log_targets() {
+        let mut state = combat_state();
+        let events = state.apply(&update(json!({
+            "gameTime": 501,
+            "info": {"mode": "world"},
+            "objects": {
+                "attacker": {"actionLog": {"attack": {"x": 11, "y": 10}}},
+                "rampart": {"hits": 9700},
+                "tower": {"actionLog": {"heal": {"x": 11, "y": 10}}}
+            }
+        })));
+
+        let mut log = CombatLog::new();
+        let records = log.record_tick(&state, &events).to_vec();
+
+        let participant = |id: &str, kind: &str, user: &str| CombatParticipant {
+            id: id.to_owned(),
+            kind: kind.to_owned(),
+            user: Some(user.to_owned()),
+        };
+        let actions = records.into_iter().map(|r| r.action).collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![
+                CombatAction::Attacked {
+                    attacker: participant("attacker", "creep", "hostile"),
+                    target: Some(participant("defender", "creep", "owner")),
+                    x: 11,
+                    y: 10,
+                    ranged: false,
+                },
+                CombatAction::Healed {
+                    healer: participant("tower", "tower", "owner"),
+                    target: Some(participant("defender", "creep", "owner")),
+                    x: 11,
+                    y: 10,
+                    ranged: true,
+                },
+                CombatAction::Damaged {
+                    target: participant("rampart", "rampart", "owner"),
+                    amount: 300,
+                },
+            ]
+        );
+        assert_eq!(log.records().len(), 3);
+        assert!(log.records().iter().all(|r| r.game_time == Some(501)));
+    }
+
+    #[test]
+    fn write_and_read_json_lines() {
+        let mut state = combat_state();
+        let events = state.apply(&update(json!({
+            "gameTime": 501,
+            "info": {"mode": "world"},
+            "objects": {
+                "attacker": {"actionLog": {"rangedMassAttack": {"x": 10, "y": 10}}},
+                "tower": {"actionLog": {"attack": {"x": 30, "y": 30}}}
+            }
+        })));
+        let mut log = CombatLog::new();
+        log.record_tick(&state, &events);
+
+        let mut out = Vec::new();
+        log.write_json_lines(&mut out).unwrap();
+        let lines = String::from_utf8(out).unwrap();
+
+        let read = lines
+            .lines()
+            .map(|line| serde_json::from_str::<CombatRecord>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(read, log.records());
+        assert_eq!(read.len(), 2);
+        match read[1].action {
+            CombatAction::Attacked {
+                ref target, x, y, ..
+            } => assert_eq!((target, x, y), (&None, 30, 30)),
+            ref other => panic!("expected tower attack, found {:?}", other),
+        }
+        let first: serde_json::Value = serde_json::from_str(lines.lines().next().unwrap()).unwrap();
+        assert_eq!(first["action"], "massAttacked");
+        assert_eq!(first["gameTime"], 501);
+    }
+}
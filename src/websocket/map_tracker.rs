@@ -0,0 +1,380 @@
+//! Aggregation of room map view updates over many rooms.
+//!
+//! [`WorldMapTracker`] keeps the latest [`RoomMapViewUpdate`] for every tracked room on a shard,
+//! and reports when power banks or deposits appear in highway rooms.
+//!
+//! Like [`RoomDetailRotator`], the tracker does no networking: subscribe to the channels it
+//! returns, and feed it every [`ChannelUpdate`] received.
+//!
+//! [`WorldMapTracker`]: struct.WorldMapTracker.html
+//! [`RoomMapViewUpdate`]: ../struct.RoomMapViewUpdate.html
+//! [`RoomDetailRotator`]: ../struct.RoomDetailRotator.html
+//! [`ChannelUpdate`]: ../enum.ChannelUpdate.html
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::RoomName;
+
+use super::{Channel, ChannelUpdate, RoomMapViewUpdate};
+
+/// The latest map view of a single room.
+#[derive(Clone, Debug)]
+pub struct MapRoomState {
+    /// The room.
+    pub room_name: RoomName,
+    /// The game time this view was received at.
+    pub seen_at: u32,
+    /// The map view.
+    pub view: RoomMapViewUpdate,
+}
+
+impl MapRoomState {
+    /// Whether this room currently has a power bank or dropped power.
+    pub fn has_power(&self) -> bool {
+        !self.view.power_or_power_bank.is_empty()
+    }
+
+    /// Whether this room currently has a deposit.
+    pub fn has_deposits(&self) -> bool {
+        !self.view.deposits.is_empty()
+    }
+
+    /// The positions of all objects owned by the given user in this room.
+    pub fn user_objects(&self, user_id: &str) -> Option<&[(u32, u32)]> {
+        self.view
+            .users_objects
+            .iter()
+            .find(|(id, _)| id == user_id)
+            .map(|(_, objects)| &**objects)
+    }
+}
+
+/// Notable changes found by [`WorldMapTracker::handle_update`].
+///
+/// [`WorldMapTracker::handle_update`]: struct.WorldMapTracker.html#method.handle_update
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MapEvent {
+    /// Power appeared at new positions in a highway room.
+    PowerBankAppeared {
+        /// The room.
+        room_name: RoomName,
+        /// The game time it was first seen.
+        seen_at: u32,
+        /// The positions with power which were not there in the previous view.
+        positions: Vec<(u32, u32)>,
+    },
+    /// All power in a highway room is gone.
+    PowerBankGone {
+        /// The room.
+        room_name: RoomName,
+        /// The game time it was first seen missing.
+        seen_at: u32,
+    },
+    /// Deposits appeared at new positions in a highway room.
+    DepositAppeared {
+        /// The room.
+        room_name: RoomName,
+        /// The game time it was first seen.
+        seen_at: u32,
+        /// The deposit positions which were not there in the previous view.
+        positions: Vec<(u32, u32)>,
+    },
+}
+
+/// Tracker of map views for a region of rooms on one shard.
+#[derive(Clone, Debug)]
+pub struct WorldMapTracker {
+    shard_name: Option<String>,
+    tracked: Vec<RoomName>,
+    rooms: HashMap<RoomName, MapRoomState>,
+}
+
+impl WorldMapTracker {
+    /// Creates a tracker for rooms on the given shard, or on a non-sharded server if `None`.
+    pub fn new<'a, T: Into<Cow<'a, str>>>(shard_name: Option<T>) -> Self {
+        WorldMapTracker {
+            shard_name: shard_name.map(|s| s.into().into_owned()),
+            tracked: Vec::new(),
+            rooms: HashMap::new(),
+        }
+    }
+
+    fn channel(&self, room_name: RoomName) -> Channel<'static> {
+        Channel::room_map_view(room_name, self.shard_name.clone())
+    }
+
+    /// Starts tracking a room. Returns the channel to subscribe to, or `None` if the room was
+    /// already tracked.
+    pub fn track(&mut self, room_name: RoomName) -> Option<Channel<'static>> {
+        if self.tracked.contains(&room_name) {
+            return None;
+        }
+        self.tracked.push(room_name);
+
+        Some(self.channel(room_name))
+    }
+
+    /// Starts tracking every room in the rectangle with the given corners, inclusive. Returns
+    /// the channels to subscribe to for rooms which were not already tracked.
+    pub fn track_region(&mut self, corner1: RoomName, corner2: RoomName) -> Vec<Channel<'static>> {
+        let (min_x, max_x) = min_max(corner1.x_coord, corner2.x_coord);
+        let (min_y, max_y) = min_max(corner1.y_coord, corner2.y_coord);
+
+        let mut channels = Vec::new();
+        for y_coord in (min_y..=max_y).rev() {
+            for x_coord in min_x..=max_x {
+                channels.extend(self.track(RoomName { x_coord, y_coord }));
+            }
+        }
+        channels
+    }
+
+    /// Stops tracking a room and forgets its last view. Returns the channel to unsubscribe
+    /// from, or `None` if the room wasn't tracked.
+    pub fn untrack(&mut self, room_name: RoomName) -> Option<Channel<'static>> {
+        let index = self.tracked.iter().position(|&r| r == room_name)?;
+        self.tracked.remove(index);
+        self.rooms.remove(&room_name);
+
+        Some(self.channel(room_name))
+    }
+
+    /// All channels for tracked rooms, for instance to re-subscribe after reconnecting.
+    pub fn channels(&self) -> Vec<Channel<'static>> {
+        self.tracked.iter().map(|&r| self.channel(r)).collect()
+    }
+
+    /// Records a channel update received at the given game time. Map view updates for tracked
+    /// rooms on this tracker's shard replace the room's last view; all other updates are
+    /// ignored.
+    ///
+    /// Returns any power bank or deposit changes in highway rooms.
+    pub fn handle_update(&mut self, update: &ChannelUpdate, game_time: u32) -> Vec<MapEvent> {
+        let (room_name, view) = match *update {
+            ChannelUpdate::RoomMapView {
+                room_name,
+                ref shard_name,
+                ref update,
+            } if *shard_name == self.shard_name && self.tracked.contains(&room_name) => {
+                (room_name, update)
+            }
+            _ => return Vec::new(),
+        };
+
+        let mut events = Vec::new();
+
+        if room_name.is_highway() {
+            let previous = self.rooms.get(&room_name).map(|state| &state.view);
+
+            let new_power = new_positions(
+                &view.power_or_power_bank,
+                previous.map(|v| &*v.power_or_power_bank),
+            );
+            if !new_power.is_empty() {
+                events.push(MapEvent::PowerBankAppeared {
+                    room_name,
+                    seen_at: game_time,
+                    positions: new_power,
+                });
+            } else if view.power_or_power_bank.is_empty()
+                && previous.iter().any(|v| !v.power_or_power_bank.is_empty())
+            {
+                events.push(MapEvent::PowerBankGone {
+                    room_name,
+                    seen_at: game_time,
+                });
+            }
+
+            let new_deposits = new_positions(&view.deposits, previous.map(|v| &*v.deposits));
+            if !new_deposits.is_empty() {
+                events.push(MapEvent::DepositAppeared {
+                    room_name,
+                    seen_at: game_time,
+                    positions: new_deposits,
+                });
+            }
+        }
+
+        self.rooms.insert(
+            room_name,
+            MapRoomState {
+                room_name,
+                seen_at: game_time,
+                view: view.clone(),
+            },
+        );
+
+        events
+    }
+
+    /// Gets the latest view of a room, if it has been seen.
+    pub fn room(&self, room_name: RoomName) -> Option<&MapRoomState> {
+        self.rooms.get(&room_name)
+    }
+
+    /// Iterates over the latest views of all rooms seen.
+    pub fn rooms(&self) -> impl Iterator<Item = &MapRoomState> {
+        self.rooms.values()
+    }
+
+    /// Iterates over all rooms which had power banks or dropped power when last seen.
+    pub fn rooms_with_power(&self) -> impl Iterator<Item = &MapRoomState> {
+        self.rooms().filter(|state| state.has_power())
+    }
+
+    /// Iterates over all rooms which had deposits when last seen.
+    pub fn rooms_with_deposits(&self) -> impl Iterator<Item = &MapRoomState> {
+        self.rooms().filter(|state| state.has_deposits())
+    }
+
+    /// Iterates over all rooms where the given user had objects when last seen.
+    pub fn rooms_with_user<'a>(
+        &'a self,
+        user_id: &'a str,
+    ) -> impl Iterator<Item = &'a MapRoomState> + 'a {
+        self.rooms()
+            .filter(move |state| state.user_objects(user_id).is_some())
+    }
+}
+
+fn min_max(a: i32, b: i32) -> (i32, i32) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+fn new_positions(current: &[(u32, u32)], previous: Option<&[(u32, u32)]>) -> Vec<(u32, u32)> {
+    let previous = previous.unwrap_or(&[]);
+    current
+        .iter()
+        .filter(|pos| !previous.contains(pos))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn room(name: &str) -> RoomName {
+        RoomName::new(name).unwrap()
+    }
+
+    fn map_update(name: &str, body: &str) -> ChannelUpdate<'static> {
+        serde_json::from_str(&format!(r#"["roomMap2:shard0/{}",{}]"#, name, body)).unwrap()
+    }
+
+    #[test]
+    fn track_region_subscribes_to_every_room() {
+        let mut tracker = WorldMapTracker::new(Some("shard0"));
+
+        let channels = tracker
+            .track_region(room("W1N1"), room("E0N0"))
+            .into_iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            channels,
+            vec![
+                "roomMap2:shard0/W1N1",
+                "roomMap2:shard0/W0N1",
+                "roomMap2:shard0/E0N1",
+                "roomMap2:shard0/W1N0",
+                "roomMap2:shard0/W0N0",
+                "roomMap2:shard0/E0N0",
+            ]
+        );
+        assert!(tracker.track(room("E0N0")).is_none());
+        assert_eq!(tracker.channels().len(), 6);
+    }
+
+    #[test]
+    fn power_bank_events_in_highways() {
+        let mut tracker = WorldMapTracker::new(Some("shard0"));
+        tracker.track(room("E10N5"));
+        tracker.track(room("E11N5"));
+
+        let events = tracker.handle_update(&map_update("E10N5", r#"{"pb":[],"d":[[5,5]]}"#), 100);
+        assert_eq!(
+            events,
+            vec![MapEvent::DepositAppeared {
+                room_name: room("E10N5"),
+                seen_at: 100,
+                positions: vec![(5, 5)],
+            }]
+        );
+
+        let events =
+            tracker.handle_update(&map_update("E10N5", r#"{"pb":[[20,21]],"d":[[5,5]]}"#), 101);
+        assert_eq!(
+            events,
+            vec![MapEvent::PowerBankAppeared {
+                room_name: room("E10N5"),
+                seen_at: 101,
+                positions: vec![(20, 21)],
+            }]
+        );
+
+        // same power bank: nothing new.
+        let events = tracker.handle_update(&map_update("E10N5", r#"{"pb":[[20,21]]}"#), 102);
+        assert!(events.is_empty());
+
+        let events = tracker.handle_update(&map_update("E10N5", r#"{"pb":[]}"#), 103);
+        assert_eq!(
+            events,
+            vec![MapEvent::PowerBankGone {
+                room_name: room("E10N5"),
+                seen_at: 103,
+            }]
+        );
+
+        // not a highway room.
+        let events = tracker.handle_update(&map_update("E11N5", r#"{"pb":[[1,1]]}"#), 104);
+        assert!(events.is_empty());
+        assert_eq!(tracker.room(room("E11N5")).unwrap().seen_at, 104);
+    }
+
+    #[test]
+    fn queries() {
+        let mut tracker = WorldMapTracker::new(Some("shard0"));
+        tracker.track_region(room("E1N1"), room("E3N1"));
+        // untracked rooms and other shards are ignored.
+        tracker.handle_update(&map_update("E9N9", r#"{"pb":[[1,1]]}"#), 1);
+        let other_shard: ChannelUpdate =
+            serde_json::from_str(r#"["roomMap2:shard1/E1N1",{"pb":[[1,1]]}]"#).unwrap();
+        tracker.handle_update(&other_shard, 1);
+        assert_eq!(tracker.rooms().count(), 0);
+
+        tracker.handle_update(
+            &map_update("E1N1", r#"{"pb":[[1,1]],"abc":[[2,2],[3,3]]}"#),
+            5,
+        );
+        tracker.handle_update(&map_update("E2N1", r#"{"d":[[4,4]],"def":[[9,9]]}"#), 5);
+        tracker.handle_update(&map_update("E3N1", r#"{"abc":[[7,7]]}"#), 6);
+
+        let mut with_user = tracker
+            .rooms_with_user("abc")
+            .map(|s| s.room_name.to_string())
+            .collect::<Vec<_>>();
+        with_user.sort();
+        assert_eq!(with_user, vec!["E1N1", "E3N1"]);
+
+        assert_eq!(
+            tracker.rooms_with_power().next().unwrap().room_name,
+            room("E1N1")
+        );
+        assert_eq!(
+            tracker.rooms_with_deposits().next().unwrap().room_name,
+            room("E2N1")
+        );
+        assert_eq!(
+            tracker.room(room("E1N1")).unwrap().user_objects("abc"),
+            Some(&[(2, 2), (3, 3)][..])
+        );
+
+        assert!(tracker.untrack(room("E1N1")).is_some());
+        assert!(tracker.room(room("E1N1")).is_none());
+    }
+}
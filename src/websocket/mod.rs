@@ -1,13 +1,23 @@
 //! Handling of socket connections to screeps using ws-rs as a backend.
 mod channel;
+mod combat_log;
 pub mod commands;
 mod connecting;
+mod map_tracker;
 mod parsing;
+mod recording;
+mod room_state;
+mod rotator;
 mod types;
 
 pub use self::{
     channel::Channel,
-    connecting::{default_url, transform_url},
+    combat_log::{CombatAction, CombatLog, CombatParticipant, CombatRecord},
+    connecting::{default_url, transform_url, UrlError},
+    map_tracker::{MapEvent, MapRoomState, WorldMapTracker},
     parsing::*,
+    recording::{FrameDirection, RecordedFrame, ReplaySpeed, SessionRecorder, SessionReplayer},
+    room_state::{RoomEvent, RoomState},
+    rotator::{RoomDetailRotator, RotationChanges, WatchedRoom, ROOM_DETAIL_SUBSCRIPTION_LIMIT},
     types::*,
 };
@@ -0,0 +1,211 @@
+//! Encoding messages for Screeps websockets, the reverse of parsing.
+//!
+//! Each encoder produces the exact string a Screeps server sends, so that parsing the result
+//! gives back the encoded value.
+use std::io::{Read, Write};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+
+use super::{
+    ParseError, ScreepsMessage, SockjsMessage, AUTH_FAILED, AUTH_OK, AUTH_PREFIX, PACKAGE_PREFIX,
+    PROTOCOL_PREFIX, TIME_PREFIX,
+};
+
+/// Prefix of compressed messages, which are followed by base64-encoded zlib data.
+pub(super) const GZIP_PREFIX: &str = "gz:";
+
+/// Compresses a raw Screeps message the way the server compresses large updates: deflated with
+/// zlib, base64-encoded, and prefixed with `gz:`.
+pub fn compress_message(message: &str) -> String {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(message.as_bytes())
+        .expect("expected writing to an in-memory buffer to succeed");
+    let compressed = encoder
+        .finish()
+        .expect("expected writing to an in-memory buffer to succeed");
+
+    format!("{}{}", GZIP_PREFIX, base64::encode(&compressed))
+}
+
+/// Decompresses a message compressed by the server or by [`compress_message`].
+///
+/// [`compress_message`]: fn.compress_message.html
+pub fn decompress_message(message: &str) -> Result<String, ParseError> {
+    if !message.starts_with(GZIP_PREFIX) {
+        return Err(ParseError::Other(format!(
+            "expected compressed message to start with {:?}, found {:?}",
+            GZIP_PREFIX, message
+        )));
+    }
+
+    let compressed = base64::decode(&message[GZIP_PREFIX.len()..]).map_err(|e| {
+        ParseError::Other(format!(
+            "error decoding base64 of compressed message: {}",
+            e
+        ))
+    })?;
+
+    let mut decompressed = String::new();
+    ZlibDecoder::new(&*compressed)
+        .read_to_string(&mut decompressed)
+        .map_err(|e| ParseError::Other(format!("error decompressing message: {}", e)))?;
+
+    Ok(decompressed)
+}
+
+fn json_string<T: serde::Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_string(value).expect("expected serializing websocket messages to succeed")
+}
+
+impl<'a> SockjsMessage<'a> {
+    /// Encodes this message as a SockJS frame, as a server would send it.
+    pub fn encode(&self) -> String {
+        self.encode_with(ScreepsMessage::encode)
+    }
+
+    /// Encodes this message as a SockJS frame, with each Screeps message inside compressed as
+    /// with [`ScreepsMessage::encode_compressed`].
+    ///
+    /// [`ScreepsMessage::encode_compressed`]: enum.ScreepsMessage.html#method.encode_compressed
+    pub fn encode_compressed(&self) -> String {
+        self.encode_with(ScreepsMessage::encode_compressed)
+    }
+
+    fn encode_with<F>(&self, encode_inner: F) -> String
+    where
+        F: Fn(&ScreepsMessage<'a>) -> String,
+    {
+        match *self {
+            SockjsMessage::Open => "o".to_owned(),
+            SockjsMessage::Heartbeat => "h".to_owned(),
+            SockjsMessage::Close { code, ref reason } => {
+                format!("c{}", json_string(&(code, reason)))
+            }
+            SockjsMessage::Message(ref message) => {
+                format!("m{}", json_string(&encode_inner(message)))
+            }
+            SockjsMessage::Messages(ref messages) => format!(
+                "a{}",
+                json_string(&messages.iter().map(encode_inner).collect::<Vec<_>>())
+            ),
+        }
+    }
+}
+
+impl<'a> ScreepsMessage<'a> {
+    /// Encodes this message as the raw string sent inside SockJS frames.
+    pub fn encode(&self) -> String {
+        match *self {
+            ScreepsMessage::AuthFailed => format!("{}{}", AUTH_PREFIX, AUTH_FAILED),
+            ScreepsMessage::AuthOk { ref new_token } => format!(
+                "{}{}{}",
+                AUTH_PREFIX,
+                AUTH_OK,
+                String::from_utf8_lossy(new_token)
+            ),
+            ScreepsMessage::ServerTime { time } => format!("{}{}", TIME_PREFIX, time),
+            ScreepsMessage::ServerProtocol { protocol } => {
+                format!("{}{}", PROTOCOL_PREFIX, protocol)
+            }
+            ScreepsMessage::ServerPackage { package } => format!("{}{}", PACKAGE_PREFIX, package),
+            ScreepsMessage::ChannelUpdate { ref update } => json_string(update),
+            ScreepsMessage::Other(ref message) => message.clone().into_owned(),
+        }
+    }
+
+    /// Encodes this message as a compressed raw string, as the server sends large updates. See
+    /// [`compress_message`].
+    ///
+    /// [`compress_message`]: fn.compress_message.html
+    pub fn encode_compressed(&self) -> String {
+        compress_message(&self.encode())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses a raw message, then checks that encoding it gives back equivalent JSON.
+    fn assert_round_trips(raw: &str) {
+        let message = ScreepsMessage::parse(raw);
+        match message {
+            ScreepsMessage::ChannelUpdate { .. } => (),
+            ref other => panic!("expected channel update parsing {}, found {:?}", raw, other),
+        }
+        let encoded = message.encode();
+
+        let expected: serde_json::Value = serde_json::from_str(raw).unwrap();
+        let found: serde_json::Value = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(found, expected);
+        assert_eq!(ScreepsMessage::parse(&encoded).encode(), encoded);
+    }
+
+    #[test]
+    fn encode_sockjs_frames() {
+        let frames = [
+            "o",
+            "h",
+            r#"c[3000,"Go away!"]"#,
+            r#"m"auth ok 8e2b3c""#,
+            r#"a["time 1500000000000","protocol 14","package 112","auth failed"]"#,
+        ];
+
+        for frame in &frames {
+            assert_eq!(&SockjsMessage::parse(frame).unwrap().encode(), frame);
+        }
+    }
+
+    #[test]
+    fn encode_channel_updates() {
+        let updates = [
+            r#"["roomMap2:shard0/E1N1",{"w":[[1,2]],"r":[],"pb":[],"p":[],"s":[[26,38]],"c":[[30,15]],"m":[[15,7]],"k":[],"d":[],"57874d42d0ae911e3bd15bbc":[[28,26]]}]"#,
+            r#"["room:shard0/E1N1",{"gameTime":100,"info":{"mode":"world"},"objects":{"58a5ffbb3e5e6f6b1b1c4a3e":{"hits":500}},"flags":"Flag1~1~10~25~5","visual":"{\"t\":\"c\",\"x\":1,\"y\":2}\n","users":{"57874d42d0ae911e3bd15bbc":{"_id":"57874d42d0ae911e3bd15bbc","username":"daboross"}}}]"#,
+            r#"["err@room:shard0/E1N1","subscribe limit reached"]"#,
+            r#"["user:57874d42d0ae911e3bd15bbc/cpu",{"cpu":12,"memory":3000}]"#,
+            r#"["user:57874d42d0ae911e3bd15bbc/console",{"messages":{"log":["hi"],"results":["2"]},"shard":"shard0"}]"#,
+            r#"["user:57874d42d0ae911e3bd15bbc/console",{"error":"ReferenceError: x is not defined"}]"#,
+            r#"["user:57874d42d0ae911e3bd15bbc/money",1500.5]"#,
+            r#"["user:57874d42d0ae911e3bd15bbc/newMessage",{"message":{"_id":"5a","outMessage":"5b","text":"hello","type":"in","unread":true,"user":"57874d42d0ae911e3bd15bbc","respondent":"5c"}}]"#,
+            r#"["user:57874d42d0ae911e3bd15bbc/message:5c",{"message":{"_id":"5a","unread":false}}]"#,
+            r#"["user:57874d42d0ae911e3bd15bbc/memory/stats.cpu","{\"used\":12.5}"]"#,
+            r#"["user:57874d42d0ae911e3bd15bbc/memory/missing","undefined"]"#,
+            r#"["user:57874d42d0ae911e3bd15bbc/set-active-branch",{"activeName":"activeWorld","branch":"default"}]"#,
+            r#"["server-message","Server restart in 5 minutes"]"#,
+            r#"["mapVisual:57874d42d0ae911e3bd15bbc/shard0","{\"t\":\"c\",\"x\":25.5,\"y\":12.5,\"n\":\"E1N1\",\"s\":{\"radius\":0.5}}\n"]"#,
+            r#"["some-other-channel",{"anything":[1,2,3]}]"#,
+        ];
+
+        for update in &updates {
+            assert_round_trips(update);
+        }
+    }
+
+    #[test]
+    fn compressed_messages_round_trip() {
+        let raw = r#"["user:57874d42d0ae911e3bd15bbc/cpu",{"cpu":12,"memory":3000}]"#;
+        let compressed = compress_message(raw);
+
+        assert!(compressed.starts_with(GZIP_PREFIX));
+        assert_eq!(decompress_message(&compressed).unwrap(), raw);
+        assert_eq!(ScreepsMessage::parse(&compressed).encode(), raw);
+
+        let frame = SockjsMessage::Messages(vec![
+            ScreepsMessage::parse(raw),
+            ScreepsMessage::ServerTime {
+                time: 1500000000000,
+            },
+        ])
+        .encode_compressed();
+        let parsed = SockjsMessage::parse(&frame).unwrap();
+        assert_eq!(
+            parsed.encode(),
+            format!("a{}", json_string(&[raw, "time 1500000000000"]))
+        );
+        assert_eq!(parsed.encode_compressed(), frame);
+
+        assert!(decompress_message("gz:not base64!").is_err());
+        assert!(decompress_message(raw).is_err());
+    }
+}
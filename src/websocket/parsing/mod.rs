@@ -9,9 +9,13 @@ use serde::{
 use crate::websocket::types::ChannelUpdate;
 use crate::Token;
 
+mod encoding;
 mod error;
 
-pub use self::error::ParseError;
+pub use self::{
+    encoding::{compress_message, decompress_message},
+    error::ParseError,
+};
 
 fn from_str_with_warning<'de, T>(input: &'de str, context: &str) -> Result<T, serde_json::Error>
 where
@@ -214,12 +218,15 @@ const AUTH_FAILED: &str = "failed";
 impl ScreepsMessage<'static> {
     /// Parses the internal message from a SockJS message into a meaningful type.
     pub fn parse<T: AsRef<str> + ?Sized>(message: &T) -> Self {
-        // TODO: deflate with base64 then zlib if the message starts with "gz:".
-
         {
             let message = message.as_ref();
 
-            if message.starts_with(AUTH_PREFIX) {
+            if message.starts_with(encoding::GZIP_PREFIX) {
+                match decompress_message(message) {
+                    Ok(decompressed) => return ScreepsMessage::parse(&decompressed),
+                    Err(e) => warn!("error decompressing message: {}", e),
+                }
+            } else if message.starts_with(AUTH_PREFIX) {
                 let rest = &message[AUTH_PREFIX.len()..];
 
                 return {
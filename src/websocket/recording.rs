@@ -0,0 +1,337 @@
+//! Recording and replaying raw websocket sessions.
+//!
+//! [`SessionRecorder`] writes every raw SockJS frame, with the time since recording started, to
+//! a newline-delimited JSON file. [`SessionReplayer`] reads such a file back and feeds each
+//! incoming frame through [`SockjsMessage::parse`], at real or accelerated speed. Recordings make
+//! parser bugs reproducible and let regression tests run offline.
+//!
+//! [`SessionRecorder`]: struct.SessionRecorder.html
+//! [`SessionReplayer`]: struct.SessionReplayer.html
+//! [`SockjsMessage::parse`]: ../enum.SockjsMessage.html#method.parse
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{ParseError, SockjsMessage};
+
+/// Which way a recorded frame was sent.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameDirection {
+    /// Sent by the server to the client. This is the default for frames recorded without a
+    /// direction.
+    #[default]
+    Incoming,
+    /// Sent by the client to the server.
+    Outgoing,
+}
+
+/// A single raw frame in a recording. Each is stored as one line of JSON.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecordedFrame {
+    /// Milliseconds since the recording started.
+    #[serde(rename = "t")]
+    pub elapsed_ms: u64,
+    /// Which way the frame was sent.
+    #[serde(rename = "dir", default)]
+    pub direction: FrameDirection,
+    /// The raw SockJS frame, exactly as sent over the websocket.
+    pub frame: String,
+}
+
+impl RecordedFrame {
+    /// Parses this frame as a message from the server.
+    pub fn parse(&self) -> Result<SockjsMessage<'_>, ParseError> {
+        SockjsMessage::parse(&self.frame)
+    }
+}
+
+/// Writer for raw websocket session recordings.
+#[derive(Debug)]
+pub struct SessionRecorder<W: Write> {
+    writer: W,
+    started: Instant,
+}
+
+impl SessionRecorder<BufWriter<File>> {
+    /// Creates a recorder writing to a new file at the given path, replacing any existing file.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(SessionRecorder::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> SessionRecorder<W> {
+    /// Creates a recorder writing to the given writer. Timestamps start from now.
+    pub fn new(writer: W) -> Self {
+        SessionRecorder {
+            writer,
+            started: Instant::now(),
+        }
+    }
+
+    /// Records a frame received from the server.
+    pub fn record_incoming(&mut self, frame: &str) -> io::Result<()> {
+        self.record(FrameDirection::Incoming, frame)
+    }
+
+    /// Records a frame sent to the server.
+    pub fn record_outgoing(&mut self, frame: &str) -> io::Result<()> {
+        self.record(FrameDirection::Outgoing, frame)
+    }
+
+    /// Records a frame, timestamped with the time since this recorder was created.
+    pub fn record(&mut self, direction: FrameDirection, frame: &str) -> io::Result<()> {
+        let elapsed = self.started.elapsed();
+        let elapsed_ms = elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis());
+
+        self.write_frame(&RecordedFrame {
+            elapsed_ms,
+            direction,
+            frame: frame.to_owned(),
+        })
+    }
+
+    /// Writes an already timestamped frame.
+    pub fn write_frame(&mut self, frame: &RecordedFrame) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, frame)?;
+        self.writer.write_all(b"\n")
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// How fast a [`SessionReplayer`] replays frames.
+///
+/// [`SessionReplayer`]: struct.SessionReplayer.html
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ReplaySpeed {
+    /// Replay all frames immediately, one after another.
+    Instant,
+    /// Wait between frames as long as the original session did.
+    RealTime,
+    /// Wait between frames the original time divided by the given factor.
+    Accelerated(f64),
+}
+
+impl ReplaySpeed {
+    fn scale(self, original: Duration) -> Option<Duration> {
+        match self {
+            ReplaySpeed::Instant => None,
+            ReplaySpeed::RealTime => Some(original),
+            ReplaySpeed::Accelerated(factor) if factor > 0.0 => Some(
+                // tiny factors overflow `Duration`, so wait as long as we can instead.
+                Duration::try_from_secs_f64(original.as_secs_f64() / factor)
+                    .unwrap_or(Duration::MAX),
+            ),
+            ReplaySpeed::Accelerated(_) => None,
+        }
+    }
+}
+
+/// Reader for raw websocket session recordings.
+///
+/// Iterating over the replayer yields each recorded frame without waiting.
+#[derive(Debug)]
+pub struct SessionReplayer<R: BufRead> {
+    reader: R,
+    line: String,
+}
+
+impl SessionReplayer<BufReader<File>> {
+    /// Opens a recording file at the given path.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(SessionReplayer::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> SessionReplayer<R> {
+    /// Creates a replayer reading a recording from the given reader.
+    pub fn new(reader: R) -> Self {
+        SessionReplayer {
+            reader,
+            line: String::new(),
+        }
+    }
+
+    /// Replays all remaining frames sent by the server, waiting between frames according to
+    /// `speed`.
+    ///
+    /// Each incoming frame is passed to `handler` along with the result of parsing it with
+    /// [`SockjsMessage::parse`]. Outgoing frames only count towards timing.
+    ///
+    /// [`SockjsMessage::parse`]: ../enum.SockjsMessage.html#method.parse
+    pub fn replay<F>(&mut self, speed: ReplaySpeed, mut handler: F) -> io::Result<()>
+    where
+        F: FnMut(&RecordedFrame, Result<SockjsMessage<'_>, ParseError>),
+    {
+        let started = Instant::now();
+
+        for frame in self {
+            let frame = frame?;
+
+            if let Some(wait_until) = speed.scale(Duration::from_millis(frame.elapsed_ms)) {
+                let elapsed = started.elapsed();
+                if wait_until > elapsed {
+                    thread::sleep(wait_until - elapsed);
+                }
+            }
+
+            if frame.direction == FrameDirection::Incoming {
+                handler(&frame, frame.parse());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for SessionReplayer<R> {
+    type Item = io::Result<RecordedFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => {
+                    if self.line.trim().is_empty() {
+                        continue;
+                    }
+                    return Some(serde_json::from_str(&self.line).map_err(io::Error::from));
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        websocket::{MapEvent, ScreepsMessage, WorldMapTracker},
+        RoomName,
+    };
+
+    fn sample_recording() -> Vec<u8> {
+        let mut recorder = SessionRecorder::new(Vec::new());
+        let frames = [
+            (0, FrameDirection::Incoming, "o"),
+            (
+                5,
+                FrameDirection::Outgoing,
+                r#"["subscribe roomMap2:shard0/E10N5"]"#,
+            ),
+            (
+                20,
+                FrameDirection::Incoming,
+                r#"a["[\"roomMap2:shard0/E10N5\",{\"pb\":[[20,21]]}]"]"#,
+            ),
+            (40, FrameDirection::Incoming, "h"),
+        ];
+        for &(elapsed_ms, direction, frame) in frames.iter() {
+            recorder
+                .write_frame(&RecordedFrame {
+                    elapsed_ms,
+                    direction,
+                    frame: frame.to_owned(),
+                })
+                .unwrap();
+        }
+        recorder.into_inner()
+    }
+
+    #[test]
+    fn record_and_read_back() {
+        let mut recorder = SessionRecorder::new(Vec::new());
+        recorder.record_incoming("o").unwrap();
+        recorder.record_outgoing(r#"["auth abc"]"#).unwrap();
+        recorder.record_incoming("h").unwrap();
+
+        let recording = recorder.into_inner();
+        let frames = SessionReplayer::new(Cursor::new(recording))
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1].direction, FrameDirection::Outgoing);
+        assert_eq!(frames[1].frame, r#"["auth abc"]"#);
+        assert!(frames[0].elapsed_ms <= frames[2].elapsed_ms);
+    }
+
+    #[test]
+    fn replay_into_map_tracker() {
+        let mut tracker = WorldMapTracker::new(Some("shard0"));
+        tracker.track(RoomName::new("E10N5").unwrap());
+
+        let mut events = Vec::new();
+        let mut parsed = 0;
+        SessionReplayer::new(Cursor::new(sample_recording()))
+            .replay(ReplaySpeed::Instant, |frame, message| {
+                parsed += 1;
+                if let SockjsMessage::Messages(messages) = message.unwrap() {
+                    for message in messages {
+                        if let ScreepsMessage::ChannelUpdate { update } = message {
+                            events.extend(tracker.handle_update(&update, frame.elapsed_ms as u32));
+                        }
+                    }
+                }
+            })
+            .unwrap();
+
+        assert_eq!(parsed, 3);
+        assert_eq!(
+            events,
+            vec![MapEvent::PowerBankAppeared {
+                room_name: RoomName::new("E10N5").unwrap(),
+                seen_at: 20,
+                positions: vec![(20, 21)],
+            }]
+        );
+    }
+
+    #[test]
+    fn replay_accelerated_keeps_timing() {
+        let started = Instant::now();
+        SessionReplayer::new(Cursor::new(sample_recording()))
+            .replay(ReplaySpeed::Accelerated(2.0), |_, _| ())
+            .unwrap();
+
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn tiny_acceleration_factors_do_not_overflow() {
+        let original = Duration::from_millis(10);
+
+        assert_eq!(
+            ReplaySpeed::Accelerated(1e-300).scale(original),
+            Some(Duration::MAX)
+        );
+        assert_eq!(ReplaySpeed::Accelerated(f64::NAN).scale(original), None);
+    }
+
+    #[test]
+    fn malformed_lines_are_errors() {
+        let mut replayer = SessionReplayer::new(Cursor::new(&b"not json\n"[..]));
+
+        assert!(replayer.next().unwrap().is_err());
+        assert!(replayer.next().is_none());
+    }
+}
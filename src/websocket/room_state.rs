@@ -0,0 +1,594 @@
+//! Tracking of a single room's full state from room detail updates.
+//!
+//! [`RoomState`] applies each [`RoomUpdate`] diff to its copy of the room's objects, and reports
+//! what changed as [`RoomEvent`]s: objects appearing and disappearing, creeps moving, hits and
+//! stores changing, controller progress, finished construction and spawning. This lets alerting
+//! code react to what happened without comparing snapshots itself.
+//!
+//! Like [`WorldMapTracker`], the tracker does no networking: subscribe to the room's
+//! [`Channel::room_detail`], and feed it every [`ChannelUpdate`] received.
+//!
+//! [`RoomState`]: struct.RoomState.html
+//! [`RoomUpdate`]: ../struct.RoomUpdate.html
+//! [`RoomEvent`]: enum.RoomEvent.html
+//! [`WorldMapTracker`]: ../struct.WorldMapTracker.html
+//! [`Channel::room_detail`]: ../enum.Channel.html#method.room_detail
+//! [`ChannelUpdate`]: ../enum.ChannelUpdate.html
+use std::{borrow::Cow, collections::HashMap};
+
+use serde::{
+    de::{value::Error as ValueError, IntoDeserializer},
+    Deserialize,
+};
+
+use crate::RoomName;
+
+use super::{
+    objects::*, resources::Store, room_object_macros::Updatable, Channel, ChannelUpdate, RoomUpdate,
+};
+
+/// A change in a room found by [`RoomState::apply`].
+///
+/// Object ids refer to objects in the room. A single update can produce several events for the
+/// same object, for instance a creep both moving and losing hits.
+///
+/// [`RoomState::apply`]: struct.RoomState.html#method.apply
+#[derive(Clone, Debug, PartialEq)]
+pub enum RoomEvent {
+    /// An object was added to the room.
+    ObjectAppeared {
+        /// The object's id.
+        id: String,
+        /// The object's type, as the server names it.
+        kind: String,
+    },
+    /// An object was removed from the room.
+    ObjectDisappeared {
+        /// The object's id.
+        id: String,
+        /// The object's type, as the server names it.
+        kind: String,
+    },
+    /// A creep or power creep changed position.
+    CreepMoved {
+        /// The creep's id.
+        id: String,
+        /// The previous position.
+        from: (u32, u32),
+        /// The new position.
+        to: (u32, u32),
+    },
+    /// An object's hits changed.
+    HitsChanged {
+        /// The object's id.
+        id: String,
+        /// The previous hits.
+        old: i32,
+        /// The new hits.
+        new: i32,
+    },
+    /// The contents of an object's store changed.
+    StoreChanged {
+        /// The object's id.
+        id: String,
+        /// The previous contents.
+        old: Store,
+        /// The new contents.
+        new: Store,
+    },
+    /// A controller's level changed.
+    ControllerLevelChanged {
+        /// The controller's id.
+        id: String,
+        /// The previous level.
+        old: u16,
+        /// The new level.
+        new: u16,
+    },
+    /// A controller's progress towards the next level changed.
+    ControllerProgressChanged {
+        /// The controller's id.
+        id: String,
+        /// The previous progress.
+        old: u64,
+        /// The new progress.
+        new: u64,
+    },
+    /// A construction site was removed, and a structure of the same type appeared at its position
+    /// in the same update.
+    ConstructionCompleted {
+        /// The id of the removed construction site.
+        site_id: String,
+        /// The id of the new structure.
+        structure_id: String,
+        /// The type of the new structure.
+        structure_type: StructureType,
+        /// The x position of the structure.
+        x: u32,
+        /// The y position of the structure.
+        y: u32,
+    },
+    /// A spawn started spawning a creep.
+    SpawnStarted {
+        /// The spawn's id.
+        spawn_id: String,
+        /// The name of the spawning creep.
+        creep_name: String,
+    },
+    /// A spawn stopped spawning a creep, either because it finished or because it was cancelled.
+    SpawnFinished {
+        /// The spawn's id.
+        spawn_id: String,
+        /// The name of the spawned creep.
+        creep_name: String,
+    },
+}
+
+/// The full state of one room, kept up to date from room detail updates.
+#[derive(Clone, Debug)]
+pub struct RoomState {
+    room_name: RoomName,
+    shard_name: Option<String>,
+    game_time: Option<u32>,
+    objects: HashMap<String, RoomObject>,
+}
+
+impl RoomState {
+    /// Creates an empty state for the given room, on the given shard or on a non-sharded server if
+    /// `None`.
+    pub fn new<'a, T: Into<Cow<'a, str>>>(room_name: RoomName, shard_name: Option<T>) -> Self {
+        RoomState {
+            room_name,
+            shard_name: shard_name.map(|s| s.into().into_owned()),
+            game_time: None,
+            objects: HashMap::new(),
+        }
+    }
+
+    /// The room this state is for.
+    pub fn room_name(&self) -> RoomName {
+        self.room_name
+    }
+
+    /// The channel to subscribe to for updates to this room.
+    pub fn channel(&self) -> Channel<'static> {
+        Channel::room_detail(self.room_name, self.shard_name.clone())
+    }
+
+    /// The game time of the last update applied, if any had one.
+    pub fn game_time(&self) -> Option<u32> {
+        self.game_time
+    }
+
+    /// Every object in the room, by id.
+    pub fn objects(&self) -> &HashMap<String, RoomObject> {
+        &self.objects
+    }
+
+    /// Gets a known room object by id.
+    pub fn get(&self, id: &str) -> Option<&KnownRoomObject> {
+        self.objects.get(id).and_then(RoomObject::known)
+    }
+
+    /// Applies a channel update if it is a room detail update for this room, and returns the
+    /// changes found. Any other update is ignored.
+    pub fn handle_update(&mut self, update: &ChannelUpdate<'_>) -> Vec<RoomEvent> {
+        match *update {
+            ChannelUpdate::RoomDetail {
+                room_name,
+                ref shard_name,
+                ref update,
+            } if room_name == self.room_name && *shard_name == self.shard_name => {
+                self.apply(update)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Applies a room update to this state, and returns the changes found.
+    ///
+    /// Objects which fail to parse or update are skipped, and keep their previous state.
+    pub fn apply(&mut self, update: &RoomUpdate) -> Vec<RoomEvent> {
+        if update.game_time.is_some() {
+            self.game_time = update.game_time;
+        }
+
+        let mut events = Vec::new();
+        let mut removed_sites = Vec::new();
+        let mut added = Vec::new();
+
+        for (id, value) in &update.objects {
+            if value.is_null() {
+                if let Some(obj) = self.objects.remove(id) {
+                    if let Some(KnownRoomObject::ConstructionSite(ref site)) = obj.known() {
+                        removed_sites.push((id.clone(), site.structure_type, obj.x(), obj.y()));
+                    }
+                    events.push(RoomEvent::ObjectDisappeared {
+                        id: id.clone(),
+                        kind: obj.kind().to_owned(),
+                    });
+                }
+                continue;
+            }
+
+            if let Some(obj) = self.objects.get_mut(id) {
+                let result = match *obj {
+                    RoomObject::Known(ref mut obj) => update_known(id, obj, value, &mut events),
+                    ref mut obj => obj.update(value.clone()),
+                };
+                if let Err(e) = result {
+                    warn!("failed to update room object {}: {}", id, e);
+                }
+                continue;
+            }
+
+            match RoomObject::from_value(value.clone()) {
+                Ok(obj) => {
+                    events.push(RoomEvent::ObjectAppeared {
+                        id: id.clone(),
+                        kind: obj.kind().to_owned(),
+                    });
+                    added.push(id.clone());
+                    self.objects.insert(id.clone(), obj);
+                }
+                Err(e) => warn!("failed to parse new room object {}: {}", id, e),
+            }
+        }
+
+        for (site_id, structure_type, x, y) in removed_sites {
+            let structure = added
+                .iter()
+                .filter_map(|id| self.objects.get(id))
+                .find(|obj| {
+                    obj.x() == x
+                        && obj.y() == y
+                        && parse_structure_type(obj.kind()) == Some(structure_type)
+                });
+            if let Some(structure) = structure {
+                events.push(RoomEvent::ConstructionCompleted {
+                    site_id,
+                    structure_id: structure.id().to_owned(),
+                    structure_type,
+                    x,
+                    y,
+                });
+            }
+        }
+
+        events
+    }
+}
+
+/// Expands to a match on a known object which parses the update into the `*Update` struct for the
+/// object's type, records events for each listed field present in it, and then applies it.
+macro_rules! update_variants {
+    (
+        $id:ident, $obj:ident, $value:ident, $events:ident;
+        $( $variant:ident($update:ident) [$( $field:ident ),*], )*
+    ) => (
+        match *$obj {
+            $(
+                KnownRoomObject::$variant(ref mut obj) => {
+                    let update = $update::deserialize($value)?;
+                    $( update_variants!(@$field $id, obj, update, $events); )*
+                    obj.update(update);
+                }
+            )*
+        }
+    );
+    (@pos $id:ident, $obj:ident, $update:ident, $events:ident) => {
+        let from = ($obj.x, $obj.y);
+        let to = ($update.x.unwrap_or($obj.x), $update.y.unwrap_or($obj.y));
+        if from != to {
+            $events.push(RoomEvent::CreepMoved {
+                id: $id.to_owned(),
+                from,
+                to,
+            });
+        }
+    };
+    (@hits $id:ident, $obj:ident, $update:ident, $events:ident) => {
+        match $update.hits {
+            Some(new) if new != $obj.hits => $events.push(RoomEvent::HitsChanged {
+                id: $id.to_owned(),
+                old: $obj.hits,
+                new,
+            }),
+            _ => (),
+        }
+    };
+    (@store $id:ident, $obj:ident, $update:ident, $events:ident) => {
+        if let Some(ref store) = $update.store {
+            let mut new = $obj.store.clone();
+            new.apply_update(store.clone());
+            if new != $obj.store {
+                $events.push(RoomEvent::StoreChanged {
+                    id: $id.to_owned(),
+                    old: $obj.store.clone(),
+                    new,
+                });
+            }
+        }
+    };
+    (@level $id:ident, $obj:ident, $update:ident, $events:ident) => {
+        match $update.level {
+            Some(new) if new != $obj.level => $events.push(RoomEvent::ControllerLevelChanged {
+                id: $id.to_owned(),
+                old: $obj.level,
+                new,
+            }),
+            _ => (),
+        }
+    };
+    (@progress $id:ident, $obj:ident, $update:ident, $events:ident) => {
+        match $update.progress {
+            Some(new) if new != $obj.progress => {
+                $events.push(RoomEvent::ControllerProgressChanged {
+                    id: $id.to_owned(),
+                    old: $obj.progress,
+                    new,
+                })
+            }
+            _ => (),
+        }
+    };
+    (@spawning $id:ident, $obj:ident, $update:ident, $events:ident) => {
+        if let Some(ref spawning) = $update.spawning {
+            let old = $obj.spawning.as_ref().map(|s| &s.name);
+            let new = match *spawning {
+                Some(ref s) => s.name.as_ref().or(old),
+                None => None,
+            };
+            if old != new {
+                if let Some(name) = old {
+                    $events.push(RoomEvent::SpawnFinished {
+                        spawn_id: $id.to_owned(),
+                        creep_name: name.clone(),
+                    });
+                }
+                if let Some(name) = new {
+                    $events.push(RoomEvent::SpawnStarted {
+                        spawn_id: $id.to_owned(),
+                        creep_name: name.clone(),
+                    });
+                }
+            }
+        }
+    };
+}
+
+/// Applies an update to a known object, and records the changes made by the fields present in it.
+fn update_known(
+    id: &str,
+    obj: &mut KnownRoomObject,
+    value: &serde_json::Value,
+    events: &mut Vec<RoomEvent>,
+) -> Result<(), serde_json::Error> {
+    update_variants!(id, obj, value, events;
+        Source(SourceUpdate) [],
+        Mineral(MineralUpdate) [],
+        Spawn(StructureSpawnUpdate) [hits, store, spawning],
+        Extension(StructureExtensionUpdate) [hits, store],
+        Extractor(StructureExtractorUpdate) [hits],
+        Wall(StructureWallUpdate) [hits],
+        Road(StructureRoadUpdate) [hits],
+        Rampart(StructureRampartUpdate) [hits],
+        KeeperLair(StructureKeeperLairUpdate) [],
+        Controller(StructureControllerUpdate) [hits, level, progress],
+        Portal(StructurePortalUpdate) [],
+        Link(StructureLinkUpdate) [hits, store],
+        Storage(StructureStorageUpdate) [hits, store],
+        Tower(StructureTowerUpdate) [hits, store],
+        Observer(StructureObserverUpdate) [hits],
+        PowerBank(StructurePowerBankUpdate) [hits],
+        PowerSpawn(StructurePowerSpawnUpdate) [hits, store],
+        Lab(StructureLabUpdate) [hits, store],
+        Terminal(StructureTerminalUpdate) [hits, store],
+        Container(StructureContainerUpdate) [hits, store],
+        Nuker(StructureNukerUpdate) [hits, store],
+        Tombstone(TombstoneUpdate) [store],
+        Creep(CreepUpdate) [pos, hits, store],
+        Resource(ResourceUpdate) [],
+        ConstructionSite(ConstructionSiteUpdate) [],
+        Factory(StructureFactoryUpdate) [hits, store],
+        InvaderCore(StructureInvaderCoreUpdate) [hits],
+        Deposit(DepositUpdate) [],
+        Ruin(RuinUpdate) [store],
+        Nuke(NukeUpdate) [],
+        PowerCreep(PowerCreepUpdate) [pos, hits, store],
+    );
+
+    Ok(())
+}
+
+/// Parses an object type as a structure type, using the names `StructureType` deserializes from.
+fn parse_structure_type(kind: &str) -> Option<StructureType> {
+    StructureType::deserialize(IntoDeserializer::<ValueError>::into_deserializer(kind)).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::websocket::resources::ResourceType;
+
+    fn update(json: serde_json::Value) -> RoomUpdate {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn initial_state() -> RoomState {
+        let mut state = RoomState::new(RoomName::new("E1N1").unwrap(), Some("shard0"));
+        state.apply(&update(json!({
+            "gameTime": 100,
+            "info": {"mode": "world"},
+            "objects": {
+                "ctrl": {
+                    "_id": "ctrl", "type": "controller", "room": "E1N1", "x": 10, "y": 10,
+                    "level": 2, "progress": 1000, "progressTotal": 45000,
+                    "user": "user1", "reservation": null, "safeMode": null,
+                    "safeModeAvailable": 1, "safeModeCooldown": null, "sign": null,
+                    "downgradeTime": 200000, "upgradeBlocked": null, "isPowerEnabled": false
+                },
+                "spawn1": {
+                    "_id": "spawn1", "type": "spawn", "room": "E1N1", "x": 20, "y": 20,
+                    "name": "Spawn1", "user": "user1", "hits": 5000, "hitsMax": 5000,
+                    "notifyWhenAttacked": true, "spawning": null,
+                    "store": {"energy": 300}, "storeCapacityResource": {"energy": 300}
+                },
+                "site1": {
+                    "_id": "site1", "type": "constructionSite", "room": "E1N1", "x": 25, "y": 25,
+                    "user": "user1", "name": "", "progress": 2900, "progressTotal": 3000,
+                    "structureType": "extension"
+                }
+            }
+        })));
+        state
+    }
+
+    #[test]
+    fn initial_objects_appear() {
+        let state = initial_state();
+
+        assert_eq!(state.game_time(), Some(100));
+        assert_eq!(state.objects().len(), 3);
+        assert!(state.get("spawn1").is_some());
+    }
+
+    #[test]
+    fn diff_events() {
+        let mut state = initial_state();
+
+        let events = state.apply(&update(json!({
+            "gameTime": 101,
+            "info": {"mode": "world"},
+            "objects": {
+                "ctrl": {"level": 3, "progress": 0},
+                "spawn1": {
+                    "hits": 4900,
+                    "store": {"energy": 100},
+                    "spawning": {"name": "creep1", "needTime": 9, "spawnTime": 110}
+                },
+                "site1": null,
+                "ext1": {
+                    "_id": "ext1", "type": "extension", "room": "E1N1", "x": 25, "y": 25,
+                    "user": "user1", "hits": 1000, "hitsMax": 1000, "notifyWhenAttacked": true,
+                    "off": false, "store": {}, "storeCapacityResource": {"energy": 50}
+                }
+            }
+        })));
+
+        let expected = vec![
+            RoomEvent::ControllerLevelChanged {
+                id: "ctrl".to_owned(),
+                old: 2,
+                new: 3,
+            },
+            RoomEvent::ControllerProgressChanged {
+                id: "ctrl".to_owned(),
+                old: 1000,
+                new: 0,
+            },
+            RoomEvent::SpawnStarted {
+                spawn_id: "spawn1".to_owned(),
+                creep_name: "creep1".to_owned(),
+            },
+            RoomEvent::HitsChanged {
+                id: "spawn1".to_owned(),
+                old: 5000,
+                new: 4900,
+            },
+            RoomEvent::ObjectDisappeared {
+                id: "site1".to_owned(),
+                kind: "constructionSite".to_owned(),
+            },
+            RoomEvent::ObjectAppeared {
+                id: "ext1".to_owned(),
+                kind: "extension".to_owned(),
+            },
+            RoomEvent::ConstructionCompleted {
+                site_id: "site1".to_owned(),
+                structure_id: "ext1".to_owned(),
+                structure_type: StructureType::Extension,
+                x: 25,
+                y: 25,
+            },
+        ];
+        for event in &expected {
+            assert!(
+                events.contains(event),
+                "missing {:?} in {:?}",
+                event,
+                events
+            );
+        }
+        assert!(events.iter().any(|e| match e {
+            RoomEvent::StoreChanged { id, new, .. } => {
+                id == "spawn1" && new.get(ResourceType::Energy) == 100
+            }
+            _ => false,
+        }));
+        assert_eq!(events.len(), expected.len() + 1);
+    }
+
+    #[test]
+    fn unchanged_fields_are_not_events() {
+        let mut state = initial_state();
+
+        let events = state.apply(&update(json!({
+            "info": {"mode": "world"},
+            "objects": {
+                "ctrl": {"level": 2},
+                "spawn1": {"hits": 5000, "store": {"energy": 300}, "notifyWhenAttacked": false}
+            }
+        })));
+
+        assert_eq!(events, vec![]);
+    }
+
+    #[test]
+    fn spawn_finishes_and_creep_moves() {
+        let mut state = initial_state();
+        state.apply(&update(json!({
+            "info": {"mode": "world"},
+            "objects": {
+                "spawn1": {"spawning": {"name": "creep1", "needTime": 9, "spawnTime": 110}}
+            }
+        })));
+
+        let events = state.apply(&update(json!({
+            "gameTime": 110,
+            "info": {"mode": "world"},
+            "objects": {
+                "spawn1": {"spawning": null},
+                "creep1": {
+                    "_id": "creep1", "type": "creep", "room": "E1N1", "x": 20, "y": 21,
+                    "name": "creep1", "user": "user1", "body": [], "hits": 100,
+                    "hitsMax": 100, "fatigue": 0, "spawning": false, "notifyWhenAttacked": true,
+                    "ageTime": 1610, "store": {}, "storeCapacity": 0, "actionLog": {}
+                }
+            }
+        })));
+        assert_eq!(events.len(), 2);
+        assert!(events.contains(&RoomEvent::SpawnFinished {
+            spawn_id: "spawn1".to_owned(),
+            creep_name: "creep1".to_owned(),
+        }));
+        assert!(events.contains(&RoomEvent::ObjectAppeared {
+            id: "creep1".to_owned(),
+            kind: "creep".to_owned(),
+        }));
+
+        let events = state.apply(&update(json!({
+            "info": {"mode": "world"},
+            "objects": {"creep1": {"y": 22}}
+        })));
+        assert_eq!(
+            events,
+            vec![RoomEvent::CreepMoved {
+                id: "creep1".to_owned(),
+                from: (20, 21),
+                to: (20, 22),
+            }]
+        );
+        assert_eq!(state.game_time(), Some(110));
+    }
+}
@@ -0,0 +1,433 @@
+//! Scheduling of room detail subscriptions past the per-account room limit.
+//!
+//! The server only sends [`ChannelUpdate::RoomDetail`] updates for two subscribed rooms per
+//! account each tick. [`RoomDetailRotator`] tracks any number of rooms, each with a refresh
+//! interval, and decides which rooms should hold the subscriptions at any given time.
+//!
+//! The rotator does no networking itself: feed it updates with
+//! [`RoomDetailRotator::handle_update`], and call [`RoomDetailRotator::poll`] once per tick to
+//! get the channels to subscribe to and unsubscribe from.
+//!
+//! [`ChannelUpdate::RoomDetail`]: ../enum.ChannelUpdate.html
+//! [`RoomDetailRotator`]: struct.RoomDetailRotator.html
+//! [`RoomDetailRotator::handle_update`]: struct.RoomDetailRotator.html#method.handle_update
+//! [`RoomDetailRotator::poll`]: struct.RoomDetailRotator.html#method.poll
+use std::{borrow::Cow, cmp::Ordering};
+
+use crate::RoomName;
+
+use super::{commands, Channel, ChannelUpdate};
+
+/// The number of room detail subscriptions which receive updates each tick.
+pub const ROOM_DETAIL_SUBSCRIPTION_LIMIT: usize = 2;
+
+/// Number of ticks a subscription is held waiting for a first update before it is given up.
+const DEFAULT_SUBSCRIPTION_TIMEOUT: u32 = 5;
+
+/// Status of a single room watched by a [`RoomDetailRotator`].
+///
+/// [`RoomDetailRotator`]: struct.RoomDetailRotator.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WatchedRoom {
+    /// The room.
+    pub room_name: RoomName,
+    /// The maximum number of ticks which should pass between updates for this room.
+    pub refresh_interval: u32,
+    /// The game time this room was last seen in a room detail update, if ever.
+    pub last_seen: Option<u32>,
+    /// The game time this room was subscribed to, if it is currently subscribed.
+    pub subscribed_since: Option<u32>,
+}
+
+impl WatchedRoom {
+    /// Whether this room has gone longer than its refresh interval without an update, as of
+    /// `game_time`.
+    pub fn is_overdue(&self, game_time: u32) -> bool {
+        match self.last_seen {
+            Some(last_seen) => game_time.saturating_sub(last_seen) > self.refresh_interval,
+            None => true,
+        }
+    }
+
+    /// Whether the current subscription has received an update yet.
+    fn seen_since_subscribed(&self) -> bool {
+        match (self.subscribed_since, self.last_seen) {
+            (Some(since), Some(seen)) => seen >= since,
+            _ => false,
+        }
+    }
+
+    /// How far through its refresh interval this room is. Rooms never seen are the most urgent.
+    fn urgency(&self, game_time: u32) -> f64 {
+        match self.last_seen {
+            Some(last_seen) => {
+                f64::from(game_time.saturating_sub(last_seen)) / f64::from(self.refresh_interval)
+            }
+            None => f64::INFINITY,
+        }
+    }
+}
+
+/// Subscription changes requested by [`RoomDetailRotator::poll`].
+///
+/// [`RoomDetailRotator::poll`]: struct.RoomDetailRotator.html#method.poll
+#[derive(Clone, Debug, Default)]
+pub struct RotationChanges {
+    /// Channels to unsubscribe from.
+    pub unsubscribe: Vec<Channel<'static>>,
+    /// Channels to subscribe to.
+    pub subscribe: Vec<Channel<'static>>,
+}
+
+impl RotationChanges {
+    /// Whether no subscriptions need to change.
+    pub fn is_empty(&self) -> bool {
+        self.unsubscribe.is_empty() && self.subscribe.is_empty()
+    }
+
+    /// The raw websocket messages to send to apply these changes, unsubscribes first.
+    pub fn commands(&self) -> Vec<String> {
+        self.unsubscribe
+            .iter()
+            .map(commands::unsubscribe)
+            .chain(self.subscribe.iter().map(commands::subscribe))
+            .collect()
+    }
+}
+
+/// Round-robin scheduler for room detail subscriptions on one shard.
+///
+/// Each tick, the rooms furthest through their refresh interval hold the available
+/// subscriptions. A subscribed room is kept until it has received an update, or until a few
+/// ticks have passed without one.
+///
+/// The room limit is per account, not per connection: if other clients on the same account hold
+/// room subscriptions, lower [`set_slots`] accordingly.
+///
+/// [`set_slots`]: #method.set_slots
+#[derive(Clone, Debug)]
+pub struct RoomDetailRotator {
+    shard_name: Option<String>,
+    slots: usize,
+    subscription_timeout: u32,
+    rooms: Vec<WatchedRoom>,
+    pending_unsubscribe: Vec<RoomName>,
+    game_time: u32,
+}
+
+impl RoomDetailRotator {
+    /// Creates a rotator for rooms on the given shard, or on a non-sharded server if `None`.
+    pub fn new<'a, T: Into<Cow<'a, str>>>(shard_name: Option<T>) -> Self {
+        RoomDetailRotator {
+            shard_name: shard_name.map(|s| s.into().into_owned()),
+            slots: ROOM_DETAIL_SUBSCRIPTION_LIMIT,
+            subscription_timeout: DEFAULT_SUBSCRIPTION_TIMEOUT,
+            rooms: Vec::new(),
+            pending_unsubscribe: Vec::new(),
+            game_time: 0,
+        }
+    }
+
+    /// Sets how many room detail subscriptions this rotator may hold at once.
+    pub fn set_slots(&mut self, slots: usize) {
+        self.slots = slots;
+    }
+
+    /// Sets how many ticks a subscription is held waiting for its first update.
+    pub fn set_subscription_timeout(&mut self, ticks: u32) {
+        self.subscription_timeout = ticks;
+    }
+
+    /// Starts watching a room, or changes the refresh interval of a room already watched.
+    ///
+    /// `refresh_interval` is the maximum number of ticks which should pass between updates for
+    /// this room. Rooms with shorter intervals are refreshed more often.
+    pub fn watch(&mut self, room_name: RoomName, refresh_interval: u32) {
+        let refresh_interval = refresh_interval.max(1);
+
+        match self.rooms.iter_mut().find(|r| r.room_name == room_name) {
+            Some(room) => room.refresh_interval = refresh_interval,
+            None => self.rooms.push(WatchedRoom {
+                room_name,
+                refresh_interval,
+                last_seen: None,
+                subscribed_since: None,
+            }),
+        }
+    }
+
+    /// Stops watching a room. If it is currently subscribed, the next [`poll`] unsubscribes it.
+    ///
+    /// Returns the room's last status, if it was watched.
+    ///
+    /// [`poll`]: #method.poll
+    pub fn unwatch(&mut self, room_name: RoomName) -> Option<WatchedRoom> {
+        let index = self.rooms.iter().position(|r| r.room_name == room_name)?;
+        let room = self.rooms.remove(index);
+
+        if room.subscribed_since.is_some() {
+            self.pending_unsubscribe.push(room_name);
+        }
+
+        Some(room)
+    }
+
+    /// Gets the status of a watched room.
+    pub fn room(&self, room_name: RoomName) -> Option<&WatchedRoom> {
+        self.rooms.iter().find(|r| r.room_name == room_name)
+    }
+
+    /// Gets the status of all watched rooms, in the order they were first watched.
+    pub fn rooms(&self) -> &[WatchedRoom] {
+        &self.rooms
+    }
+
+    /// Gets the game time a room was last seen in a room detail update.
+    pub fn last_seen(&self, room_name: RoomName) -> Option<u32> {
+        self.room(room_name).and_then(|r| r.last_seen)
+    }
+
+    /// Iterates over all watched rooms which have gone longer than their refresh interval
+    /// without an update, as of the last game time given to [`poll`].
+    ///
+    /// [`poll`]: #method.poll
+    pub fn overdue(&self) -> impl Iterator<Item = &WatchedRoom> {
+        let game_time = self.game_time;
+        self.rooms.iter().filter(move |r| r.is_overdue(game_time))
+    }
+
+    /// Records a channel update. Room detail updates for watched rooms on this rotator's shard
+    /// mark the room as seen; all other updates are ignored.
+    ///
+    /// Returns true if the update was for a watched room.
+    pub fn handle_update(&mut self, update: &ChannelUpdate) -> bool {
+        let (room_name, shard_name, game_time) = match *update {
+            ChannelUpdate::RoomDetail {
+                room_name,
+                ref shard_name,
+                ref update,
+            } => (room_name, shard_name, update.game_time),
+            _ => return false,
+        };
+
+        if *shard_name != self.shard_name {
+            return false;
+        }
+
+        let game_time = game_time.unwrap_or(self.game_time);
+        self.game_time = self.game_time.max(game_time);
+
+        match self.rooms.iter_mut().find(|r| r.room_name == room_name) {
+            Some(room) => {
+                room.last_seen = Some(game_time);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Advances the rotator to the given game time, returning the subscription changes to make.
+    ///
+    /// Call this once per tick, for instance whenever a `UserCpu` update arrives.
+    pub fn poll(&mut self, game_time: u32) -> RotationChanges {
+        self.game_time = self.game_time.max(game_time);
+        let game_time = self.game_time;
+        let timeout = self.subscription_timeout;
+
+        let mut changes = RotationChanges::default();
+
+        for room_name in self.pending_unsubscribe.drain(..) {
+            changes
+                .unsubscribe
+                .push(Channel::room_detail(room_name, self.shard_name.clone()));
+        }
+
+        // rooms which are subscribed and still waiting for an update keep their slot.
+        let mut held = Vec::new();
+        let mut candidates = Vec::new();
+        for (index, room) in self.rooms.iter().enumerate() {
+            let timed_out = match room.subscribed_since {
+                Some(since) if !room.seen_since_subscribed() => {
+                    if game_time.saturating_sub(since) < timeout {
+                        held.push(index);
+                        continue;
+                    }
+                    true
+                }
+                _ => false,
+            };
+            candidates.push((index, timed_out));
+        }
+
+        let rooms = &self.rooms;
+        // rooms which just timed out go after all others so they don't immediately take their
+        // slot back. The sort is stable: remaining ties go to the room watched first.
+        candidates.sort_by(|&(a, a_timed_out), &(b, b_timed_out)| {
+            a_timed_out.cmp(&b_timed_out).then_with(|| {
+                rooms[b]
+                    .urgency(game_time)
+                    .partial_cmp(&rooms[a].urgency(game_time))
+                    .unwrap_or(Ordering::Equal)
+            })
+        });
+        let candidates = candidates
+            .into_iter()
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+
+        let free = self.slots.saturating_sub(held.len());
+        let chosen = &candidates[..free.min(candidates.len())];
+
+        for (index, room) in self.rooms.iter_mut().enumerate() {
+            let should_hold = held.contains(&index) || chosen.contains(&index);
+            match (room.subscribed_since.is_some(), should_hold) {
+                (true, false) => {
+                    room.subscribed_since = None;
+                    changes.unsubscribe.push(Channel::room_detail(
+                        room.room_name,
+                        self.shard_name.clone(),
+                    ));
+                }
+                (false, true) => {
+                    room.subscribed_since = Some(game_time);
+                    changes.subscribe.push(Channel::room_detail(
+                        room.room_name,
+                        self.shard_name.clone(),
+                    ));
+                }
+                (true, true) => {
+                    // a room which was seen and stays subscribed starts a new wait.
+                    if room.seen_since_subscribed() {
+                        room.subscribed_since = Some(game_time);
+                    }
+                }
+                (false, false) => (),
+            }
+        }
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn room(name: &str) -> RoomName {
+        RoomName::new(name).unwrap()
+    }
+
+    fn detail_update(name: &str, game_time: u32) -> ChannelUpdate<'static> {
+        let json = format!(
+            r#"["room:{}",{{"gameTime":{},"info":{{"mode":"world"}},"objects":{{}},"users":{{}}}}]"#,
+            name, game_time
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    fn subscribed(changes: &RotationChanges) -> Vec<String> {
+        changes.subscribe.iter().map(ToString::to_string).collect()
+    }
+
+    fn unsubscribed(changes: &RotationChanges) -> Vec<String> {
+        changes
+            .unsubscribe
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn rotates_through_all_rooms() {
+        let mut rotator = RoomDetailRotator::new(None::<&str>);
+        for name in &["E1N1", "E2N1", "E3N1", "E4N1"] {
+            rotator.watch(room(name), 4);
+        }
+
+        let changes = rotator.poll(100);
+        assert_eq!(subscribed(&changes), vec!["room:E1N1", "room:E2N1"]);
+        assert!(changes.unsubscribe.is_empty());
+
+        // no updates yet: subscriptions are held.
+        assert!(rotator.poll(101).is_empty());
+
+        assert!(rotator.handle_update(&detail_update("E1N1", 101)));
+        assert!(rotator.handle_update(&detail_update("E2N1", 101)));
+        assert_eq!(rotator.last_seen(room("E1N1")), Some(101));
+
+        let changes = rotator.poll(102);
+        assert_eq!(unsubscribed(&changes), vec!["room:E1N1", "room:E2N1"]);
+        assert_eq!(subscribed(&changes), vec!["room:E3N1", "room:E4N1"]);
+        assert_eq!(
+            changes.commands()[0],
+            commands::unsubscribe(&Channel::room_detail(room("E1N1"), None::<&str>))
+        );
+
+        rotator.handle_update(&detail_update("E3N1", 103));
+        rotator.handle_update(&detail_update("E4N1", 103));
+
+        let changes = rotator.poll(104);
+        assert_eq!(subscribed(&changes), vec!["room:E1N1", "room:E2N1"]);
+        assert_eq!(rotator.overdue().count(), 0);
+    }
+
+    #[test]
+    fn short_intervals_are_refreshed_more_often() {
+        let mut rotator = RoomDetailRotator::new(Some("shard0"));
+        rotator.set_slots(1);
+        rotator.watch(room("W1N1"), 2);
+        rotator.watch(room("W2N1"), 100);
+
+        let mut times_seen = [0u32; 2];
+        for time in 0..40 {
+            rotator.poll(time);
+            let subscribed = rotator
+                .rooms()
+                .iter()
+                .position(|r| r.subscribed_since.is_some());
+            if let Some(index) = subscribed {
+                let name = rotator.rooms()[index].room_name.to_string();
+                let json = format!(
+                    r#"["room:shard0/{}",{{"gameTime":{},"info":{{}},"objects":{{}}}}]"#,
+                    name, time
+                );
+                let update: ChannelUpdate = serde_json::from_str(&json).unwrap();
+                assert!(rotator.handle_update(&update));
+                times_seen[index] += 1;
+            }
+        }
+
+        assert!(times_seen[0] > times_seen[1] * 3);
+        assert!(times_seen[1] >= 1);
+    }
+
+    #[test]
+    fn timed_out_subscriptions_are_released() {
+        let mut rotator = RoomDetailRotator::new(None::<&str>);
+        rotator.set_slots(1);
+        rotator.set_subscription_timeout(3);
+        rotator.watch(room("E1N1"), 10);
+        rotator.watch(room("E2N1"), 10);
+
+        assert_eq!(subscribed(&rotator.poll(0)), vec!["room:E1N1"]);
+        assert!(rotator.poll(2).is_empty());
+
+        let changes = rotator.poll(3);
+        assert_eq!(unsubscribed(&changes), vec!["room:E1N1"]);
+        assert_eq!(subscribed(&changes), vec!["room:E2N1"]);
+    }
+
+    #[test]
+    fn unwatch_unsubscribes() {
+        let mut rotator = RoomDetailRotator::new(None::<&str>);
+        rotator.watch(room("E1N1"), 10);
+        rotator.poll(0);
+
+        assert!(rotator.unwatch(room("E1N1")).is_some());
+        assert!(!rotator.handle_update(&detail_update("E1N1", 1)));
+
+        let changes = rotator.poll(1);
+        assert_eq!(unsubscribed(&changes), vec!["room:E1N1"]);
+        assert!(changes.subscribe.is_empty());
+    }
+}
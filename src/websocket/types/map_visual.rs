@@ -0,0 +1,375 @@
+//! Update parsing for map visuals drawn by a user's code with `Game.map.visual`.
+//!
+//! Like room visuals, these are sent as a string of JSON objects separated by `\n`, one per shape
+//! drawn, but each position also carries the room it is in.
+use std::fmt;
+
+use serde::{
+    de::{Deserializer, Error, Visitor},
+    Deserialize, Serialize, Serializer,
+};
+
+use crate::RoomName;
+
+use super::room::visual::{LineVisualStyle, ShapeStyle, TextAlign};
+
+/// A position on the world map: a room, and coordinates within it.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub struct MapPosition {
+    /// The X position within the room.
+    pub x: f64,
+    /// The Y position within the room.
+    pub y: f64,
+    /// The room this position is in.
+    #[serde(rename = "n")]
+    pub room_name: RoomName,
+}
+
+/// Style for map text. Map text is styled with separate font properties rather than one CSS font
+/// string like room visual text.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MapTextStyle {
+    /// The text color, in any web format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// The font family.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_family: Option<String>,
+    /// The font size, in game coordinates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<f64>,
+    /// The font style, such as `"italic"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_style: Option<String>,
+    /// The font variant, such as `"small-caps"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_variant: Option<String>,
+    /// The outline color, in any web format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke: Option<String>,
+    /// The outline width.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_width: Option<f64>,
+    /// The background color, in any web format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background_color: Option<String>,
+    /// The padding between the text and the edge of its background.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background_padding: Option<f64>,
+    /// The text alignment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub align: Option<TextAlign>,
+    /// The opacity, between 0 and 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<f64>,
+}
+
+/// A circle drawn on the world map.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MapCircleVisual {
+    /// The position of the center.
+    #[serde(flatten)]
+    pub pos: MapPosition,
+    /// The circle's style.
+    #[serde(rename = "s", default)]
+    pub style: ShapeStyle,
+}
+
+/// A line drawn on the world map, possibly spanning multiple rooms.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MapLineVisual {
+    /// The X position of the start.
+    pub x1: f64,
+    /// The Y position of the start.
+    pub y1: f64,
+    /// The room of the start.
+    #[serde(rename = "n1")]
+    pub room_name1: RoomName,
+    /// The X position of the end.
+    pub x2: f64,
+    /// The Y position of the end.
+    pub y2: f64,
+    /// The room of the end.
+    #[serde(rename = "n2")]
+    pub room_name2: RoomName,
+    /// The line's style.
+    #[serde(rename = "s", default)]
+    pub style: LineVisualStyle,
+}
+
+impl MapLineVisual {
+    /// The start of this line.
+    pub fn start(&self) -> MapPosition {
+        MapPosition {
+            x: self.x1,
+            y: self.y1,
+            room_name: self.room_name1,
+        }
+    }
+
+    /// The end of this line.
+    pub fn end(&self) -> MapPosition {
+        MapPosition {
+            x: self.x2,
+            y: self.y2,
+            room_name: self.room_name2,
+        }
+    }
+}
+
+/// A rectangle drawn on the world map.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MapRectVisual {
+    /// The position of the top-left corner.
+    #[serde(flatten)]
+    pub pos: MapPosition,
+    /// The width.
+    #[serde(rename = "w")]
+    pub width: f64,
+    /// The height.
+    #[serde(rename = "h")]
+    pub height: f64,
+    /// The rectangle's style.
+    #[serde(rename = "s", default)]
+    pub style: ShapeStyle,
+}
+
+/// A polygon or polyline drawn on the world map.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MapPolyVisual {
+    /// The points of the polygon, in order.
+    pub points: Vec<MapPosition>,
+    /// The polygon's style.
+    #[serde(rename = "s", default)]
+    pub style: ShapeStyle,
+}
+
+/// Text drawn on the world map.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MapTextVisual {
+    /// The text drawn.
+    pub text: String,
+    /// The position of the text's baseline.
+    #[serde(flatten)]
+    pub pos: MapPosition,
+    /// The text's style.
+    #[serde(rename = "s", default)]
+    pub style: MapTextStyle,
+}
+
+/// A single shape drawn by a user's `Game.map.visual` calls.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "t")]
+pub enum MapVisual {
+    /// A circle.
+    #[serde(rename = "c")]
+    Circle(MapCircleVisual),
+    /// A line.
+    #[serde(rename = "l")]
+    Line(MapLineVisual),
+    /// A rectangle.
+    #[serde(rename = "r")]
+    Rect(MapRectVisual),
+    /// A polygon or polyline.
+    #[serde(rename = "p")]
+    Poly(MapPolyVisual),
+    /// A piece of text.
+    #[serde(rename = "t")]
+    Text(MapTextVisual),
+}
+
+impl MapVisual {
+    /// Parses map visuals from their wire format: JSON objects separated by `\n`.
+    ///
+    /// Entries which are not recognized or fail to parse are skipped.
+    pub fn parse_all(raw: &str) -> Vec<MapVisual> {
+        raw.lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(visual) => Some(visual),
+                Err(e) => {
+                    debug!("skipping unrecognized map visual {:?}: {}", line, e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Serializes map visuals into their wire format: JSON objects separated by `\n`.
+    pub fn encode_all<'a, I>(visuals: I) -> String
+    where
+        I: IntoIterator<Item = &'a MapVisual>,
+    {
+        let mut result = String::new();
+        for visual in visuals {
+            let line = serde_json::to_string(visual)
+                .expect("expected serializing a MapVisual to unfailingly succeed, but it failed.");
+            result.push_str(&line);
+            result.push('\n');
+        }
+        result
+    }
+}
+
+/// Update containing all map visuals a user drew last tick on one shard.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MapVisualUpdate {
+    /// All visuals drawn, in the order they were drawn.
+    pub visuals: Vec<MapVisual>,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    _non_exhaustive: (),
+}
+
+struct MapVisualUpdateVisitor;
+
+impl<'de> Visitor<'de> for MapVisualUpdateVisitor {
+    type Value = MapVisualUpdate;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string of map visuals separated by newlines")
+    }
+
+    #[inline]
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok(MapVisualUpdate::default())
+    }
+
+    #[inline]
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok(MapVisualUpdate {
+            visuals: MapVisual::parse_all(v),
+            _non_exhaustive: (),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for MapVisualUpdate {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(MapVisualUpdateVisitor)
+    }
+}
+
+impl Serialize for MapVisualUpdate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&MapVisual::encode_all(&self.visuals))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::websocket::types::room::visual::LineStyle;
+
+    #[test]
+    fn parse_sample_map_visuals() {
+        let update: MapVisualUpdate = serde_json::from_value(json!(concat!(
+            r#"{"t":"l","x1":25,"y1":25,"n1":"E1N1","x2":10,"y2":40,"n2":"E2N1","s":{"color":"red","width":0.5}}"#,
+            "\n",
+            r#"{"t":"c","x":25,"y":25,"n":"E1N1","s":{"radius":10,"fill":"blue"}}"#,
+            "\n",
+            r#"{"t":"p","points":[{"x":1,"y":1,"n":"E1N1"},{"x":3,"y":4,"n":"E1N2"}],"s":{}}"#,
+            "\n",
+            r#"{"t":"r","x":0,"y":0,"n":"W1S1","w":50,"h":50,"s":{"opacity":0.2}}"#,
+            "\n",
+            r#"{"t":"t","text":"target","x":25,"y":25,"n":"E1N1","s":{"fontSize":8,"align":"center"}}"#,
+            "\n",
+            r#"{"t":"c","x":25,"y":25,"n":"not a room"}"#,
+        )))
+        .unwrap();
+
+        let visuals = update.visuals;
+        assert_eq!(visuals.len(), 5);
+
+        match visuals[0] {
+            MapVisual::Line(ref line) => {
+                assert_eq!(
+                    line.start(),
+                    MapPosition {
+                        x: 25.0,
+                        y: 25.0,
+                        room_name: RoomName::new("E1N1").unwrap(),
+                    }
+                );
+                assert_eq!(line.end().room_name, RoomName::new("E2N1").unwrap());
+                assert_eq!(
+                    line.style,
+                    LineVisualStyle {
+                        width: Some(0.5),
+                        color: Some("red".to_owned()),
+                        ..LineVisualStyle::default()
+                    }
+                );
+            }
+            ref other => panic!("expected line, found {:?}", other),
+        }
+        match visuals[1] {
+            MapVisual::Circle(ref circle) => {
+                assert_eq!(circle.pos.room_name, RoomName::new("E1N1").unwrap());
+                assert_eq!(circle.style.radius, Some(10.0));
+            }
+            ref other => panic!("expected circle, found {:?}", other),
+        }
+        match visuals[2] {
+            MapVisual::Poly(ref poly) => {
+                assert_eq!(poly.points.len(), 2);
+                assert_eq!(poly.points[1].room_name, RoomName::new("E1N2").unwrap());
+            }
+            ref other => panic!("expected poly, found {:?}", other),
+        }
+        match visuals[3] {
+            MapVisual::Rect(ref rect) => assert_eq!((rect.width, rect.height), (50.0, 50.0)),
+            ref other => panic!("expected rect, found {:?}", other),
+        }
+        match visuals[4] {
+            MapVisual::Text(ref text) => {
+                assert_eq!(text.text, "target");
+                assert_eq!(text.style.font_size, Some(8.0));
+                assert_eq!(text.style.align, Some(TextAlign::Center));
+            }
+            ref other => panic!("expected text, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn map_visuals_round_trip() {
+        let visuals = vec![
+            MapVisual::Circle(MapCircleVisual {
+                pos: MapPosition {
+                    x: 1.0,
+                    y: 2.0,
+                    room_name: RoomName::new("W3N4").unwrap(),
+                },
+                style: ShapeStyle {
+                    line_style: Some(LineStyle::Dotted),
+                    ..ShapeStyle::default()
+                },
+            }),
+            MapVisual::Text(MapTextVisual {
+                text: "hi".to_owned(),
+                pos: MapPosition {
+                    x: 5.0,
+                    y: 6.0,
+                    room_name: RoomName::new("E0S0").unwrap(),
+                },
+                style: MapTextStyle::default(),
+            }),
+        ];
+
+        let encoded = MapVisual::encode_all(&visuals);
+
+        assert_eq!(MapVisual::parse_all(&encoded), visuals);
+    }
+}
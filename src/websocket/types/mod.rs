@@ -3,19 +3,27 @@ use std::{borrow::Cow, fmt, marker::PhantomData};
 
 use serde::{
     de::{self, SeqAccess, Unexpected, Visitor},
-    Deserialize, Deserializer,
+    ser::SerializeTuple,
+    Deserialize, Deserializer, Serialize, Serializer,
 };
 
 use crate::websocket::Channel;
 use crate::RoomName;
 
+mod map_visual;
 mod messages;
 mod room;
 mod room_map_view;
+mod server_message;
+mod user_branch;
 mod user_console;
 mod user_cpu;
+mod user_memory;
 
-pub use self::{messages::*, room::*, room_map_view::*, user_console::*, user_cpu::*};
+pub use self::{
+    map_visual::*, messages::*, room::*, room_map_view::*, server_message::*, user_branch::*,
+    user_console::*, user_cpu::*, user_memory::*,
+};
 
 /// An update to a Screeps server 'channel' that has been subscribed to.
 #[derive(Clone, Debug)]
@@ -96,9 +104,42 @@ pub enum ChannelUpdate<'a> {
         /// The message update.
         update: ConversationUpdate,
     },
-    /// Another update that was not accounted for.
+    /// An update on the value at a subscribed path in the user's memory. Sent once per tick.
+    UserMemoryPath {
+        /// The user ID this memory update is for.
+        user_id: Cow<'a, str>,
+        /// The memory path, separated with '.'.
+        path: Cow<'a, str>,
+        /// The update.
+        update: UserMemoryPathUpdate,
+    },
+    /// An update sent on the user's `set-active-branch` channel whenever the active world or
+    /// simulation code branch changes.
+    UserActiveBranch {
+        /// The user ID this branch update is for.
+        user_id: Cow<'a, str>,
+        /// The update.
+        update: ActiveBranchUpdate,
+    },
+    /// A message broadcast by the server to all clients.
+    ServerMessage {
+        /// The update.
+        update: ServerMessageUpdate,
+    },
+    /// An update containing everything a user's code drew with `Game.map.visual` last tick on
+    /// one shard. Sent once per tick.
+    MapVisual {
+        /// The user ID this map visual update is for.
+        user_id: Cow<'a, str>,
+        /// The shard the visuals were drawn on.
+        shard_name: String,
+        /// The update.
+        update: MapVisualUpdate,
+    },
+    /// Another update that was not accounted for, such as one on a channel created with
+    /// [`Channel::other`].
     ///
-    /// TODO: when we're sure of everything, remove this variant.
+    /// [`Channel::other`]: ../enum.Channel.html#method.other
     Other {
         /// The string describing what channel this is.
         channel: Cow<'a, str>,
@@ -116,6 +157,7 @@ impl<'a> ChannelUpdate<'a> {
             | ChannelUpdate::NoRoomDetail { ref shard_name, .. } => {
                 shard_name.as_ref().map(String::as_str)
             }
+            ChannelUpdate::MapVisual { ref shard_name, .. } => Some(shard_name),
             _ => None,
         }
     }
@@ -141,7 +183,10 @@ impl<'a> ChannelUpdate<'a> {
             | ChannelUpdate::UserConsole { ref user_id, .. }
             | ChannelUpdate::UserMessage { ref user_id, .. }
             | ChannelUpdate::UserConversation { ref user_id, .. }
-            | ChannelUpdate::UserCredits { ref user_id, .. } => Some(user_id.as_ref()),
+            | ChannelUpdate::UserCredits { ref user_id, .. }
+            | ChannelUpdate::UserMemoryPath { ref user_id, .. }
+            | ChannelUpdate::UserActiveBranch { ref user_id, .. }
+            | ChannelUpdate::MapVisual { ref user_id, .. } => Some(user_id.as_ref()),
             _ => None,
         }
     }
@@ -181,6 +226,20 @@ impl<'a> ChannelUpdate<'a> {
                 ref target_user_id,
                 ..
             } => Channel::user_conversation(user_id.as_ref(), target_user_id.as_ref()),
+            ChannelUpdate::UserMemoryPath {
+                ref user_id,
+                ref path,
+                ..
+            } => Channel::user_memory_path(user_id.as_ref(), path.as_ref()),
+            ChannelUpdate::UserActiveBranch { ref user_id, .. } => {
+                Channel::user_active_branch(user_id.as_ref())
+            }
+            ChannelUpdate::ServerMessage { .. } => Channel::server_messages(),
+            ChannelUpdate::MapVisual {
+                ref user_id,
+                ref shard_name,
+                ..
+            } => Channel::map_visual(user_id.as_ref(), shard_name.as_str()),
             ChannelUpdate::Other { ref channel, .. } => Channel::other(channel.as_ref()),
         }
     }
@@ -210,6 +269,8 @@ impl<'de> Visitor<'de> for ChannelUpdateVisitor<'de> {
         A: SeqAccess<'de>,
     {
         const ROOM_MAP_VIEW_PREFIX: &str = "roomMap2:";
+        const MAP_VISUAL_PREFIX: &str = "mapVisual:";
+        const SERVER_MESSAGES: &str = "server-message";
         const ROOM_PREFIX: &str = "room:";
         const ROOM_ERR_PREFIX: &str = "err@room:"; // TODO: generic error handling with this `err@` format.
         const USER_PREFIX: &str = "user:";
@@ -218,6 +279,8 @@ impl<'de> Visitor<'de> for ChannelUpdateVisitor<'de> {
         const USER_CREDITS: &str = "money";
         const USER_MESSAGE: &str = "newMessage";
         const USER_CONVERSATION_PREFIX: &str = "message:";
+        const USER_MEMORY_PATH_PREFIX: &str = "memory/";
+        const USER_ACTIVE_BRANCH: &str = "set-active-branch";
 
         let channel: &str = seq
             .next_element()?
@@ -234,9 +297,13 @@ impl<'de> Visitor<'de> for ChannelUpdateVisitor<'de> {
             }};
         }
 
-        if channel.starts_with(ROOM_MAP_VIEW_PREFIX) {
-            let room_name_and_shard = &channel[ROOM_MAP_VIEW_PREFIX.len()..];
-
+        if channel == SERVER_MESSAGES {
+            return Ok(ChannelUpdate::ServerMessage {
+                update: seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(2, &self))?,
+            });
+        } else if let Some(room_name_and_shard) = channel.strip_prefix(ROOM_MAP_VIEW_PREFIX) {
             let (shard_name, room_name) = {
                 let mut split = room_name_and_shard.splitn(2, "/");
                 match (split.next(), split.next()) {
@@ -259,9 +326,23 @@ impl<'de> Visitor<'de> for ChannelUpdateVisitor<'de> {
                     .next_element()?
                     .ok_or_else(|| de::Error::invalid_length(2, &self))?,
             });
-        } else if channel.starts_with(ROOM_PREFIX) {
-            let room_name_and_shard = &channel[ROOM_PREFIX.len()..];
+        } else if let Some(user_id_and_shard) = channel.strip_prefix(MAP_VISUAL_PREFIX) {
+            let (user_id, shard_name) = {
+                let mut split = user_id_and_shard.splitn(2, '/');
+                match (split.next(), split.next()) {
+                    (Some(user_id), Some(shard)) => (user_id, shard),
+                    _ => finish_other!(),
+                }
+            };
 
+            return Ok(ChannelUpdate::MapVisual {
+                user_id: user_id.to_owned().into(),
+                shard_name: shard_name.to_owned(),
+                update: seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(2, &self))?,
+            });
+        } else if let Some(room_name_and_shard) = channel.strip_prefix(ROOM_PREFIX) {
             let (shard_name, room_name) = {
                 let mut split = room_name_and_shard.splitn(2, '/');
                 match (split.next(), split.next()) {
@@ -285,9 +366,7 @@ impl<'de> Visitor<'de> for ChannelUpdateVisitor<'de> {
                     .next_element()?
                     .ok_or_else(|| de::Error::invalid_length(2, &self))?,
             });
-        } else if channel.starts_with(ROOM_ERR_PREFIX) {
-            let room_name_and_shard = &channel[ROOM_ERR_PREFIX.len()..];
-
+        } else if let Some(room_name_and_shard) = channel.strip_prefix(ROOM_ERR_PREFIX) {
             let (shard_name, room_name) = {
                 let mut split = room_name_and_shard.splitn(2, '/');
                 match (split.next(), split.next()) {
@@ -322,9 +401,7 @@ impl<'de> Visitor<'de> for ChannelUpdateVisitor<'de> {
                     shard_name: shard_name.map(ToOwned::to_owned),
                 });
             }
-        } else if channel.starts_with(USER_PREFIX) {
-            let user_id_and_part = &channel[USER_PREFIX.len()..];
-
+        } else if let Some(user_id_and_part) = channel.strip_prefix(USER_PREFIX) {
             let (user_id, sub_channel) = {
                 let mut split = user_id_and_part.splitn(2, '/');
                 match (split.next(), split.next()) {
@@ -366,10 +443,26 @@ impl<'de> Visitor<'de> for ChannelUpdateVisitor<'de> {
                             .ok_or_else(|| de::Error::invalid_length(2, &self))?,
                     })
                 }
+                USER_ACTIVE_BRANCH => {
+                    return Ok(ChannelUpdate::UserActiveBranch {
+                        user_id: user_id.to_owned().into(),
+                        update: seq
+                            .next_element()?
+                            .ok_or_else(|| de::Error::invalid_length(2, &self))?,
+                    })
+                }
                 sub_channel => {
-                    if sub_channel.starts_with(USER_CONVERSATION_PREFIX) {
-                        let target_user_id = &sub_channel[USER_CONVERSATION_PREFIX.len()..];
-
+                    if let Some(path) = sub_channel.strip_prefix(USER_MEMORY_PATH_PREFIX) {
+                        return Ok(ChannelUpdate::UserMemoryPath {
+                            user_id: user_id.to_owned().into(),
+                            path: path.to_owned().into(),
+                            update: seq
+                                .next_element()?
+                                .ok_or_else(|| de::Error::invalid_length(2, &self))?,
+                        });
+                    } else if let Some(target_user_id) =
+                        sub_channel.strip_prefix(USER_CONVERSATION_PREFIX)
+                    {
                         return Ok(ChannelUpdate::UserConversation {
                             user_id: user_id.to_owned().into(),
                             target_user_id: target_user_id.to_owned().into(),
@@ -396,3 +489,140 @@ impl<'de> Deserialize<'de> for ChannelUpdate<'static> {
         deserializer.deserialize_seq(ChannelUpdateVisitor::new())
     }
 }
+
+/// Serializes into the `[channel, update]` array the server sends.
+impl<'a> Serialize for ChannelUpdate<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let channel = match *self {
+            ChannelUpdate::NoRoomDetail {
+                room_name,
+                shard_name: Some(ref shard_name),
+            } => format!("err@room:{}/{}", shard_name, room_name),
+            ChannelUpdate::NoRoomDetail {
+                room_name,
+                shard_name: None,
+            } => format!("err@room:{}", room_name),
+            _ => self.channel().to_string(),
+        };
+
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&channel)?;
+        match *self {
+            ChannelUpdate::RoomMapView { ref update, .. } => tuple.serialize_element(update)?,
+            ChannelUpdate::RoomDetail { ref update, .. } => tuple.serialize_element(update)?,
+            ChannelUpdate::NoRoomDetail { .. } => {
+                tuple.serialize_element("subscribe limit reached")?
+            }
+            ChannelUpdate::UserCpu { ref update, .. } => tuple.serialize_element(update)?,
+            ChannelUpdate::UserConsole { ref update, .. } => tuple.serialize_element(update)?,
+            ChannelUpdate::UserCredits { ref update, .. } => tuple.serialize_element(update)?,
+            ChannelUpdate::UserMessage { ref update, .. } => tuple.serialize_element(update)?,
+            ChannelUpdate::UserConversation { ref update, .. } => {
+                tuple.serialize_element(update)?
+            }
+            ChannelUpdate::UserMemoryPath { ref update, .. } => tuple.serialize_element(update)?,
+            ChannelUpdate::UserActiveBranch { ref update, .. } => {
+                tuple.serialize_element(update)?
+            }
+            ChannelUpdate::ServerMessage { ref update } => tuple.serialize_element(update)?,
+            ChannelUpdate::MapVisual { ref update, .. } => tuple.serialize_element(update)?,
+            ChannelUpdate::Other { ref update, .. } => tuple.serialize_element(update)?,
+        }
+        tuple.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ChannelUpdate;
+
+    fn parse_and_round_trip(json: &str, channel: &str) -> ChannelUpdate<'static> {
+        let update: ChannelUpdate = serde_json::from_str(json).unwrap();
+
+        assert_eq!(update.channel().to_string(), channel);
+
+        update
+    }
+
+    #[test]
+    fn parse_memory_path_update() {
+        let update = parse_and_round_trip(
+            r#"["user:57874d42d0ae911e3bd15bbc/memory/stats.cpu","{\"used\":12.5}"]"#,
+            "user:57874d42d0ae911e3bd15bbc/memory/stats.cpu",
+        );
+
+        match update {
+            ChannelUpdate::UserMemoryPath {
+                ref path,
+                ref update,
+                ..
+            } => {
+                assert_eq!(path, "stats.cpu");
+                assert_eq!(update.value, Some(json!({"used": 12.5})));
+            }
+            ref other => panic!("expected memory path update, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_active_branch_update() {
+        let update = parse_and_round_trip(
+            r#"["user:57874d42d0ae911e3bd15bbc/set-active-branch",{"activeName":"activeSim","branch":"experimental"}]"#,
+            "user:57874d42d0ae911e3bd15bbc/set-active-branch",
+        );
+
+        match update {
+            ChannelUpdate::UserActiveBranch { ref update, .. } => {
+                assert_eq!(update.branch, "experimental");
+            }
+            ref other => panic!("expected active branch update, found {:?}", other),
+        }
+        assert_eq!(update.user_id(), Some("57874d42d0ae911e3bd15bbc"));
+    }
+
+    #[test]
+    fn parse_server_message_update() {
+        let update = parse_and_round_trip(
+            r#"["server-message","Maintenance in 10 minutes"]"#,
+            "server-message",
+        );
+
+        match update {
+            ChannelUpdate::ServerMessage { ref update } => {
+                assert_eq!(update.message, "Maintenance in 10 minutes");
+            }
+            ref other => panic!("expected server message, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_map_visual_update() {
+        let update: ChannelUpdate = serde_json::from_str(
+            r#"["mapVisual:57874d42d0ae911e3bd15bbc/shard2","{\"t\":\"c\",\"x\":25,\"y\":25,\"n\":\"E1N1\",\"s\":{\"radius\":10}}\n"]"#,
+        )
+        .unwrap();
+
+        match update {
+            ChannelUpdate::MapVisual {
+                ref user_id,
+                ref shard_name,
+                ref update,
+            } => {
+                assert_eq!(user_id, "57874d42d0ae911e3bd15bbc");
+                assert_eq!(shard_name, "shard2");
+                assert_eq!(update.visuals.len(), 1);
+            }
+            ref other => panic!("expected map visual update, found {:?}", other),
+        }
+
+        assert_eq!(
+            update.channel().to_string(),
+            "mapVisual:57874d42d0ae911e3bd15bbc/shard2"
+        );
+        assert_eq!(update.shard_name(), Some("shard2"));
+        assert_eq!(update.user_id(), Some("57874d42d0ae911e3bd15bbc"));
+    }
+}
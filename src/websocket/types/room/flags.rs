@@ -1,10 +1,17 @@
 //! Types for user flags which can appear in rooms.
 use std::fmt;
 
-use serde::de::{Deserializer, Error, Unexpected, Visitor};
+use serde::{
+    de::{Deserializer, Error, Unexpected, Visitor},
+    Deserialize, Serialize, Serializer,
+};
 
 /// Single flag.
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+///
+/// Rooms send flags encoded in a single string, but this serializes as a regular structure with
+/// each color as its numeric code.
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
 pub struct Flag {
     /// The name of the flag, unique per user.
     pub name: String,
@@ -90,6 +97,24 @@ impl FlagColor {
     }
 }
 
+impl Serialize for FlagColor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+impl<'de> Deserialize<'de> for FlagColor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        FlagColor::from_serde(u8::deserialize(deserializer)?)
+    }
+}
+
 struct FlagStringVisitor;
 
 impl<'de> Visitor<'de> for FlagStringVisitor {
@@ -181,3 +206,61 @@ where
 {
     deserializer.deserialize_option(FlagStringVisitor)
 }
+
+/// Serializes flags into the string format room updates use, the reverse of `deserialize_flags`.
+pub(super) fn serialize_flags<S>(flags: &[Flag], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let encoded = flags
+        .iter()
+        .map(|flag| {
+            format!(
+                "{}~{}~{}~{}~{}",
+                flag.name, flag.primary_color as u8, flag.secondary_color as u8, flag.x, flag.y
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("|");
+
+    serializer.serialize_str(&encoded)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_and_round_trip_flags() {
+        let flags = deserialize_flags(&json!("Flag1~1~10~25~5|attack~5~2~0~49")).unwrap();
+
+        assert_eq!(
+            flags,
+            vec![
+                Flag {
+                    name: "Flag1".to_owned(),
+                    primary_color: FlagColor::Red,
+                    secondary_color: FlagColor::White,
+                    x: 25,
+                    y: 5,
+                },
+                Flag {
+                    name: "attack".to_owned(),
+                    primary_color: FlagColor::Green,
+                    secondary_color: FlagColor::Purple,
+                    x: 0,
+                    y: 49,
+                },
+            ]
+        );
+
+        let json = serde_json::to_value(&flags).unwrap();
+
+        assert_eq!(json[0]["primaryColor"], json!(1));
+        assert_eq!(serde_json::from_value::<Vec<Flag>>(json).unwrap(), flags);
+        assert!(serde_json::from_value::<FlagColor>(json!(11)).is_err());
+
+        let encoded = serialize_flags(&flags, serde_json::value::Serializer).unwrap();
+        assert_eq!(encoded, json!("Flag1~1~10~25~5|attack~5~2~0~49"));
+    }
+}
@@ -13,14 +13,19 @@ pub mod room_object_macros;
 #[macro_use]
 pub mod resources;
 pub mod objects;
+pub mod visual;
 
-use self::flags::{deserialize_flags, Flag};
+use self::{
+    flags::{deserialize_flags, serialize_flags, Flag},
+    visual::RoomVisual,
+};
 
 /// Update for detailed room information.
-#[derive(serde::Deserialize, Clone, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct RoomUpdate {
     /// The game time when this update was created.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub game_time: Option<u32>,
     /// Information on the room "mode".
     pub info: RoomUpdateInfo,
@@ -38,27 +43,43 @@ pub struct RoomUpdate {
     /// This will always be present when there are flags, even if
     /// no flags have changed.
     #[serde(deserialize_with = "deserialize_flags")]
-    #[serde(default)]
+    #[serde(serialize_with = "serialize_flags")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub flags: Vec<Flag>,
     /// The logged in user's visuals for this room.
     ///
-    /// Represented by a series of json objects separated by `\n`.
+    /// Represented by a series of json objects separated by `\n`. Use [`RoomUpdate::visuals`]
+    /// to parse them.
     ///
-    /// TODO: parse this further.
+    /// [`RoomUpdate::visuals`]: #method.visuals
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub visual: Option<String>,
     /// Detailed information on all users that have things in this room.
     ///
     /// Parse using `RoomUserInfo`.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub users: Option<HashMap<String, serde_json::Value>>,
     /// Phantom data in order to allow adding any additional fields in the future.
     #[serde(skip)]
     _phantom: (),
 }
 
+impl RoomUpdate {
+    /// Parses the logged in user's visuals for this room, skipping any unrecognized entries.
+    ///
+    /// Returns an empty list if this update carries no visuals.
+    pub fn visuals(&self) -> Vec<RoomVisual> {
+        self.visual
+            .as_ref()
+            .map(|raw| RoomVisual::parse_all(raw))
+            .unwrap_or_default()
+    }
+}
+
 /// "info" struct to go with room update.
 ///
 /// TODO: find all variants and parse into enum.
-#[derive(serde::Deserialize, Clone, Hash, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Hash, Debug)]
 pub struct RoomUpdateInfo {
     /// Usually "world" for regular rooms.
     pub mode: Option<String>,
@@ -4,7 +4,7 @@ use crate::data::RoomName;
 /// Type of structure (not general room object).
 ///
 /// Currently only used when decoding ConstructionSites.
-#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum StructureType {
     /// StructureSpawn structure type
@@ -45,6 +45,8 @@ pub enum StructureType {
     Container,
     /// StructureNuker structure type
     Nuker,
+    /// StructureFactory structure type
+    Factory,
 }
 
 basic_updatable!(StructureType);
@@ -97,6 +99,8 @@ mod test {
 
         let obj = ConstructionSite::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             ConstructionSite {
@@ -104,6 +108,7 @@ mod test {
                 room: RoomName::new("E9S32").unwrap(),
                 x: 4,
                 y: 25,
+                effects: Vec::new(),
                 user: "59cec9e20dd629146b767d96".to_owned(),
                 name: None,
                 progress: 211,
@@ -84,6 +84,8 @@ mod test {
 
         let obj = StructureContainer::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         match obj {
             StructureContainer {
                 capacity: 2000,
@@ -27,7 +27,7 @@ implement_update_for! {
 
 with_update_struct! {
     /// A struct describing a room's reservation.
-    #[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+    #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     pub struct ControllerReservation {
         /// The user ID of the user reserving this controller.
@@ -189,6 +189,8 @@ mod test {
 
         let mut obj = StructureController::deserialize(&json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureController {
@@ -196,6 +198,7 @@ mod test {
                 room: RoomName::new("E4S61").unwrap(),
                 x: 16,
                 y: 39,
+                effects: Vec::new(),
                 hits: 0,
                 hits_max: 0,
                 level: 8,
@@ -231,6 +234,7 @@ mod test {
                 room: RoomName::new("E4S61").unwrap(),
                 x: 16,
                 y: 39,
+                effects: Vec::new(),
                 hits: 0,
                 hits_max: 0,
                 level: 8,
@@ -280,6 +284,8 @@ mod test {
 
         let mut obj = StructureController::deserialize(&json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureController {
@@ -287,6 +293,7 @@ mod test {
                 room: RoomName::new("W12S55").unwrap(),
                 x: 22,
                 y: 37,
+                effects: Vec::new(),
                 downgrade_time: None,
                 hits: 0,
                 hits_max: 0,
@@ -322,6 +329,7 @@ mod test {
                 room: RoomName::new("W12S55").unwrap(),
                 x: 22,
                 y: 37,
+                effects: Vec::new(),
                 downgrade_time: None,
                 hits: 0,
                 hits_max: 0,
@@ -349,6 +357,7 @@ mod test {
             room: RoomName::new("E4S61").unwrap(),
             x: 16,
             y: 39,
+            effects: Vec::new(),
             hits: 0,
             hits_max: 0,
             level: 8,
@@ -383,6 +392,7 @@ mod test {
                 room: RoomName::new("E4S61").unwrap(),
                 x: 16,
                 y: 39,
+                effects: Vec::new(),
                 hits: 0,
                 hits_max: 0,
                 level: 8,
@@ -416,6 +426,7 @@ mod test {
             }
         );
         let obj = StructureController::deserialize(&json).unwrap();
+        super::super::assert_round_trip(&obj);
 
         assert_eq!(
             obj,
@@ -424,6 +435,7 @@ mod test {
                 room: RoomName::new("E6S31").unwrap(),
                 x: 35,
                 y: 27,
+                effects: Vec::new(),
                 downgrade_time: None,
                 hits: 0,
                 hits_max: 0,
@@ -472,6 +484,7 @@ mod test {
             }
         );
         let obj = StructureController::deserialize(&json).unwrap();
+        super::super::assert_round_trip(&obj);
         assert_eq!(
             obj,
             StructureController {
@@ -479,6 +492,7 @@ mod test {
                 room: RoomName::new("E7S27").unwrap(),
                 x: 17,
                 y: 18,
+                effects: Vec::new(),
                 downgrade_time: Some(7131716),
                 hits: 0,
                 hits_max: 0,
@@ -508,6 +522,7 @@ mod test {
             room: RoomName::new("E7S27").unwrap(),
             x: 17,
             y: 18,
+            effects: Vec::new(),
             downgrade_time: Some(7131716),
             hits: 0,
             hits_max: 0,
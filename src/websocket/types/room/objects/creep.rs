@@ -8,7 +8,7 @@ with_update_struct! {
     /// A struct describing a creep part.
     ///
     /// TODO: parse creep part boosts.
-    #[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+    #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     pub struct CreepPart {
         /// Part health, out of 100.
@@ -55,7 +55,7 @@ basic_updatable!(CreepPartType);
 
 with_update_struct! {
     /// A struct describing a creep's message conveyed with `say`.
-    #[derive(serde::Deserialize, Default, Clone, Debug, PartialEq)]
+    #[derive(serde::Deserialize, serde::Serialize, Default, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     pub struct CreepMessage {
         /// If true, the message is visible to all players.
@@ -72,7 +72,7 @@ with_update_struct! {
 
 with_update_struct! {
     /// A struct describing a creep's actions.
-    #[derive(serde::Deserialize, Default, Clone, Debug, PartialEq)]
+    #[derive(serde::Deserialize, serde::Serialize, Default, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     pub struct CreepActions {
         /// The location this creep harvested last tick.
@@ -244,6 +244,8 @@ mod test {
 
         let obj = Creep::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         match obj {
             Creep {
                 ref id,
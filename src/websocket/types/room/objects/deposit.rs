@@ -0,0 +1,120 @@
+//! `Deposit` data description.
+use super::super::resources::ResourceType;
+use crate::data::RoomName;
+
+/// The type of resource a deposit yields when harvested.
+#[derive(
+    serde::Serialize, serde::Deserialize, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum DepositType {
+    /// RESOURCE_MIST: "mist",
+    Mist,
+    /// RESOURCE_BIOMASS: "biomass",
+    Biomass,
+    /// RESOURCE_METAL: "metal",
+    Metal,
+    /// RESOURCE_SILICON: "silicon",
+    Silicon,
+}
+
+basic_updatable!(DepositType);
+
+impl DepositType {
+    /// Gets the resource type harvested from this kind of deposit.
+    pub fn resource_type(self) -> ResourceType {
+        match self {
+            DepositType::Mist => ResourceType::Mist,
+            DepositType::Biomass => ResourceType::Biomass,
+            DepositType::Metal => ResourceType::Metal,
+            DepositType::Silicon => ResourceType::Silicon,
+        }
+    }
+}
+
+with_base_fields_and_update_struct! {
+    /// A deposit object - a naturally occurring source of commodity raw materials found in highway
+    /// rooms.
+    #[derive(Clone, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Deposit {
+        /// The type of resource this deposit yields.
+        pub deposit_type: DepositType,
+        /// The total amount of resources harvested from this deposit so far. The harvesting
+        /// cooldown increases as this grows.
+        #[serde(default)]
+        pub harvested: u32,
+        /// The game time at which this deposit can next be harvested, if it has been harvested
+        /// recently.
+        pub cooldown_time: Option<u32>,
+        /// The game time at which this deposit will disappear if it is not harvested before then.
+        pub decay_time: u32,
+    }
+
+    /// The update structure for a `Deposit`.
+    #[derive(Clone, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DepositUpdate {
+        - deposit_type: DepositType,
+        - harvested: u32,
+        - cooldown_time: Option<u32>,
+        - decay_time: u32,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use crate::data::RoomName;
+
+    use super::super::super::resources::ResourceType;
+    use super::{Deposit, DepositType};
+
+    #[test]
+    fn parse_deposit_and_update() {
+        let json = json!({
+            "_id": "5e8a3d1f2c3b4a5d6e7f8091",
+            "type": "deposit",
+            "x": 14,
+            "y": 31,
+            "room": "W40N27",
+            "depositType": "silicon",
+            "harvested": 0,
+            "decayTime": 23510000
+        });
+
+        let mut obj = Deposit::deserialize(json).unwrap();
+
+        super::super::assert_round_trip(&obj);
+
+        assert_eq!(
+            obj,
+            Deposit {
+                room: RoomName::new("W40N27").unwrap(),
+                x: 14,
+                y: 31,
+                effects: Vec::new(),
+                id: "5e8a3d1f2c3b4a5d6e7f8091".to_owned(),
+                deposit_type: DepositType::Silicon,
+                harvested: 0,
+                cooldown_time: None,
+                decay_time: 23510000,
+            }
+        );
+
+        obj.update(
+            serde_json::from_value(json!({
+                "harvested": 72,
+                "cooldownTime": 23505003,
+                "decayTime": 23515000
+            }))
+            .unwrap(),
+        );
+
+        assert_eq!(obj.deposit_type.resource_type(), ResourceType::Silicon);
+        assert_eq!(obj.harvested, 72);
+        assert_eq!(obj.cooldown_time, Some(23505003));
+        assert_eq!(obj.decay_time, 23515000);
+    }
+}
@@ -65,12 +65,15 @@ mod test {
 
         let mut obj = StructureExtension::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureExtension {
                 room: RoomName::new("E9S32").unwrap(),
                 x: 21,
                 y: 24,
+                effects: Vec::new(),
                 id: "5bef67919e7eaa3015aadfe2".to_owned(),
                 store: store! { Energy: 200 },
                 store_capacity_resource: store! { Energy: 200 },
@@ -98,6 +101,7 @@ mod test {
                 room: RoomName::new("E9S32").unwrap(),
                 x: 21,
                 y: 24,
+                effects: Vec::new(),
                 id: "5bef67919e7eaa3015aadfe2".to_owned(),
                 store: store! {},
                 store_capacity_resource: store! { Energy: 200 },
@@ -51,12 +51,15 @@ mod test {
 
         let obj = StructureExtractor::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureExtractor {
                 room: RoomName::new("E8S29").unwrap(),
                 x: 29,
                 y: 40,
+                effects: Vec::new(),
                 id: "5cb56020f7d8904f5df7a1ae".to_owned(),
                 hits: 500,
                 hits_max: 500,
@@ -80,12 +83,15 @@ mod test {
 
         let obj = StructureExtractor::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureExtractor {
                 room: RoomName::new("W5S35").unwrap(),
                 x: 9,
                 y: 36,
+                effects: Vec::new(),
                 id: "59834302d7922107c0781386".to_owned(),
                 hits: 0,
                 hits_max: 0,
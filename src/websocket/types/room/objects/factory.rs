@@ -0,0 +1,140 @@
+//! `StructureFactory` data description.
+use super::super::resources::Store;
+use crate::data::RoomName;
+
+with_structure_fields_and_update_struct! {
+    /// A factory structure - a structure which can produce commodities from base minerals and
+    /// other commodities.
+    #[derive(Clone, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    pub struct StructureFactory {
+        /// The user ID of the owner of this structure.
+        pub user: String,
+        /// Whether or not this structure is non-functional due to a degraded controller.
+        #[serde(default, rename = "off")]
+        pub disabled: bool,
+        /// The current amount of resources held in this structure.
+        pub store: Store,
+        /// The total amount of resources this structure can hold.
+        #[serde(rename = "storeCapacity")]
+        pub capacity: i32,
+        /// The game time at which this factory will next be able to produce a commodity, if it has
+        /// produced anything recently.
+        pub cooldown_time: Option<u32>,
+        /// The level of this factory, set by a power creep using `PWR_OPERATE_FACTORY` for the first
+        /// time. None if the factory has never been operated.
+        pub level: Option<u32>,
+        /// Whether or not an attack on this structure will send an email to the owner automatically.
+        pub notify_when_attacked: bool,
+    }
+
+    /// The update structure for a `StructureFactory`.
+    #[derive(Clone, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct StructureFactoryUpdate {
+        - user: String,
+        #[serde(rename = "off")]
+        - disabled: bool,
+        - store: Store,
+        #[serde(rename = "storeCapacity")]
+        - capacity: i32,
+        - cooldown_time: Option<u32>,
+        - level: Option<u32>,
+        - notify_when_attacked: bool,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use crate::data::RoomName;
+
+    use super::{
+        super::{ConstructionSite, StructureType},
+        StructureFactory,
+    };
+
+    #[test]
+    fn parse_factory_and_update() {
+        let json = json!({
+            "_id": "5db0b4b3a8d2d6c30a8b2a6b",
+            "type": "factory",
+            "x": 21,
+            "y": 36,
+            "room": "E9S32",
+            "notifyWhenAttacked": true,
+            "user": "5a8466038f866773f59fa6c8",
+            "store": {
+                "energy": 12400,
+                "U": 3000
+            },
+            "storeCapacity": 50000,
+            "hits": 1000,
+            "hitsMax": 1000
+        });
+
+        let mut obj = StructureFactory::deserialize(json).unwrap();
+
+        super::super::assert_round_trip(&obj);
+
+        assert_eq!(
+            obj,
+            StructureFactory {
+                room: RoomName::new("E9S32").unwrap(),
+                x: 21,
+                y: 36,
+                effects: Vec::new(),
+                id: "5db0b4b3a8d2d6c30a8b2a6b".to_owned(),
+                hits: 1000,
+                hits_max: 1000,
+                user: "5a8466038f866773f59fa6c8".to_owned(),
+                disabled: false,
+                store: store! { Energy: 12400, Utrium: 3000 },
+                capacity: 50000,
+                cooldown_time: None,
+                level: None,
+                notify_when_attacked: true,
+            }
+        );
+
+        obj.update(
+            serde_json::from_value(json!({
+                "store": {
+                    "energy": 12200,
+                    "U": 2500
+                },
+                "cooldownTime": 23469612,
+                "level": 2
+            }))
+            .unwrap(),
+        );
+
+        assert_eq!(obj.store, store! { Energy: 12200, Utrium: 2500 });
+        assert_eq!(obj.cooldown_time, Some(23469612));
+        assert_eq!(obj.level, Some(2));
+    }
+
+    #[test]
+    fn parse_factory_construction_site() {
+        let json = json!({
+            "_id": "5db0b2f1a8d2d6c30a8b2a5e",
+            "type": "constructionSite",
+            "x": 21,
+            "y": 36,
+            "room": "E9S32",
+            "user": "5a8466038f866773f59fa6c8",
+            "name": null,
+            "progress": 0,
+            "progressTotal": 100000,
+            "structureType": "factory"
+        });
+
+        let obj = ConstructionSite::deserialize(json).unwrap();
+
+        super::super::assert_round_trip(&obj);
+
+        assert_eq!(obj.structure_type, StructureType::Factory);
+        assert_eq!(obj.progress_total, 100000);
+    }
+}
@@ -0,0 +1,208 @@
+//! `StructureInvaderCore` data description.
+use crate::data::RoomName;
+
+with_update_struct! {
+    /// A struct describing the creep an invader core is currently spawning.
+    #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    pub struct InvaderCoreSpawning {
+        /// The name of the creep being spawned.
+        pub name: String,
+        /// The total number of game ticks needed to spawn this creep.
+        #[serde(rename = "needTime")]
+        pub total_time: u32,
+        /// The game tick on which the creep will be spawned.
+        pub spawn_time: u32,
+    }
+
+    /// The update structure for an `InvaderCoreSpawning`.
+    #[derive(serde::Deserialize, Clone, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct InvaderCoreSpawningUpdate { ... }
+}
+
+with_structure_fields_and_update_struct! {
+    /// An invader core - the center of an NPC stronghold in a source keeper room, or a lesser core
+    /// reserving a room in the open world.
+    #[derive(Clone, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    pub struct StructureInvaderCore {
+        /// The user ID of the owner of this structure - always the invader NPC user.
+        pub user: String,
+        /// The level of this core - 0 for lesser cores which only reserve rooms, and 1-5 for
+        /// strongholds.
+        pub level: u32,
+        /// The name of the stronghold template this core was deployed from.
+        pub template_name: Option<String>,
+        /// The unique ID of the stronghold this core belongs to.
+        pub stronghold_id: Option<String>,
+        /// The game time at which this core finishes deploying and becomes active. None once the
+        /// core is active.
+        pub deploy_time: Option<u32>,
+        /// The game time at which this core will next spawn a lesser core in a nearby room.
+        pub next_expand_time: Option<u32>,
+        /// The game time at which this core will collapse, if it has not been destroyed by then.
+        pub decay_time: Option<u32>,
+        /// The creep this core is currently spawning, if any.
+        pub spawning: Option<InvaderCoreSpawning>,
+    }
+
+    /// The update structure for a `StructureInvaderCore`.
+    #[derive(Clone, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct StructureInvaderCoreUpdate { ... }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use crate::{
+        data::RoomName,
+        websocket::types::room::objects::{Effect, PowerType},
+    };
+
+    use super::{InvaderCoreSpawning, StructureInvaderCore};
+
+    #[test]
+    fn parse_invader_core_and_update() {
+        let json = json!({
+            "_id": "5f5e0f4fd6a8f0a3c6fbd4a2",
+            "type": "invaderCore",
+            "x": 24,
+            "y": 20,
+            "room": "W35N25",
+            "user": "2",
+            "level": 3,
+            "hits": 100000,
+            "hitsMax": 100000,
+            "templateName": "bunker3",
+            "strongholdId": "5f5e0f4fd6a8f0a3c6fbd4a1",
+            "deployTime": 22650000,
+            "nextExpandTime": 22651500
+        });
+
+        let mut obj = StructureInvaderCore::deserialize(json).unwrap();
+
+        super::super::assert_round_trip(&obj);
+
+        assert_eq!(
+            obj,
+            StructureInvaderCore {
+                room: RoomName::new("W35N25").unwrap(),
+                x: 24,
+                y: 20,
+                effects: Vec::new(),
+                id: "5f5e0f4fd6a8f0a3c6fbd4a2".to_owned(),
+                hits: 100000,
+                hits_max: 100000,
+                user: "2".to_owned(),
+                level: 3,
+                template_name: Some("bunker3".to_owned()),
+                stronghold_id: Some("5f5e0f4fd6a8f0a3c6fbd4a1".to_owned()),
+                deploy_time: Some(22650000),
+                next_expand_time: Some(22651500),
+                decay_time: None,
+                spawning: None,
+            }
+        );
+
+        obj.update(
+            serde_json::from_value(json!({
+                "deployTime": null,
+                "decayTime": 22725000,
+                "spawning": {
+                    "name": "bunker3_defender_0",
+                    "needTime": 30,
+                    "spawnTime": 22650030
+                }
+            }))
+            .unwrap(),
+        );
+
+        assert_eq!(obj.deploy_time, None);
+        assert_eq!(obj.decay_time, Some(22725000));
+        assert_eq!(
+            obj.spawning,
+            Some(InvaderCoreSpawning {
+                name: "bunker3_defender_0".to_owned(),
+                total_time: 30,
+                spawn_time: 22650030,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_lesser_invader_core() {
+        let json = json!({
+            "_id": "5f5f2c1e8b3f4a6d1c2e3f40",
+            "type": "invaderCore",
+            "x": 31,
+            "y": 12,
+            "room": "W37N26",
+            "user": "2",
+            "level": 0,
+            "hits": 100000,
+            "hitsMax": 100000,
+            "strongholdId": "5f5e0f4fd6a8f0a3c6fbd4a1"
+        });
+
+        let obj = StructureInvaderCore::deserialize(json).unwrap();
+
+        super::super::assert_round_trip(&obj);
+
+        assert_eq!(obj.level, 0);
+        assert_eq!(obj.template_name, None);
+    }
+
+    #[test]
+    fn parse_invulnerable_invader_core() {
+        let json = json!({
+            "_id": "5f6a1b2c3d4e5f6a7b8c9d0e",
+            "type": "invaderCore",
+            "x": 25,
+            "y": 25,
+            "room": "W35N25",
+            "user": "2",
+            "level": 4,
+            "hits": 100000,
+            "hitsMax": 100000,
+            "templateName": "bunker4",
+            "strongholdId": "5f6a1b2c3d4e5f6a7b8c9d0d",
+            "effects": [
+                {
+                    "effect": 1001,
+                    "endTime": 22655000,
+                    "duration": 5000
+                }
+            ]
+        });
+
+        let mut obj = StructureInvaderCore::deserialize(json).unwrap();
+
+        super::super::assert_round_trip(&obj);
+
+        assert_eq!(
+            obj.effects,
+            vec![Effect {
+                power: PowerType::Invulnerability,
+                level: None,
+                ends_at: 22655000,
+            }]
+        );
+
+        obj.update(
+            serde_json::from_value(json!({
+                "effects": {
+                    "0": {
+                        "endTime": 22656000
+                    }
+                }
+            }))
+            .unwrap(),
+        );
+
+        assert_eq!(obj.effects[0].power, PowerType::Invulnerability);
+        assert_eq!(obj.effects[0].ends_at, 22656000);
+    }
+}
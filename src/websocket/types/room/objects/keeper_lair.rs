@@ -45,12 +45,15 @@ mod test {
 
         let mut obj = StructureKeeperLair::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureKeeperLair {
                 room: RoomName::new("E4N4").unwrap(),
                 x: 16,
                 y: 1,
+                effects: Vec::new(),
                 id: "55c34a6b5be41a0a6e80c325".to_owned(),
                 next_spawn_time: None,
             }
@@ -69,6 +72,7 @@ mod test {
                 room: RoomName::new("E4N4").unwrap(),
                 x: 16,
                 y: 1,
+                effects: Vec::new(),
                 id: "55c34a6b5be41a0a6e80c325".to_owned(),
                 next_spawn_time: Some(20000000),
             }
@@ -89,12 +93,15 @@ mod test {
 
         let mut obj = StructureKeeperLair::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureKeeperLair {
                 room: RoomName::new("W14S5").unwrap(),
                 x: 22,
                 y: 13,
+                effects: Vec::new(),
                 id: "55c34a6b5be41a0a6e80c6ce".to_owned(),
                 next_spawn_time: Some(20184048),
             }
@@ -113,6 +120,7 @@ mod test {
                 room: RoomName::new("W14S5").unwrap(),
                 x: 22,
                 y: 13,
+                effects: Vec::new(),
                 id: "55c34a6b5be41a0a6e80c6ce".to_owned(),
                 next_spawn_time: None,
             }
@@ -42,7 +42,7 @@ with_structure_fields_and_update_struct! {
 
 with_update_struct! {
     /// A struct describing the source labs for a lab performing a mineral reaction.
-    #[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+    #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
     pub struct LabActionTarget {
         /// The x position of the first source lab.
         pub x1: u32,
@@ -61,7 +61,7 @@ with_update_struct! {
 
 with_update_struct! {
     /// A struct describing a lab's actions.
-    #[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+    #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     pub struct StructureLabActions {
         /// The two source labs that provided minerals for the reaction that was run last tick.
@@ -81,6 +81,7 @@ mod test {
 
     use crate::data::RoomName;
 
+    use super::super::{Effect, PowerType};
     use super::{LabActionTarget, StructureLab, StructureLabActions};
 
     #[test]
@@ -181,12 +182,19 @@ mod test {
 
         let mut obj = StructureLab::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureLab {
                 room: RoomName::new("E9S32").unwrap(),
                 x: 24,
                 y: 17,
+                effects: vec![Effect {
+                    power: PowerType::OperateLab,
+                    level: Some(4),
+                    ends_at: 29752073,
+                }],
                 id: "5aebc6e4ee797138fa7b4a4f".to_owned(),
                 store: store! { Energy: 2000, UtriumOxide: 45 },
                 store_capacity_resource: store! { Energy: 2000, UtriumOxide: 3000 },
@@ -264,6 +272,11 @@ mod test {
                 room: RoomName::new("E9S32").unwrap(),
                 x: 24,
                 y: 17,
+                effects: vec![Effect {
+                    power: PowerType::OperateLab,
+                    level: Some(4),
+                    ends_at: 29752073,
+                }],
                 id: "5aebc6e4ee797138fa7b4a4f".to_owned(),
                 store: store! { Energy: 2000, UtriumOxide: 50 },
                 store_capacity_resource: store! { Energy: 2000, UtriumOxide: 3000 },
@@ -375,12 +388,15 @@ mod test {
 
         let obj = StructureLab::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureLab {
                 room: RoomName::new("W44S12").unwrap(),
                 x: 24,
                 y: 28,
+                effects: Vec::new(),
                 id: "5d2aca5c5e41f216fb099492".to_owned(),
                 store: store! { Energy: 2000 },
                 store_capacity_resource: store! { Energy: 2000 },
@@ -423,12 +439,15 @@ mod test {
 
         let obj = StructureLab::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureLab {
                 room: RoomName::new("W31N48").unwrap(),
                 x: 28,
                 y: 31,
+                effects: Vec::new(),
                 id: "583f5dba29a7bf89761d7511".to_owned(),
                 store: store! { Energy: 2000 },
                 store_capacity_resource: store! { Energy: 2000 },
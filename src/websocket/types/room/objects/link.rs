@@ -43,7 +43,7 @@ with_structure_fields_and_update_struct! {
 
 with_update_struct! {
     /// A struct describing a link's actions.
-    #[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+    #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     pub struct StructureLinkActions {
         /// The x,y position the link last transfered energy to.
@@ -91,12 +91,15 @@ mod test {
 
         let mut obj = StructureLink::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureLink {
                 room: RoomName::new("E17N55").unwrap(),
                 x: 9,
                 y: 6,
+                effects: Vec::new(),
                 id: "57fdb3ea3dad49a17265ecea".to_owned(),
                 store: store! { Energy: 100 },
                 store_capacity_resource: store! { Energy: 800 },
@@ -177,6 +180,7 @@ mod test {
                 room: RoomName::new("E17N55").unwrap(),
                 x: 9,
                 y: 6,
+                effects: Vec::new(),
                 id: "57fdb3ea3dad49a17265ecea".to_owned(),
                 store: store! { Energy: 50 },
                 store_capacity_resource: store! { Energy: 800 },
@@ -50,6 +50,8 @@ mod test {
 
         let obj = Mineral::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             Mineral {
@@ -57,6 +59,7 @@ mod test {
                 room: RoomName::new("E4S61").unwrap(),
                 x: 14,
                 y: 21,
+                effects: Vec::new(),
                 density: 3,
                 mineral_amount: 65590.0,
                 mineral_type: ResourceType::Hydrogen,
@@ -5,26 +5,35 @@
 //!
 //! Reading the source code is definitely possible. But there may be some investment in reading
 //! each of the macros defined and used here, and it will be much easier to just read the documentation.
+use serde::{Deserialize, Deserializer};
+
+use super::resources::Store;
 use crate::RoomName;
 
 mod construction_site;
 mod container;
 mod controller;
 mod creep;
+mod deposit;
 mod extension;
 mod extractor;
+mod factory;
+mod invader_core;
 mod keeper_lair;
 mod lab;
 mod link;
 mod mineral;
+mod nuke;
 mod nuker;
 mod observer;
 mod portal;
 mod power_bank;
+mod power_creep;
 mod power_spawn;
 mod rampart;
 mod resource;
 mod road;
+mod ruin;
 mod shared;
 mod source;
 mod spawn;
@@ -35,14 +44,15 @@ mod tower;
 mod wall;
 
 pub use self::{
-    construction_site::*, container::*, controller::*, creep::*, extension::*, extractor::*,
-    keeper_lair::*, lab::*, link::*, mineral::*, nuker::*, observer::*, portal::*, power_bank::*,
-    power_spawn::*, rampart::*, resource::*, road::*, shared::*, source::*, spawn::*, storage::*,
+    construction_site::*, container::*, controller::*, creep::*, deposit::*, extension::*,
+    extractor::*, factory::*, invader_core::*, keeper_lair::*, lab::*, link::*, mineral::*,
+    nuke::*, nuker::*, observer::*, portal::*, power_bank::*, power_creep::*, power_spawn::*,
+    rampart::*, resource::*, road::*, ruin::*, shared::*, source::*, spawn::*, storage::*,
     terminal::*, tombstone::*, tower::*, wall::*,
 };
 
 /// Enum describing all known room objects.
-#[derive(serde::Deserialize, Clone, Debug)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase", tag = "type")]
 pub enum KnownRoomObject {
     /// Source object.
@@ -97,6 +107,18 @@ pub enum KnownRoomObject {
     Resource(Resource),
     /// Construction site
     ConstructionSite(ConstructionSite),
+    /// Factory owned structure.
+    Factory(StructureFactory),
+    /// Invader core NPC structure.
+    InvaderCore(StructureInvaderCore),
+    /// Deposit naturally occurring object.
+    Deposit(Deposit),
+    /// Ruin of a destroyed structure
+    Ruin(Ruin),
+    /// Nuke in flight
+    Nuke(Nuke),
+    /// Power creep
+    PowerCreep(PowerCreep),
 }
 
 macro_rules! match_many_variants {
@@ -110,6 +132,18 @@ macro_rules! match_many_variants {
                 KnownRoomObject::$enum_name($name) => $code,
             )*
         }
+    );
+    (
+        $src:ident, ($(
+            $enum_name:ident
+        ),*) ($name:ident) => $code:expr, _ => $default:expr
+    ) => (
+        match $src {
+            $(
+                KnownRoomObject::$enum_name($name) => $code,
+            )*
+            _ => $default,
+        }
     )
 }
 
@@ -121,7 +155,7 @@ macro_rules! match_obj_variants {
             $src,
             (Source, Mineral, Spawn, Extension, Extractor, Wall, Road, Rampart, KeeperLair, Controller, Portal,
             Link, Storage, Tower, Observer, PowerBank, PowerSpawn, Lab, Terminal, Container, Nuker, Tombstone, Creep,
-            Resource, ConstructionSite)
+            Resource, ConstructionSite, Factory, InvaderCore, Deposit, Ruin, Nuke, PowerCreep)
             ($name) => $code
         )
     )
@@ -156,6 +190,327 @@ impl KnownRoomObject {
     pub fn room(&self) -> RoomName {
         match_obj_variants!(self, v => v.room)
     }
+
+    /// Get this object's current hits, if it is a creep or a structure which can be damaged.
+    pub fn hits(&self) -> Option<i32> {
+        match_many_variants!(
+            self,
+            (Spawn, Extension, Extractor, Wall, Road, Rampart, Controller, Link, Storage, Tower,
+            Observer, PowerBank, PowerSpawn, Lab, Terminal, Container, Nuker, Creep, Factory,
+            InvaderCore, PowerCreep)
+            (v) => Some(v.hits),
+            _ => None
+        )
+    }
+
+    /// Get the user ID of this object's owner, if it is owned.
+    pub fn user(&self) -> Option<&str> {
+        match_many_variants!(
+            self,
+            (Controller, Extractor, Terminal, Ruin)
+            (v) => v.user.as_deref(),
+            _ => match_many_variants!(
+                self,
+                (Spawn, Extension, Rampart, Link, Storage, Tower, Observer, PowerSpawn, Lab, Nuker,
+                Tombstone, Creep, ConstructionSite, Factory, InvaderCore, PowerCreep)
+                (v) => Some(&v.user),
+                _ => None
+            )
+        )
+    }
+
+    /// Get this object's store, if it can hold resources.
+    pub fn store(&self) -> Option<&Store> {
+        match_many_variants!(
+            self,
+            (Spawn, Extension, Link, Storage, Tower, PowerSpawn, Lab, Terminal, Container, Nuker,
+            Tombstone, Creep, Factory, Ruin, PowerCreep)
+            (v) => Some(&v.store),
+            _ => None
+        )
+    }
+
+    /// Get this object's type, as the server names it in the `type` field.
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            KnownRoomObject::Source(_) => "source",
+            KnownRoomObject::Mineral(_) => "mineral",
+            KnownRoomObject::Spawn(_) => "spawn",
+            KnownRoomObject::Extension(_) => "extension",
+            KnownRoomObject::Extractor(_) => "extractor",
+            KnownRoomObject::Wall(_) => "constructedWall",
+            KnownRoomObject::Road(_) => "road",
+            KnownRoomObject::Rampart(_) => "rampart",
+            KnownRoomObject::KeeperLair(_) => "keeperLair",
+            KnownRoomObject::Controller(_) => "controller",
+            KnownRoomObject::Portal(_) => "portal",
+            KnownRoomObject::Link(_) => "link",
+            KnownRoomObject::Storage(_) => "storage",
+            KnownRoomObject::Tower(_) => "tower",
+            KnownRoomObject::Observer(_) => "observer",
+            KnownRoomObject::PowerBank(_) => "powerBank",
+            KnownRoomObject::PowerSpawn(_) => "powerSpawn",
+            KnownRoomObject::Lab(_) => "lab",
+            KnownRoomObject::Terminal(_) => "terminal",
+            KnownRoomObject::Container(_) => "container",
+            KnownRoomObject::Nuker(_) => "nuker",
+            KnownRoomObject::Tombstone(_) => "tombstone",
+            KnownRoomObject::Creep(_) => "creep",
+            KnownRoomObject::Resource(_) => "energy",
+            KnownRoomObject::ConstructionSite(_) => "constructionSite",
+            KnownRoomObject::Factory(_) => "factory",
+            KnownRoomObject::InvaderCore(_) => "invaderCore",
+            KnownRoomObject::Deposit(_) => "deposit",
+            KnownRoomObject::Ruin(_) => "ruin",
+            KnownRoomObject::Nuke(_) => "nuke",
+            KnownRoomObject::PowerCreep(_) => "powerCreep",
+        }
+    }
+
+    /// Returns true if the given server-side object type is parsed into a `KnownRoomObject`.
+    pub fn is_known_kind(kind: &str) -> bool {
+        matches!(
+            kind,
+            "source"
+                | "mineral"
+                | "spawn"
+                | "extension"
+                | "extractor"
+                | "constructedWall"
+                | "road"
+                | "rampart"
+                | "keeperLair"
+                | "controller"
+                | "portal"
+                | "link"
+                | "storage"
+                | "tower"
+                | "observer"
+                | "powerBank"
+                | "powerSpawn"
+                | "lab"
+                | "terminal"
+                | "container"
+                | "nuker"
+                | "tombstone"
+                | "creep"
+                | "energy"
+                | "constructionSite"
+                | "factory"
+                | "invaderCore"
+                | "deposit"
+                | "ruin"
+                | "nuke"
+                | "powerCreep"
+        )
+    }
+}
+
+/// A room object of any type, including types this library doesn't know about.
+///
+/// Objects with a `type` known to [`KnownRoomObject`] are fully parsed. Objects with any other type,
+/// such as those added by private server mods or by new game content, are kept as raw JSON, and
+/// updates to them are merged into that JSON. This allows tracking a room's state without losing
+/// objects when the server adds something new.
+///
+/// [`KnownRoomObject`]: enum.KnownRoomObject.html
+#[derive(Clone, Debug, PartialEq)]
+// nearly every object is `Known`, so boxing it would add an allocation to almost every update.
+#[allow(clippy::large_enum_variant)]
+pub enum RoomObject {
+    /// An object of a type this library knows how to parse.
+    Known(KnownRoomObject),
+    /// An object of a type this library doesn't know how to parse.
+    Unknown {
+        /// The object's type, from the `type` field.
+        kind: String,
+        /// Unique 'id' identifier for all game objects on a server.
+        id: String,
+        /// X position within the room (0-50).
+        x: u32,
+        /// Y position within the room (0-50).
+        y: u32,
+        /// Room object is in.
+        room: RoomName,
+        /// All of the object's data, including the fields above, with all updates applied.
+        raw: serde_json::Value,
+    },
+}
+
+/// Fields common to all room objects, used to parse `RoomObject::Unknown`.
+#[derive(serde::Deserialize)]
+struct RoomObjectHeader {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(rename = "_id")]
+    id: String,
+    #[serde(with = "crate::decoders::u32_or_str_containing")]
+    x: u32,
+    #[serde(with = "crate::decoders::u32_or_str_containing")]
+    y: u32,
+    room: RoomName,
+}
+
+impl RoomObject {
+    /// Parses a room object from a full (non-update) JSON value.
+    ///
+    /// Objects with a known type which fail to parse still result in an error, rather than being
+    /// turned into `RoomObject::Unknown`.
+    pub fn from_value(value: serde_json::Value) -> Result<Self, serde_json::Error> {
+        let header = RoomObjectHeader::deserialize(&value)?;
+
+        if KnownRoomObject::is_known_kind(&header.kind) {
+            Ok(RoomObject::Known(serde_json::from_value(value)?))
+        } else {
+            let RoomObjectHeader {
+                kind,
+                id,
+                x,
+                y,
+                room,
+            } = header;
+            Ok(RoomObject::Unknown {
+                kind,
+                id,
+                x,
+                y,
+                room,
+                raw: value,
+            })
+        }
+    }
+
+    /// Update this room object with a JSON update value.
+    pub fn update(&mut self, input: serde_json::Value) -> Result<(), serde_json::Error> {
+        match *self {
+            RoomObject::Known(ref mut obj) => obj.update(input),
+            RoomObject::Unknown {
+                ref mut kind,
+                ref mut id,
+                ref mut x,
+                ref mut y,
+                ref mut room,
+                ref mut raw,
+            } => {
+                let mut updated = raw.clone();
+                merge_json(&mut updated, input);
+                let header = RoomObjectHeader::deserialize(&updated)?;
+                *kind = header.kind;
+                *id = header.id;
+                *x = header.x;
+                *y = header.y;
+                *room = header.room;
+                *raw = updated;
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the known room object, if this object's type is known.
+    pub fn known(&self) -> Option<&KnownRoomObject> {
+        match *self {
+            RoomObject::Known(ref obj) => Some(obj),
+            RoomObject::Unknown { .. } => None,
+        }
+    }
+
+    /// Get this object's type, as the server names it in the `type` field.
+    pub fn kind(&self) -> &str {
+        match *self {
+            RoomObject::Known(ref obj) => obj.kind(),
+            RoomObject::Unknown { ref kind, .. } => kind,
+        }
+    }
+
+    /// Get this object's x position
+    pub fn x(&self) -> u32 {
+        match *self {
+            RoomObject::Known(ref obj) => obj.x(),
+            RoomObject::Unknown { x, .. } => x,
+        }
+    }
+
+    /// Get this object's y position
+    pub fn y(&self) -> u32 {
+        match *self {
+            RoomObject::Known(ref obj) => obj.y(),
+            RoomObject::Unknown { y, .. } => y,
+        }
+    }
+
+    /// Get this object's id
+    pub fn id(&self) -> &str {
+        match *self {
+            RoomObject::Known(ref obj) => obj.id(),
+            RoomObject::Unknown { ref id, .. } => id,
+        }
+    }
+
+    /// Get this object's room name
+    pub fn room(&self) -> RoomName {
+        match *self {
+            RoomObject::Known(ref obj) => obj.room(),
+            RoomObject::Unknown { room, .. } => room,
+        }
+    }
+}
+
+impl From<KnownRoomObject> for RoomObject {
+    fn from(obj: KnownRoomObject) -> Self {
+        RoomObject::Known(obj)
+    }
+}
+
+impl<'de> Deserialize<'de> for RoomObject {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        RoomObject::from_value(value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Recursively merges a partial JSON update into an existing value, in the same way the server
+/// computes room object diffs: objects are merged key by key, `null` removes a key, and anything
+/// else is replaced.
+fn merge_json(existing: &mut serde_json::Value, update: serde_json::Value) {
+    use serde_json::Value::Object;
+
+    match (existing, update) {
+        (&mut Object(ref mut existing_map), Object(update_map)) => {
+            for (key, value) in update_map {
+                if value.is_null() {
+                    existing_map.remove(&key);
+                    continue;
+                }
+                match existing_map.get_mut(&key) {
+                    Some(existing_value) => merge_json(existing_value, value),
+                    None => {
+                        existing_map.insert(key, value);
+                    }
+                }
+            }
+        }
+        (existing, update) => *existing = update,
+    }
+}
+
+/// Asserts that a room object serializes into a format which deserializes back to an equal value.
+#[cfg(test)]
+fn assert_round_trip<T>(obj: &T)
+where
+    T: serde::Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug,
+{
+    let json = serde_json::to_value(obj).expect("expected room object to serialize");
+    let round_tripped: T = serde_json::from_value(json.clone()).unwrap_or_else(|e| {
+        panic!(
+            "expected serialized room object to deserialize: {}\n{:#}",
+            e, json
+        )
+    });
+
+    assert_eq!(*obj, round_tripped);
 }
 
 #[cfg(test)]
@@ -165,7 +520,9 @@ mod test {
 
     use serde_json;
 
-    use super::KnownRoomObject;
+    use crate::RoomName;
+
+    use super::{KnownRoomObject, RoomObject};
 
     #[test]
     fn parse_a_room_update_chain() {
@@ -203,20 +560,220 @@ mod test {
                 } else {
                     match id_to_thing.entry(id.clone()) {
                         Occupied(entry) => {
-                            entry.into_mut().update(value).expect(&format!(
-                                "expected {} in update #{} to succeed",
-                                id, update_index
-                            ));
+                            entry.into_mut().update(value).unwrap_or_else(|e| {
+                                panic!(
+                                    "expected {} in update #{} to succeed: {}",
+                                    id, update_index, e
+                                )
+                            });
+                        }
+                        Vacant(entry) => {
+                            entry.insert(serde_json::from_value(value).unwrap_or_else(|e| {
+                                panic!(
+                                    "expected {} in update #{} to succeed: {}",
+                                    id, update_index, e
+                                )
+                            }));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn room_update_chain_round_trips() {
+        let json: serde_json::Value = serde_json::from_slice(include_bytes!("test-stream.json"))
+            .expect("expected saved json file to parse successfully.");
+
+        let array = match json {
+            serde_json::Value::Array(v) => v,
+            other => panic!("expected Array(), found {:?}", other),
+        };
+
+        let mut id_to_thing = HashMap::<String, KnownRoomObject>::new();
+
+        for update in array {
+            let update = match update {
+                serde_json::Value::Object(m) => m,
+                other => panic!("expected Map, found {:?}", other),
+            };
+
+            for (id, value) in update.into_iter() {
+                if value.is_null() {
+                    id_to_thing.remove(&id);
+                } else {
+                    let obj = match id_to_thing.entry(id) {
+                        Occupied(entry) => {
+                            let obj = entry.into_mut();
+                            obj.update(value).unwrap();
+                            obj
+                        }
+                        Vacant(entry) => entry.insert(serde_json::from_value(value).unwrap()),
+                    };
+                    super::assert_round_trip(obj);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_a_room_update_chain_as_room_objects() {
+        let json: serde_json::Value = serde_json::from_slice(include_bytes!("test-stream.json"))
+            .expect("expected saved json file to parse successfully.");
+
+        let array = match json {
+            serde_json::Value::Array(v) => v,
+            other => panic!("expected Array(), found {:?}", other),
+        };
+
+        let mut id_to_thing = HashMap::<String, RoomObject>::new();
+
+        for (update_index, update) in array.into_iter().enumerate() {
+            let update = match update {
+                serde_json::Value::Object(m) => m,
+                other => panic!("expected Map, found {:?}", other),
+            };
+
+            for (id, value) in update.into_iter() {
+                if value.is_null() {
+                    id_to_thing.remove(&id);
+                } else {
+                    match id_to_thing.entry(id.clone()) {
+                        Occupied(entry) => {
+                            entry.into_mut().update(value).unwrap_or_else(|e| {
+                                panic!(
+                                    "expected {} in update #{} to succeed: {}",
+                                    id, update_index, e
+                                )
+                            });
                         }
                         Vacant(entry) => {
-                            entry.insert(serde_json::from_value(value).expect(&format!(
-                                "expected {} in update #{} to succeed",
-                                id, update_index
-                            )));
+                            entry.insert(RoomObject::from_value(value).unwrap_or_else(|e| {
+                                panic!(
+                                    "expected {} in update #{} to succeed: {}",
+                                    id, update_index, e
+                                )
+                            }));
                         }
                     }
                 }
             }
         }
+
+        assert!(id_to_thing.values().all(|obj| obj.known().is_some()));
+    }
+
+    #[test]
+    fn parse_unknown_room_object_and_update() {
+        let json = json!({
+            "_id": "5f9c8b7a6d5e4f3a2b1c0d9e",
+            "type": "scoreCollector",
+            "x": 12,
+            "y": "37",
+            "room": "E5S5",
+            "store": {
+                "score": 0
+            },
+            "storeCapacityResource": {
+                "score": 20000
+            }
+        });
+
+        let mut obj: RoomObject = serde_json::from_value(json.clone()).unwrap();
+
+        assert_eq!(
+            obj,
+            RoomObject::Unknown {
+                kind: "scoreCollector".to_owned(),
+                id: "5f9c8b7a6d5e4f3a2b1c0d9e".to_owned(),
+                x: 12,
+                y: 37,
+                room: RoomName::new("E5S5").unwrap(),
+                raw: json,
+            }
+        );
+        assert_eq!(obj.kind(), "scoreCollector");
+        assert!(obj.known().is_none());
+
+        obj.update(json!({
+            "x": 13,
+            "store": {
+                "score": 1500
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(obj.x(), 13);
+        match obj {
+            RoomObject::Unknown { ref raw, .. } => {
+                assert_eq!(
+                    *raw,
+                    json!({
+                        "_id": "5f9c8b7a6d5e4f3a2b1c0d9e",
+                        "type": "scoreCollector",
+                        "x": 13,
+                        "y": "37",
+                        "room": "E5S5",
+                        "store": {
+                            "score": 1500
+                        },
+                        "storeCapacityResource": {
+                            "score": 20000
+                        }
+                    })
+                );
+            }
+            ref other => panic!("expected Unknown, found {:?}", other),
+        }
+
+        obj.update(json!({
+            "storeCapacityResource": null
+        }))
+        .unwrap();
+
+        match obj {
+            RoomObject::Unknown { ref raw, .. } => {
+                assert!(raw.get("storeCapacityResource").is_none());
+            }
+            ref other => panic!("expected Unknown, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn room_object_known_type_errors_are_not_hidden() {
+        let json = json!({
+            "_id": "5d334cefdbfe1b628e862a0d",
+            "type": "tower",
+            "x": 26,
+            "y": 25,
+            "room": "W44S12"
+        });
+
+        assert!(RoomObject::from_value(json).is_err());
+    }
+
+    #[test]
+    fn parse_known_room_object_through_wrapper() {
+        let json = json!({
+            "_id": "5bbcb0839099fc012e63c5c3",
+            "type": "nuke",
+            "x": 25,
+            "y": 25,
+            "room": "E17S37",
+            "landTime": 30310100,
+            "launchRoomName": "E17S38"
+        });
+
+        let obj = RoomObject::from_value(json).unwrap();
+
+        match obj {
+            RoomObject::Known(KnownRoomObject::Nuke(ref nuke)) => {
+                assert_eq!(nuke.land_time, 30310100);
+            }
+            ref other => panic!("expected Known(Nuke), found {:?}", other),
+        }
+        assert_eq!(obj.kind(), "nuke");
+        assert_eq!(obj.id(), "5bbcb0839099fc012e63c5c3");
     }
 }
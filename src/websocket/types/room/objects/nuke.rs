@@ -0,0 +1,58 @@
+//! `Nuke` data description.
+use crate::data::RoomName;
+
+with_base_fields_and_update_struct! {
+    /// A nuke - a missile launched from a nuker which is on its way to land in this room.
+    #[derive(Clone, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Nuke {
+        /// The game time at which this nuke will land.
+        pub land_time: u32,
+        /// The name of the room this nuke was launched from.
+        pub launch_room_name: RoomName,
+    }
+
+    /// The update structure for a `Nuke`.
+    #[derive(Clone, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct NukeUpdate { ... }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use crate::data::RoomName;
+
+    use super::Nuke;
+
+    #[test]
+    fn parse_nuke() {
+        let json = json!({
+            "_id": "5f0c3a2b1d4e5f6a7b8c9d0e",
+            "type": "nuke",
+            "x": 25,
+            "y": 25,
+            "room": "E17S37",
+            "landTime": 30310100,
+            "launchRoomName": "E17S38"
+        });
+
+        let obj = Nuke::deserialize(json).unwrap();
+
+        super::super::assert_round_trip(&obj);
+
+        assert_eq!(
+            obj,
+            Nuke {
+                room: RoomName::new("E17S37").unwrap(),
+                x: 25,
+                y: 25,
+                effects: Vec::new(),
+                id: "5f0c3a2b1d4e5f6a7b8c9d0e".to_owned(),
+                land_time: 30310100,
+                launch_room_name: RoomName::new("E17S38").unwrap(),
+            }
+        );
+    }
+}
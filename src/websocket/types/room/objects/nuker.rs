@@ -70,12 +70,15 @@ mod test {
 
         let obj = StructureNuker::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureNuker {
                 room: RoomName::new("E17S38").unwrap(),
                 x: 25,
                 y: 19,
+                effects: Vec::new(),
                 id: "5df45e27d1ba60a873688d18".to_owned(),
                 hits: 1000,
                 hits_max: 1000,
@@ -58,12 +58,15 @@ mod test {
 
         let mut obj = StructureObserver::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureObserver {
                 room: RoomName::new("E9N23").unwrap(),
                 x: 20,
                 y: 18,
+                effects: Vec::new(),
                 id: "582587dd871f73007b177b0f".to_owned(),
                 hits: 500,
                 hits_max: 500,
@@ -87,6 +90,7 @@ mod test {
                 room: RoomName::new("E9N23").unwrap(),
                 x: 20,
                 y: 18,
+                effects: Vec::new(),
                 id: "582587dd871f73007b177b0f".to_owned(),
                 hits: 500,
                 hits_max: 500,
@@ -105,6 +109,7 @@ mod test {
                 room: RoomName::new("E9N23").unwrap(),
                 x: 20,
                 y: 18,
+                effects: Vec::new(),
                 id: "582587dd871f73007b177b0f".to_owned(),
                 hits: 500,
                 hits_max: 500,
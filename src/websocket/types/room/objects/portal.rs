@@ -6,7 +6,7 @@ use crate::{
 
 with_update_struct! {
     /// The destination for a portal structure.
-    #[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+    #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
     pub struct PortalDestination {
         /// The room name the other side of this portal ends at.
         pub room: RoomName,
@@ -79,12 +79,15 @@ mod test {
 
         let obj = StructurePortal::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructurePortal {
                 room: RoomName::new("W5N35").unwrap(),
                 x: 13,
                 y: 30,
+                effects: Vec::new(),
                 id: "59570dde2d46c88436d2ab7e".to_owned(),
                 unstable_date: None,
                 decay_time: Some(20197693),
@@ -115,12 +118,15 @@ mod test {
 
         let mut obj = StructurePortal::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructurePortal {
                 room: RoomName::new("W5N85").unwrap(),
                 x: 22,
                 y: 37,
+                effects: Vec::new(),
                 id: "595fc9cd28a6884ac973e390".to_owned(),
                 unstable_date: Some(time::Timespec::new(1500313804391, 0)),
                 decay_time: None,
@@ -146,6 +152,7 @@ mod test {
                 room: RoomName::new("W5N85").unwrap(),
                 x: 22,
                 y: 37,
+                effects: Vec::new(),
                 id: "595fc9cd28a6884ac973e390".to_owned(),
                 unstable_date: None,
                 decay_time: Some(20197693),
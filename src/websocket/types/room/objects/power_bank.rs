@@ -43,12 +43,15 @@ mod test {
 
         let obj = StructurePowerBank::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructurePowerBank {
                 room: RoomName::new("W66N20").unwrap(),
                 x: 24,
                 y: 40,
+                effects: Vec::new(),
                 id: "59695d5c09c7343d8a4192fd".to_owned(),
                 decay_time: 20238724,
                 hits: 2000000,
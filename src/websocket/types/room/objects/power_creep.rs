@@ -0,0 +1,302 @@
+//! `PowerCreep` data description.
+use std::collections::HashMap;
+
+use super::super::resources::ResourceType;
+use super::super::resources::Store;
+use super::creep::CreepMessage;
+use super::{ActionLogTarget, PowerType};
+use crate::data::RoomName;
+
+with_update_struct! {
+    /// A struct describing one power a power creep has learned.
+    #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    pub struct PowerCreepPower {
+        /// The level this power has been upgraded to.
+        pub level: u32,
+        /// The game time at which this power can next be used, if it has been used recently.
+        pub cooldown_time: Option<u32>,
+    }
+
+    /// The update structure for a `PowerCreepPower`.
+    #[derive(serde::Deserialize, Clone, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct PowerCreepPowerUpdate { ... }
+}
+
+with_update_struct! {
+    /// A struct describing a power used by a power creep.
+    #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+    pub struct PowerCreepPowerUse {
+        /// The power which was used.
+        #[serde(rename = "id")]
+        pub power: PowerType,
+        /// The in-room x position of the power's target.
+        pub x: u32,
+        /// The in-room y position of the power's target.
+        pub y: u32,
+    }
+
+    /// The update structure for a `PowerCreepPowerUse`.
+    #[derive(serde::Deserialize, Clone, Debug)]
+    pub struct PowerCreepPowerUseUpdate { ... }
+}
+
+with_update_struct! {
+    /// A struct describing a power creep's actions.
+    #[derive(serde::Deserialize, serde::Serialize, Default, Clone, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    pub struct PowerCreepActions {
+        /// The location this power creep was attacked from last tick.
+        pub attacked: Option<ActionLogTarget>,
+        /// The location this power creep was healed from last tick.
+        pub healed: Option<ActionLogTarget>,
+        /// The power this power creep used last tick, and where.
+        pub power: Option<PowerCreepPowerUse>,
+        /// The message this power creep said last tick.
+        pub say: Option<CreepMessage>,
+        /// If present, this power creep was spawned last tick.
+        pub spawned: Option<bool>,
+    }
+
+    /// The update structure for a `PowerCreepActions`.
+    #[derive(serde::Deserialize, Clone, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct PowerCreepActionsUpdate { ... }
+}
+
+with_base_fields_and_update_struct! {
+    /// A power creep - an immortal hero unit which can be respawned at power spawns, and which uses
+    /// powers rather than body parts.
+    #[derive(Clone, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    pub struct PowerCreep {
+        /// The current number of hit-points this power creep has.
+        #[serde(default)]
+        pub hits: i32,
+        /// The maximum number of hit-points this power creep has.
+        #[serde(default)]
+        pub hits_max: i32,
+        /// The user ID of the owner of this power creep.
+        pub user: String,
+        /// The name of this power creep, unique per player.
+        pub name: String,
+        /// The class of this power creep, such as `"operator"`.
+        pub class_name: Option<String>,
+        /// The level of this power creep.
+        #[serde(default)]
+        pub level: u32,
+        /// The powers this power creep has learned.
+        #[serde(default)]
+        pub powers: HashMap<PowerType, PowerCreepPower>,
+        /// The game time at which this power creep will die if not renewed before then.
+        pub age_time: Option<u32>,
+        /// The shard this power creep is spawned on.
+        pub shard: Option<String>,
+        /// The total amount of resources this power creep can carry.
+        #[serde(rename = "storeCapacity")]
+        pub capacity: i32,
+        /// The resources and amounts of each resource this power creep holds.
+        pub store: Store,
+        /// The current 'fatigue' of this power creep. Power creeps never gain fatigue, but the
+        /// server still reports it.
+        #[serde(default)]
+        pub fatigue: i32,
+        /// Whether or not an attack on this power creep will send an email to the owner automatically.
+        #[serde(default)]
+        pub notify_when_attacked: bool,
+        /// A record of all actions this power creep performed and some actions performed onto
+        /// this power creep last tick.
+        #[serde(default)]
+        pub action_log: PowerCreepActions,
+    }
+
+    /// The update structure for a `PowerCreep`.
+    #[derive(Clone, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct PowerCreepUpdate {
+        - hits: i32,
+        - hits_max: i32,
+        - user: String,
+        - name: String,
+        - class_name: Option<String>,
+        - level: u32,
+        - powers: HashMap<PowerType, PowerCreepPower>,
+        - age_time: Option<u32>,
+        - shard: Option<String>,
+        #[serde(rename = "storeCapacity")]
+        - capacity: i32,
+        - store: Store,
+        - fatigue: i32,
+        - notify_when_attacked: bool,
+        - action_log: PowerCreepActions,
+    }
+}
+
+impl PowerCreep {
+    /// Iterates over this power creep's carried resources.
+    pub fn carry_contents(&self) -> impl Iterator<Item = (ResourceType, i32)> + '_ {
+        self.store.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use crate::data::RoomName;
+
+    use super::{
+        ActionLogTarget, PowerCreep, PowerCreepActions, PowerCreepPower, PowerCreepPowerUse,
+        PowerType,
+    };
+
+    #[test]
+    fn parse_power_creep_and_update() {
+        let json = json!({
+            "_id": "5d9a6e2c1b3f4a5e6d7c8b9a",
+            "type": "powerCreep",
+            "x": 19,
+            "y": 28,
+            "room": "E9S32",
+            "user": "5a8466038f866773f59fa6c8",
+            "name": "operator-1",
+            "className": "operator",
+            "level": 3,
+            "hits": 4000,
+            "hitsMax": 4000,
+            "ageTime": 23474000,
+            "shard": "shard2",
+            "powers": {
+                "1": {
+                    "level": 2
+                },
+                "2": {
+                    "level": 1,
+                    "cooldownTime": 23469100
+                }
+            },
+            "store": {
+                "ops": 120,
+                "energy": 50
+            },
+            "storeCapacity": 400,
+            "fatigue": 0,
+            "notifyWhenAttacked": true,
+            "actionLog": {
+                "attacked": null,
+                "healed": null,
+                "power": null,
+                "say": null,
+                "spawned": null
+            }
+        });
+
+        let mut obj = PowerCreep::deserialize(json).unwrap();
+
+        super::super::assert_round_trip(&obj);
+
+        assert_eq!(
+            obj,
+            PowerCreep {
+                room: RoomName::new("E9S32").unwrap(),
+                x: 19,
+                y: 28,
+                effects: Vec::new(),
+                id: "5d9a6e2c1b3f4a5e6d7c8b9a".to_owned(),
+                hits: 4000,
+                hits_max: 4000,
+                user: "5a8466038f866773f59fa6c8".to_owned(),
+                name: "operator-1".to_owned(),
+                class_name: Some("operator".to_owned()),
+                level: 3,
+                powers: vec![
+                    (
+                        PowerType::GenerateOps,
+                        PowerCreepPower {
+                            level: 2,
+                            cooldown_time: None
+                        }
+                    ),
+                    (
+                        PowerType::OperateSpawn,
+                        PowerCreepPower {
+                            level: 1,
+                            cooldown_time: Some(23469100)
+                        }
+                    ),
+                ]
+                .into_iter()
+                .collect(),
+                age_time: Some(23474000),
+                shard: Some("shard2".to_owned()),
+                capacity: 400,
+                store: store! { Ops: 120, Energy: 50 },
+                fatigue: 0,
+                notify_when_attacked: true,
+                action_log: PowerCreepActions::default(),
+            }
+        );
+
+        obj.update(
+            serde_json::from_value(json!({
+                "x": 20,
+                "powers": {
+                    "1": {
+                        "cooldownTime": 23469550
+                    }
+                },
+                "actionLog": {
+                    "power": {
+                        "id": 1,
+                        "x": 20,
+                        "y": 28
+                    }
+                }
+            }))
+            .unwrap(),
+        );
+
+        assert_eq!(obj.x, 20);
+        assert_eq!(
+            obj.powers[&PowerType::GenerateOps],
+            PowerCreepPower {
+                level: 2,
+                cooldown_time: Some(23469550)
+            }
+        );
+        assert_eq!(obj.powers[&PowerType::OperateSpawn].level, 1);
+        assert_eq!(
+            obj.action_log,
+            PowerCreepActions {
+                power: Some(PowerCreepPowerUse {
+                    power: PowerType::GenerateOps,
+                    x: 20,
+                    y: 28
+                }),
+                ..PowerCreepActions::default()
+            }
+        );
+
+        obj.update(
+            serde_json::from_value(json!({
+                "actionLog": {
+                    "power": null,
+                    "attacked": {
+                        "x": 21,
+                        "y": 29
+                    }
+                }
+            }))
+            .unwrap(),
+        );
+
+        assert_eq!(
+            obj.action_log,
+            PowerCreepActions {
+                attacked: Some(ActionLogTarget { x: 21, y: 29 }),
+                ..PowerCreepActions::default()
+            }
+        );
+    }
+}
@@ -66,6 +66,8 @@ mod test {
 
         let obj = StructurePowerSpawn::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructurePowerSpawn {
@@ -73,6 +75,7 @@ mod test {
                 room: RoomName::new("W15S43").unwrap(),
                 x: 33,
                 y: 24,
+                effects: Vec::new(),
                 store: store! {
                     Energy: 369,
                     Power: 27
@@ -55,12 +55,15 @@ mod test {
 
         let obj = StructureRampart::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureRampart {
                 room: RoomName::new("W73N43").unwrap(),
                 x: 29,
                 y: 35,
+                effects: Vec::new(),
                 id: "58e5ae786dace5c319d5b7ee".to_owned(),
                 hits: 7181701,
                 hits_max: 10000000,
@@ -90,12 +93,15 @@ mod test {
 
         let obj = StructureRampart::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureRampart {
                 room: RoomName::new("E4S61").unwrap(),
                 x: 20,
                 y: 14,
+                effects: Vec::new(),
                 id: "58a2895a578de3836ea89fbb".to_owned(),
                 hits: 97490601,
                 hits_max: 300000000,
@@ -1,9 +1,12 @@
 //! `Resource` data description.
 use std::fmt;
 
-use serde::de::{
-    value::Error as ValueError, Deserialize, Deserializer, Error, IgnoredAny, IntoDeserializer,
-    MapAccess, Visitor,
+use serde::{
+    de::{
+        value::Error as ValueError, Deserialize, Deserializer, Error, IgnoredAny, IntoDeserializer,
+        MapAccess, Visitor,
+    },
+    ser::{Serialize, SerializeMap, Serializer},
 };
 
 use super::super::resources::ResourceType;
@@ -226,6 +229,24 @@ impl<'de> Deserialize<'de> for Resource {
     }
 }
 
+/// Serializes in the same format the server uses, with the amount stored in a field named by the
+/// resource type.
+impl Serialize for Resource {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(6))?;
+        map.serialize_entry("_id", &self.id)?;
+        map.serialize_entry("room", &self.room)?;
+        map.serialize_entry("x", &self.x)?;
+        map.serialize_entry("y", &self.y)?;
+        map.serialize_entry("resourceType", &self.resource_type)?;
+        map.serialize_entry(&self.resource_type, &self.amount)?;
+        map.end()
+    }
+}
+
 impl<'de> Deserialize<'de> for ResourceUpdate {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -350,6 +371,8 @@ mod test {
 
         let obj = Resource::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             Resource {
@@ -46,12 +46,15 @@ mod test {
 
         let mut obj = StructureRoad::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureRoad {
                 room: RoomName::new("E4S61").unwrap(),
                 x: 14,
                 y: 20,
+                effects: Vec::new(),
                 id: "58a1ec36947c6c2d324a2d39".to_owned(),
                 hits: 2600,
                 hits_max: 5000,
@@ -75,6 +78,7 @@ mod test {
                 room: RoomName::new("E4S61").unwrap(),
                 x: 14,
                 y: 20,
+                effects: Vec::new(),
                 id: "58a1ec36947c6c2d324a2d39".to_owned(),
                 hits: 2000,
                 hits_max: 5000,
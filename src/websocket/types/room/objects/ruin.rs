@@ -0,0 +1,155 @@
+//! `Ruin` data description.
+use super::super::resources::ResourceType;
+use super::super::resources::Store;
+use crate::data::RoomName;
+
+with_update_struct! {
+    /// A struct describing the structure a ruin was left behind by.
+    #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RuinStructure {
+        /// The ID of the destroyed structure.
+        pub id: String,
+        /// The type of the destroyed structure, such as `"spawn"` or `"constructedWall"`.
+        #[serde(rename = "type")]
+        pub structure_type: String,
+        /// The maximum number of hit-points the destroyed structure had.
+        pub hits_max: i32,
+        /// The user ID of the owner of the destroyed structure, if it was owned.
+        pub user: Option<String>,
+    }
+
+    /// The update structure for a `RuinStructure`.
+    #[derive(serde::Deserialize, Clone, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RuinStructureUpdate { ... }
+}
+
+with_base_fields_and_update_struct! {
+    /// A ruin - remnants of a destroyed structure.
+    #[derive(Clone, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Ruin {
+        /// The user ID of the owner of the destroyed structure, if it was owned.
+        pub user: Option<String>,
+        /// Information on the structure which was destroyed.
+        pub structure: RuinStructure,
+        /// The game time at which the structure was destroyed.
+        pub destroy_time: u32,
+        /// The game time at which this ruin will decay.
+        pub decay_time: u32,
+        /// The resources and amounts of each resource some game object holds.
+        pub store: Store,
+    }
+
+    /// The update structure for a `Ruin`.
+    #[derive(Clone, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RuinUpdate { ... }
+}
+
+impl Ruin {
+    /// Iterates over this ruin's resources.
+    pub fn resources(&self) -> impl Iterator<Item = (ResourceType, i32)> + '_ {
+        self.store.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use crate::data::RoomName;
+
+    use super::{Ruin, RuinStructure};
+
+    #[test]
+    fn parse_ruin_and_update() {
+        let json = json!({
+            "_id": "5f1ad3e9c2b7a84e1d5c6f70",
+            "type": "ruin",
+            "x": 33,
+            "y": 17,
+            "room": "W41N48",
+            "user": "57874d42d0ae911e3bd15bbc",
+            "structure": {
+                "id": "5e9b0c6d7a8f2e3b4c5d6e7f",
+                "type": "storage",
+                "hits": 0,
+                "hitsMax": 10000,
+                "user": "57874d42d0ae911e3bd15bbc"
+            },
+            "store": {
+                "energy": 93000,
+                "K": 4200
+            },
+            "destroyTime": 38445127,
+            "decayTime": 38545127
+        });
+
+        let mut obj = Ruin::deserialize(json).unwrap();
+
+        super::super::assert_round_trip(&obj);
+
+        assert_eq!(
+            obj,
+            Ruin {
+                room: RoomName::new("W41N48").unwrap(),
+                x: 33,
+                y: 17,
+                effects: Vec::new(),
+                id: "5f1ad3e9c2b7a84e1d5c6f70".to_owned(),
+                user: Some("57874d42d0ae911e3bd15bbc".to_owned()),
+                structure: RuinStructure {
+                    id: "5e9b0c6d7a8f2e3b4c5d6e7f".to_owned(),
+                    structure_type: "storage".to_owned(),
+                    hits_max: 10000,
+                    user: Some("57874d42d0ae911e3bd15bbc".to_owned()),
+                },
+                destroy_time: 38445127,
+                decay_time: 38545127,
+                store: store! { Energy: 93000, Keanium: 4200 },
+            }
+        );
+
+        obj.update(
+            serde_json::from_value(json!({
+                "store": {
+                    "energy": 92000,
+                    "K": null
+                }
+            }))
+            .unwrap(),
+        );
+
+        assert_eq!(obj.store, store! { Energy: 92000 });
+    }
+
+    #[test]
+    fn parse_unowned_ruin() {
+        let json = json!({
+            "_id": "5f1ad3e9c2b7a84e1d5c6f71",
+            "type": "ruin",
+            "x": 10,
+            "y": 40,
+            "room": "W41N48",
+            "structure": {
+                "id": "5e9b0c6d7a8f2e3b4c5d6e80",
+                "type": "constructedWall",
+                "hits": 0,
+                "hitsMax": 300000000
+            },
+            "store": {},
+            "destroyTime": 38445127,
+            "decayTime": 38445627
+        });
+
+        let obj = Ruin::deserialize(json).unwrap();
+
+        super::super::assert_round_trip(&obj);
+
+        assert_eq!(obj.user, None);
+        assert_eq!(obj.structure.structure_type, "constructedWall");
+        assert_eq!(obj.store, store! {});
+    }
+}
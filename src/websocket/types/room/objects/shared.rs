@@ -1,8 +1,14 @@
 //! Structures which are shared and exist as sub-field of multiple structures.
+use std::{convert::TryFrom, fmt};
+
+use serde::{
+    de::{Deserializer, Error, Unexpected, Visitor},
+    Deserialize, Serialize, Serializer,
+};
 
 with_update_struct! {
     /// A struct describing the destination of various actions within action logs.
-    #[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+    #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
     pub struct ActionLogTarget {
         /// The in-room x position of this target.
         pub x: u32,
@@ -14,3 +20,246 @@ with_update_struct! {
     #[derive(serde::Deserialize, Clone, Debug)]
     pub struct ActionLogTargetUpdate { ... }
 }
+
+/// A power creep ability, or another effect which can be applied to room objects.
+///
+/// These are sent by the server as numbers. Numbers this library doesn't know about are kept as
+/// `PowerType::Unknown`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PowerType {
+    /// PWR_GENERATE_OPS: 1
+    GenerateOps,
+    /// PWR_OPERATE_SPAWN: 2
+    OperateSpawn,
+    /// PWR_OPERATE_TOWER: 3
+    OperateTower,
+    /// PWR_OPERATE_STORAGE: 4
+    OperateStorage,
+    /// PWR_OPERATE_LAB: 5
+    OperateLab,
+    /// PWR_OPERATE_EXTENSION: 6
+    OperateExtension,
+    /// PWR_OPERATE_OBSERVER: 7
+    OperateObserver,
+    /// PWR_OPERATE_TERMINAL: 8
+    OperateTerminal,
+    /// PWR_DISRUPT_SPAWN: 9
+    DisruptSpawn,
+    /// PWR_DISRUPT_TOWER: 10
+    DisruptTower,
+    /// PWR_DISRUPT_SOURCE: 11
+    DisruptSource,
+    /// PWR_SHIELD: 12
+    Shield,
+    /// PWR_REGEN_SOURCE: 13
+    RegenSource,
+    /// PWR_REGEN_MINERAL: 14
+    RegenMineral,
+    /// PWR_DISRUPT_TERMINAL: 15
+    DisruptTerminal,
+    /// PWR_OPERATE_POWER: 16
+    OperatePower,
+    /// PWR_FORTIFY: 17
+    Fortify,
+    /// PWR_OPERATE_CONTROLLER: 18
+    OperateController,
+    /// PWR_OPERATE_FACTORY: 19
+    OperateFactory,
+    /// EFFECT_INVULNERABILITY: 1001
+    Invulnerability,
+    /// EFFECT_COLLAPSE_TIMER: 1002
+    CollapseTimer,
+    /// Any other power or effect.
+    Unknown(u32),
+}
+
+basic_updatable!(PowerType);
+
+impl PowerType {
+    /// Finds the power type with the given in-game numeric identifier.
+    pub fn from_id(id: u32) -> Self {
+        match id {
+            1 => PowerType::GenerateOps,
+            2 => PowerType::OperateSpawn,
+            3 => PowerType::OperateTower,
+            4 => PowerType::OperateStorage,
+            5 => PowerType::OperateLab,
+            6 => PowerType::OperateExtension,
+            7 => PowerType::OperateObserver,
+            8 => PowerType::OperateTerminal,
+            9 => PowerType::DisruptSpawn,
+            10 => PowerType::DisruptTower,
+            11 => PowerType::DisruptSource,
+            12 => PowerType::Shield,
+            13 => PowerType::RegenSource,
+            14 => PowerType::RegenMineral,
+            15 => PowerType::DisruptTerminal,
+            16 => PowerType::OperatePower,
+            17 => PowerType::Fortify,
+            18 => PowerType::OperateController,
+            19 => PowerType::OperateFactory,
+            1001 => PowerType::Invulnerability,
+            1002 => PowerType::CollapseTimer,
+            other => PowerType::Unknown(other),
+        }
+    }
+
+    /// Finds the in-game numeric identifier for this power type.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use screeps_api::websocket::objects::PowerType;
+    /// assert_eq!(PowerType::DisruptTower.id(), 10)
+    /// ```
+    pub fn id(self) -> u32 {
+        match self {
+            PowerType::GenerateOps => 1,
+            PowerType::OperateSpawn => 2,
+            PowerType::OperateTower => 3,
+            PowerType::OperateStorage => 4,
+            PowerType::OperateLab => 5,
+            PowerType::OperateExtension => 6,
+            PowerType::OperateObserver => 7,
+            PowerType::OperateTerminal => 8,
+            PowerType::DisruptSpawn => 9,
+            PowerType::DisruptTower => 10,
+            PowerType::DisruptSource => 11,
+            PowerType::Shield => 12,
+            PowerType::RegenSource => 13,
+            PowerType::RegenMineral => 14,
+            PowerType::DisruptTerminal => 15,
+            PowerType::OperatePower => 16,
+            PowerType::Fortify => 17,
+            PowerType::OperateController => 18,
+            PowerType::OperateFactory => 19,
+            PowerType::Invulnerability => 1001,
+            PowerType::CollapseTimer => 1002,
+            PowerType::Unknown(other) => other,
+        }
+    }
+}
+
+impl Serialize for PowerType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u32(self.id())
+    }
+}
+
+struct PowerTypeVisitor;
+
+impl<'de> Visitor<'de> for PowerTypeVisitor {
+    type Value = PowerType;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an integer power identifier")
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        if value > u64::from(u32::MAX) {
+            return Err(E::invalid_value(Unexpected::Unsigned(value), &self));
+        }
+        Ok(PowerType::from_id(value as u32))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        if value < 0 || value > i64::from(u32::MAX) {
+            return Err(E::invalid_value(Unexpected::Signed(value), &self));
+        }
+        Ok(PowerType::from_id(value as u32))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        value
+            .parse()
+            .map(PowerType::from_id)
+            .map_err(|_| E::invalid_value(Unexpected::Str(value), &self))
+    }
+}
+
+impl<'de> Deserialize<'de> for PowerType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(PowerTypeVisitor)
+    }
+}
+
+with_update_struct! {
+    /// An effect currently applied to a room object, such as a power creep's ability or a
+    /// stronghold's invulnerability.
+    #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+    #[serde(try_from = "EffectUpdate")]
+    pub struct Effect {
+        /// The power or effect which has been applied.
+        #[serde(rename = "effect")]
+        pub power: PowerType,
+        /// The level of the power which was used. None for effects which aren't powers.
+        pub level: Option<u32>,
+        /// The game time at which this effect will expire.
+        #[serde(rename = "endTime")]
+        pub ends_at: u32,
+    }
+
+    /// The update structure for an `Effect`.
+    #[derive(serde::Deserialize, Clone, Debug)]
+    #[serde(from = "EffectData")]
+    pub struct EffectUpdate { ... }
+}
+
+/// Raw effect data. Effects from powers have both an `effect` and a `power` key with the same id,
+/// while other effects, such as a stronghold's invulnerability, only have `effect`.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EffectData {
+    #[serde(default)]
+    effect: Option<PowerType>,
+    #[serde(default)]
+    power: Option<PowerType>,
+    #[serde(default, with = "crate::websocket::room_object_macros::always_some")]
+    level: Option<Option<u32>>,
+    #[serde(default)]
+    end_time: Option<u32>,
+}
+
+impl From<EffectData> for EffectUpdate {
+    fn from(data: EffectData) -> Self {
+        EffectUpdate {
+            power: data.effect.or(data.power),
+            level: data.level,
+            ends_at: data.end_time,
+        }
+    }
+}
+
+impl TryFrom<EffectUpdate> for Effect {
+    type Error = &'static str;
+
+    fn try_from(update: EffectUpdate) -> Result<Self, Self::Error> {
+        Ok(Effect {
+            power: update.power.ok_or("missing field `effect`")?,
+            level: update.level.flatten(),
+            ends_at: update.ends_at.ok_or("missing field `endTime`")?,
+        })
+    }
+}
+
+impl Effect {
+    /// Returns true if this effect is still active at the given game time.
+    pub fn is_active_at(&self, game_time: u32) -> bool {
+        self.ends_at > game_time
+    }
+}
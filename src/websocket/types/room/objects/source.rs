@@ -60,6 +60,8 @@ mod test {
 
         let mut obj = Source::deserialize(&json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             Source {
@@ -67,6 +69,7 @@ mod test {
                 room: RoomName::new("E4S61").unwrap(),
                 x: 26,
                 y: 9,
+                effects: Vec::new(),
                 energy: 260,
                 energy_capacity: 3000,
                 invader_harvested: 29240,
@@ -91,6 +94,7 @@ mod test {
                 room: RoomName::new("E4S61").unwrap(),
                 x: 40,
                 y: 50,
+                effects: Vec::new(),
                 energy: 0,
                 energy_capacity: 3000,
                 invader_harvested: 29240,
@@ -116,6 +120,8 @@ mod test {
 
         let obj = Source::deserialize(&json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             Source {
@@ -123,6 +129,7 @@ mod test {
                 room: RoomName::new("E6S26").unwrap(),
                 x: 40,
                 y: 34,
+                effects: Vec::new(),
                 energy: 4000,
                 energy_capacity: 4000,
                 invader_harvested: 0,
@@ -4,7 +4,7 @@ use crate::data::RoomName;
 
 with_update_struct! {
     /// A struct describing a creep currently spawning (used as part of the update for a StructureSpawn).
-    #[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+    #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     pub struct SpawningCreep {
         /// The name of this creep, unique per player.
@@ -94,6 +94,8 @@ mod test {
 
         let obj = StructureSpawn::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureSpawn {
@@ -101,6 +103,7 @@ mod test {
                 room: RoomName::new("W41N48").unwrap(),
                 x: 26,
                 y: 28,
+                effects: Vec::new(),
                 store: store! { Energy: 300 },
                 store_capacity_resource: store! { Energy: 300 },
                 hits: 5000,
@@ -143,6 +146,8 @@ mod test {
 
         let mut obj = StructureSpawn::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureSpawn {
@@ -150,6 +155,7 @@ mod test {
                 room: RoomName::new("W44S12").unwrap(),
                 x: 28,
                 y: 26,
+                effects: Vec::new(),
                 store: store! { Energy: 300 },
                 store_capacity_resource: store! { Energy: 300 },
                 hits: 5000,
@@ -78,6 +78,8 @@ mod test {
 
         let obj = StructureStorage::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         match obj {
             StructureStorage {
                 capacity: 1000000,
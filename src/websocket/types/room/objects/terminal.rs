@@ -89,6 +89,8 @@ mod test {
 
         let obj = StructureTerminal::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         match obj {
             StructureTerminal {
                 capacity: 300000,
@@ -138,6 +140,8 @@ mod test {
 
         let obj = StructureTerminal::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(obj.cooldown_time, 0);
     }
 
@@ -163,6 +167,7 @@ mod test {
             "y": 45
         });
         let obj = StructureTerminal::deserialize(json).unwrap();
+        super::super::assert_round_trip(&obj);
         assert_eq!(obj.store, store!());
         assert_eq!(obj.notify_when_attacked, false);
     }
@@ -78,6 +78,8 @@ mod test {
 
         let obj = Tombstone::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(obj.creep_id, "5f025b3706ec7d5f6e3cd7a3");
         assert_eq!(obj.creep_name, "38445073-0");
     }
@@ -136,6 +138,8 @@ mod test {
 
         let obj = Tombstone::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(obj.creep_id, "5f021a8c0a816c111ff81006");
         assert_eq!(obj.creep_name, "W31N48_transport_20200");
         assert_eq!(obj.store, store! { Energy: 1000 });
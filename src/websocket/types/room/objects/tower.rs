@@ -2,7 +2,7 @@
 use super::super::resources::Store;
 use crate::data::RoomName;
 
-use super::ActionLogTarget;
+use super::{ActionLogTarget, PowerType};
 
 with_structure_fields_and_update_struct! {
     /// A tower structure - a structure that can be filled with energy, and then attack, heal and
@@ -41,7 +41,7 @@ with_structure_fields_and_update_struct! {
 
 with_update_struct! {
     /// A struct describing a tower's actions.
-    #[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+    #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     pub struct StructureTowerActions {
         /// Where this tower attacked last tick.
@@ -58,6 +58,16 @@ with_update_struct! {
     pub struct StructureTowerActionsUpdate { ... }
 }
 
+impl StructureTower {
+    /// Returns true if this tower is disrupted by a power creep's `PWR_DISRUPT_TOWER` at the given
+    /// game time.
+    pub fn is_disrupted_at(&self, game_time: u32) -> bool {
+        self.effects
+            .iter()
+            .any(|e| e.power == PowerType::DisruptTower && e.is_active_at(game_time))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use serde::Deserialize;
@@ -65,7 +75,8 @@ mod test {
 
     use crate::data::RoomName;
 
-    use super::{ActionLogTarget, StructureTower, StructureTowerActions};
+    use super::super::Effect;
+    use super::{ActionLogTarget, PowerType, StructureTower, StructureTowerActions};
 
     #[test]
     fn parse_tower_and_update() {
@@ -97,12 +108,15 @@ mod test {
 
         let mut obj = StructureTower::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureTower {
                 room: RoomName::new("W44S12").unwrap(),
                 x: 26,
                 y: 25,
+                effects: Vec::new(),
                 id: "5d334cefdbfe1b628e862a0d".to_owned(),
                 store: store! { Energy: 570 },
                 store_capacity_resource: store! { Energy: 1000 },
@@ -183,4 +197,97 @@ mod test {
             },
         );
     }
+
+    #[test]
+    fn parse_disrupted_tower_effects() {
+        let json = json!({
+          "_id": "5d334cefdbfe1b628e862a0d",
+          "type": "tower",
+          "x": 26,
+          "y": 25,
+          "room": "W44S12",
+          "notifyWhenAttacked": true,
+          "user": "5a8466038f866773f59fa6c8",
+          "hits": 3000,
+          "hitsMax": 3000,
+          "actionLog": {
+            "attack": null,
+            "heal": null,
+            "repair": null
+          },
+          "store": {
+            "energy": 1000
+          },
+          "storeCapacityResource": {
+            "energy": 1000
+          },
+          "effects": [
+            {
+              "effect": 10,
+              "power": 10,
+              "level": 3,
+              "endTime": 23469612,
+              "duration": 5
+            }
+          ]
+        });
+
+        let mut obj = StructureTower::deserialize(json).unwrap();
+
+        super::super::assert_round_trip(&obj);
+
+        assert_eq!(
+            obj.effects,
+            vec![Effect {
+                power: PowerType::DisruptTower,
+                level: Some(3),
+                ends_at: 23469612,
+            }]
+        );
+        assert!(obj.is_disrupted_at(23469610));
+        assert!(!obj.is_disrupted_at(23469612));
+
+        obj.update(
+            serde_json::from_value(json!({
+              "effects": {
+                "0": {
+                  "endTime": 23469620
+                },
+                "1": {
+                  "effect": 3,
+                  "power": 3,
+                  "level": 1,
+                  "endTime": 23469700
+                }
+              }
+            }))
+            .unwrap(),
+        );
+
+        assert_eq!(
+            obj.effects,
+            vec![
+                Effect {
+                    power: PowerType::DisruptTower,
+                    level: Some(3),
+                    ends_at: 23469620,
+                },
+                Effect {
+                    power: PowerType::OperateTower,
+                    level: Some(1),
+                    ends_at: 23469700,
+                },
+            ]
+        );
+
+        obj.update(
+            serde_json::from_value(json!({
+              "effects": null
+            }))
+            .unwrap(),
+        );
+
+        assert_eq!(obj.effects, vec![]);
+        assert!(!obj.is_disrupted_at(23469610));
+    }
 }
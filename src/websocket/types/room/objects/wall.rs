@@ -6,7 +6,7 @@ use crate::{
 
 with_update_struct! {
     /// Describes the decay of a decaying wall.
-    #[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+    #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     pub struct WallDecayTime {
         /// Unix timestamp of when this wall will decay.
@@ -68,12 +68,15 @@ mod test {
 
         let obj = StructureWall::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureWall {
                 room: RoomName::new("W73N43").unwrap(),
                 x: 47,
                 y: 24,
+                effects: Vec::new(),
                 id: "584a5d89cbe27a302e4ba889".to_owned(),
                 hits: 7222461,
                 hits_max: 300000000,
@@ -98,12 +101,15 @@ mod test {
 
         let obj = StructureWall::deserialize(json).unwrap();
 
+        super::super::assert_round_trip(&obj);
+
         assert_eq!(
             obj,
             StructureWall {
                 room: RoomName::new("W5S35").unwrap(),
                 x: 29,
                 y: 0,
+                effects: Vec::new(),
                 id: "5cdf140a8e5fb05519843bc6".to_owned(),
                 hits: 0,
                 hits_max: 0,
@@ -2,7 +2,7 @@
 use std::{cmp, collections::HashMap, fmt};
 
 use serde::{
-    de::{Deserializer, MapAccess, Visitor},
+    de::{value::Error as ValueError, Deserializer, IntoDeserializer, MapAccess, Visitor},
     Deserialize, Serialize,
 };
 
@@ -140,6 +140,141 @@ pub enum ResourceType {
     /// RESOURCE_CATALYZED_GHODIUM_ALKALIDE: "XGHO2",
     #[serde(rename = "XGHO2")]
     CatalyzedGhodiumAlkalide,
+    /// RESOURCE_OPS: "ops",
+    #[serde(rename = "ops")]
+    Ops,
+    /// RESOURCE_SILICON: "silicon",
+    #[serde(rename = "silicon")]
+    Silicon,
+    /// RESOURCE_METAL: "metal",
+    #[serde(rename = "metal")]
+    Metal,
+    /// RESOURCE_BIOMASS: "biomass",
+    #[serde(rename = "biomass")]
+    Biomass,
+    /// RESOURCE_MIST: "mist",
+    #[serde(rename = "mist")]
+    Mist,
+    /// RESOURCE_UTRIUM_BAR: "utrium_bar",
+    #[serde(rename = "utrium_bar")]
+    UtriumBar,
+    /// RESOURCE_LEMERGIUM_BAR: "lemergium_bar",
+    #[serde(rename = "lemergium_bar")]
+    LemergiumBar,
+    /// RESOURCE_ZYNTHIUM_BAR: "zynthium_bar",
+    #[serde(rename = "zynthium_bar")]
+    ZynthiumBar,
+    /// RESOURCE_KEANIUM_BAR: "keanium_bar",
+    #[serde(rename = "keanium_bar")]
+    KeaniumBar,
+    /// RESOURCE_GHODIUM_MELT: "ghodium_melt",
+    #[serde(rename = "ghodium_melt")]
+    GhodiumMelt,
+    /// RESOURCE_OXIDANT: "oxidant",
+    #[serde(rename = "oxidant")]
+    Oxidant,
+    /// RESOURCE_REDUCTANT: "reductant",
+    #[serde(rename = "reductant")]
+    Reductant,
+    /// RESOURCE_PURIFIER: "purifier",
+    #[serde(rename = "purifier")]
+    Purifier,
+    /// RESOURCE_BATTERY: "battery",
+    #[serde(rename = "battery")]
+    Battery,
+    /// RESOURCE_COMPOSITE: "composite",
+    #[serde(rename = "composite")]
+    Composite,
+    /// RESOURCE_CRYSTAL: "crystal",
+    #[serde(rename = "crystal")]
+    Crystal,
+    /// RESOURCE_LIQUID: "liquid",
+    #[serde(rename = "liquid")]
+    Liquid,
+    /// RESOURCE_WIRE: "wire",
+    #[serde(rename = "wire")]
+    Wire,
+    /// RESOURCE_SWITCH: "switch",
+    #[serde(rename = "switch")]
+    Switch,
+    /// RESOURCE_TRANSISTOR: "transistor",
+    #[serde(rename = "transistor")]
+    Transistor,
+    /// RESOURCE_MICROCHIP: "microchip",
+    #[serde(rename = "microchip")]
+    Microchip,
+    /// RESOURCE_CIRCUIT: "circuit",
+    #[serde(rename = "circuit")]
+    Circuit,
+    /// RESOURCE_DEVICE: "device",
+    #[serde(rename = "device")]
+    Device,
+    /// RESOURCE_CELL: "cell",
+    #[serde(rename = "cell")]
+    Cell,
+    /// RESOURCE_PHLEGM: "phlegm",
+    #[serde(rename = "phlegm")]
+    Phlegm,
+    /// RESOURCE_TISSUE: "tissue",
+    #[serde(rename = "tissue")]
+    Tissue,
+    /// RESOURCE_MUSCLE: "muscle",
+    #[serde(rename = "muscle")]
+    Muscle,
+    /// RESOURCE_ORGANOID: "organoid",
+    #[serde(rename = "organoid")]
+    Organoid,
+    /// RESOURCE_ORGANISM: "organism",
+    #[serde(rename = "organism")]
+    Organism,
+    /// RESOURCE_ALLOY: "alloy",
+    #[serde(rename = "alloy")]
+    Alloy,
+    /// RESOURCE_TUBE: "tube",
+    #[serde(rename = "tube")]
+    Tube,
+    /// RESOURCE_FIXTURES: "fixtures",
+    #[serde(rename = "fixtures")]
+    Fixtures,
+    /// RESOURCE_FRAME: "frame",
+    #[serde(rename = "frame")]
+    Frame,
+    /// RESOURCE_HYDRAULICS: "hydraulics",
+    #[serde(rename = "hydraulics")]
+    Hydraulics,
+    /// RESOURCE_MACHINE: "machine",
+    #[serde(rename = "machine")]
+    Machine,
+    /// RESOURCE_CONDENSATE: "condensate",
+    #[serde(rename = "condensate")]
+    Condensate,
+    /// RESOURCE_CONCENTRATE: "concentrate",
+    #[serde(rename = "concentrate")]
+    Concentrate,
+    /// RESOURCE_EXTRACT: "extract",
+    #[serde(rename = "extract")]
+    Extract,
+    /// RESOURCE_SPIRIT: "spirit",
+    #[serde(rename = "spirit")]
+    Spirit,
+    /// RESOURCE_EMANATION: "emanation",
+    #[serde(rename = "emanation")]
+    Emanation,
+    /// RESOURCE_ESSENCE: "essence",
+    #[serde(rename = "essence")]
+    Essence,
+    /// SUBSCRIPTION_TOKEN: "token",
+    #[serde(rename = "token")]
+    SubscriptionToken,
+    /// CPU_UNLOCK: "cpuUnlock",
+    #[serde(rename = "cpuUnlock")]
+    CpuUnlock,
+    /// PIXEL: "pixel",
+    #[serde(rename = "pixel")]
+    Pixel,
+    /// ACCESS_KEY: "accessKey",
+    #[serde(rename = "accessKey")]
+    AccessKey,
 }
 
 basic_updatable!(ResourceType);
@@ -202,6 +337,51 @@ impl ResourceType {
             ResourceType::CatalyzedZynthiumAlkalide => "XZHO2",
             ResourceType::CatalyzedGhodiumAcid => "XGH2O",
             ResourceType::CatalyzedGhodiumAlkalide => "XGHO2",
+            ResourceType::Ops => "ops",
+            ResourceType::Silicon => "silicon",
+            ResourceType::Metal => "metal",
+            ResourceType::Biomass => "biomass",
+            ResourceType::Mist => "mist",
+            ResourceType::UtriumBar => "utrium_bar",
+            ResourceType::LemergiumBar => "lemergium_bar",
+            ResourceType::ZynthiumBar => "zynthium_bar",
+            ResourceType::KeaniumBar => "keanium_bar",
+            ResourceType::GhodiumMelt => "ghodium_melt",
+            ResourceType::Oxidant => "oxidant",
+            ResourceType::Reductant => "reductant",
+            ResourceType::Purifier => "purifier",
+            ResourceType::Battery => "battery",
+            ResourceType::Composite => "composite",
+            ResourceType::Crystal => "crystal",
+            ResourceType::Liquid => "liquid",
+            ResourceType::Wire => "wire",
+            ResourceType::Switch => "switch",
+            ResourceType::Transistor => "transistor",
+            ResourceType::Microchip => "microchip",
+            ResourceType::Circuit => "circuit",
+            ResourceType::Device => "device",
+            ResourceType::Cell => "cell",
+            ResourceType::Phlegm => "phlegm",
+            ResourceType::Tissue => "tissue",
+            ResourceType::Muscle => "muscle",
+            ResourceType::Organoid => "organoid",
+            ResourceType::Organism => "organism",
+            ResourceType::Alloy => "alloy",
+            ResourceType::Tube => "tube",
+            ResourceType::Fixtures => "fixtures",
+            ResourceType::Frame => "frame",
+            ResourceType::Hydraulics => "hydraulics",
+            ResourceType::Machine => "machine",
+            ResourceType::Condensate => "condensate",
+            ResourceType::Concentrate => "concentrate",
+            ResourceType::Extract => "extract",
+            ResourceType::Spirit => "spirit",
+            ResourceType::Emanation => "emanation",
+            ResourceType::Essence => "essence",
+            ResourceType::SubscriptionToken => "token",
+            ResourceType::CpuUnlock => "cpuUnlock",
+            ResourceType::Pixel => "pixel",
+            ResourceType::AccessKey => "accessKey",
         }
     }
 }
@@ -223,6 +403,29 @@ impl Store {
     }
 }
 
+/// A key in a store's map, which may or may not be a known resource type.
+///
+/// Unknown resources are skipped rather than failing the whole store, so that new resources or
+/// private server mods don't break parsing of any objects holding them.
+struct StoreKey(Option<ResourceType>);
+
+impl<'de> Deserialize<'de> for StoreKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let key = std::borrow::Cow::<'de, str>::deserialize(deserializer)?;
+
+        match ResourceType::deserialize(IntoDeserializer::<ValueError>::into_deserializer(&*key)) {
+            Ok(resource_type) => Ok(StoreKey(Some(resource_type))),
+            Err(_) => {
+                debug!("ignoring unknown resource type in store: {:?}", key);
+                Ok(StoreKey(None))
+            }
+        }
+    }
+}
+
 struct StoreVisitor;
 
 impl<'de> Visitor<'de> for StoreVisitor {
@@ -241,9 +444,9 @@ impl<'de> Visitor<'de> for StoreVisitor {
         // https://github.com/serde-rs/serde/blob/master/serde/src/de/impls.rs
         let mut values = HashMap::with_capacity(cmp::min(map.size_hint().unwrap_or(0), 4096));
 
-        while let Some((key, value)) = map.next_entry::<_, Option<i32>>()? {
+        while let Some((key, value)) = map.next_entry::<StoreKey, Option<i32>>()? {
             let value = value.unwrap_or(0);
-            if value != 0 {
+            if let (StoreKey(Some(key)), true) = (key, value != 0) {
                 values.insert(key, value);
             }
         }
@@ -285,9 +488,10 @@ impl<'de> Visitor<'de> for StoreUpdateVisitor {
         // https://github.com/serde-rs/serde/blob/master/serde/src/de/impls.rs
         let mut values = HashMap::with_capacity(cmp::min(map.size_hint().unwrap_or(0), 4096));
 
-        while let Some((key, value)) = map.next_entry::<_, Option<i32>>()? {
-            let value = value.unwrap_or(0);
-            values.insert(key, value);
+        while let Some((key, value)) = map.next_entry::<StoreKey, Option<i32>>()? {
+            if let StoreKey(Some(key)) = key {
+                values.insert(key, value.unwrap_or(0));
+            }
         }
 
         Ok(StoreUpdate(values))
@@ -351,3 +555,86 @@ macro_rules! store {
 //     last_line = line
 // print(last_line.strip() + '|None')
 // ```
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use super::{ResourceType, Store, StoreUpdate};
+
+    #[test]
+    fn parse_commodity_resource_types() {
+        let json = json!([
+            "metal",
+            "silicon",
+            "biomass",
+            "mist",
+            "utrium_bar",
+            "ghodium_melt",
+            "battery",
+            "composite",
+            "crystal",
+            "liquid",
+            "wire",
+            "switch",
+            "transistor",
+            "microchip",
+            "circuit",
+            "device",
+            "ops",
+            "token",
+            "pixel",
+            "cpuUnlock",
+            "accessKey"
+        ]);
+
+        let parsed = Vec::<ResourceType>::deserialize(json.clone()).unwrap();
+
+        assert_eq!(parsed[0], ResourceType::Metal);
+        assert_eq!(parsed[5], ResourceType::GhodiumMelt);
+        assert_eq!(parsed[15], ResourceType::Device);
+        assert_eq!(parsed[19], ResourceType::CpuUnlock);
+
+        let strings = parsed
+            .iter()
+            .map(|r| r.to_resource_string())
+            .collect::<Vec<_>>();
+        assert_eq!(json, json!(strings));
+    }
+
+    #[test]
+    fn parse_store_with_commodities() {
+        let json = json!({
+            "energy": 4000,
+            "utrium_bar": 600,
+            "wire": 45,
+            "metal": null
+        });
+
+        let store = Store::deserialize(json).unwrap();
+
+        assert_eq!(store, store! { Energy: 4000, UtriumBar: 600, Wire: 45 });
+    }
+
+    #[test]
+    fn parse_store_with_unknown_resources() {
+        let json = json!({
+            "energy": 300,
+            "score": 25,
+            "thorium": 1000
+        });
+
+        let store = Store::deserialize(json).unwrap();
+
+        assert_eq!(store, store! { Energy: 300 });
+
+        let update = StoreUpdate::deserialize(json!({
+            "score": 30,
+            "U": 0
+        }))
+        .unwrap();
+
+        assert_eq!(update.0.len(), 1);
+        assert_eq!(update.0.get(&ResourceType::Utrium), Some(&0));
+    }
+}
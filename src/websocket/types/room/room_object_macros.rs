@@ -1,4 +1,9 @@
 //! Module containing macros which simplify making "updateable" structures.
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    hash::Hash,
+};
+
 use time::Timespec;
 
 use crate::data::{Badge, RoomName};
@@ -7,7 +12,7 @@ use crate::data::{Badge, RoomName};
 ///
 /// This is implemented trivially for basic types, then specifically for
 /// any 'sub-updates' we have, like a spawn's inner spawn, or a room sign.
-pub(super) trait Updatable: Sized {
+pub(crate) trait Updatable: Sized {
     type Update;
 
     /// Updates all fields of this struct with all present fields in the update.
@@ -56,6 +61,12 @@ pub(crate) mod vec_update {
         PartialObj(VecPartialUpdate<T>),
     }
 
+    impl<T> Default for VecUpdate<T> {
+        fn default() -> Self {
+            VecUpdate::Array(Vec::new())
+        }
+    }
+
     #[derive(Debug, Clone)]
     pub struct VecPartialUpdate<T>(Vec<(u32, T)>);
 
@@ -230,6 +241,44 @@ where
     }
 }
 
+/// Maps are updated per-key: present keys are updated or inserted, and keys set to `null` are
+/// removed.
+impl<K, V> Updatable for HashMap<K, V>
+where
+    K: Eq + Hash,
+    V: Updatable,
+{
+    type Update = HashMap<K, Option<V::Update>>;
+
+    fn apply_update(&mut self, update: Self::Update) {
+        for (key, value_update) in update {
+            match value_update {
+                Some(value_update) => match self.entry(key) {
+                    Entry::Occupied(entry) => entry.into_mut().apply_update(value_update),
+                    Entry::Vacant(entry) => {
+                        if let Some(value) = V::create_from_update(value_update) {
+                            entry.insert(value);
+                        }
+                    }
+                },
+                None => {
+                    self.remove(&key);
+                }
+            }
+        }
+    }
+
+    fn create_from_update(update: Self::Update) -> Option<Self> {
+        update
+            .into_iter()
+            .filter_map(|(key, value_update)| {
+                value_update
+                    .map(|value_update| V::create_from_update(value_update).map(|v| (key, v)))
+            })
+            .collect()
+    }
+}
+
 /// Mostly an implementation detail of `with_update_struct`, but can be used independently to
 /// implement Updatable on external structures.
 ///
@@ -440,7 +489,7 @@ macro_rules! add_metadata {
         pub struct $update_name {
             $(
                 $( #[$built_field_attr] )*
-                $built_field: $built_type,
+                pub(crate) $built_field: $built_type,
             )*
         }
     );
@@ -451,6 +500,9 @@ macro_rules! add_metadata {
 ///
 /// A method `update` is implemented on the base structure which will take an instance of the update
 /// structure and apply all changes to the base structure's fields.
+///
+/// The update structure's fields are visible within the crate, so that code applying updates can
+/// see which fields changed.
 macro_rules! with_update_struct {
     (
         $( #[$struct_attr:meta] )*
@@ -537,8 +589,8 @@ macro_rules! with_update_struct {
     )
 }
 
-/// This macro creates the struct described within the invocation, but with an additional 4 fields common to all
-/// room objects, and with `#[derive(serde::Deserialize)]`. The structure definition is then passed on to `with_update_struct`.
+/// This macro creates the struct described within the invocation, but with an additional 5 fields common to all
+/// room objects, and with `#[derive(serde::Deserialize, serde::Serialize)]`. The structure definition is then passed on to `with_update_struct`.
 macro_rules! with_base_fields_and_update_struct {
     (
         $( #[$struct_attr:meta] )*
@@ -591,7 +643,7 @@ macro_rules! with_base_fields_and_update_struct {
         }
     ) => (
         with_update_struct! {
-            #[derive(serde::Deserialize)]
+            #[derive(serde::Deserialize, serde::Serialize)]
             $( #[$struct_attr] )*
             pub struct $name {
                 /// Unique 'id' identifier for all game objects on a server.
@@ -605,6 +657,9 @@ macro_rules! with_base_fields_and_update_struct {
                 /// Y position within the room (0-50).
                 #[serde(with = "crate::decoders::u32_or_str_containing")]
                 pub y: u32,
+                /// Effects currently applied to this object, such as power creep abilities.
+                #[serde(default, with = "crate::decoders::vec_or_index_map")]
+                pub effects: Vec<crate::websocket::types::room::objects::Effect>,
                 $(
                     $( #[$field_attr] )*
                     $( ($field_extra:tt) )*
@@ -625,6 +680,8 @@ macro_rules! with_base_fields_and_update_struct {
                 #[serde(default, with = "crate::decoders::optional_u32_or_str_containing")]
                 (no_extra_meta)
                 - y: u32,
+                (null_is_default)
+                - effects: Vec<crate::websocket::types::room::objects::Effect>,
                 $( $update_field )*
             }
         }
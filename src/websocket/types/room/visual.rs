@@ -0,0 +1,353 @@
+//! Types for room visuals drawn by a user's code.
+//!
+//! Visuals are sent as a string of JSON objects separated by `\n`, one per shape drawn.
+use serde::{Deserialize, Serialize};
+
+/// Line style for the outline of a shape, or for a line.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineStyle {
+    /// A solid line. This is the default when no style is given.
+    Solid,
+    /// A dashed line.
+    Dashed,
+    /// A dotted line.
+    Dotted,
+}
+
+/// Horizontal alignment of text relative to its position.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TextAlign {
+    /// Text is centered on its position. This is the default.
+    Center,
+    /// Text starts at its position.
+    Left,
+    /// Text ends at its position.
+    Right,
+}
+
+/// Font for text visuals: either a size in game coordinates, or a CSS font string.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Font {
+    /// A font size, in game coordinates.
+    Size(f64),
+    /// A CSS font specification, such as `"bold italic 0.7 serif"`.
+    Css(String),
+}
+
+/// Style for circles, rectangles and polygons.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShapeStyle {
+    /// The circle's radius. Only used for circles.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub radius: Option<f64>,
+    /// The fill color, in any web format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill: Option<String>,
+    /// The opacity, between 0 and 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<f64>,
+    /// The outline color, in any web format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke: Option<String>,
+    /// The outline width.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_width: Option<f64>,
+    /// The outline style.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_style: Option<LineStyle>,
+}
+
+/// Style for lines.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LineVisualStyle {
+    /// The line width.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<f64>,
+    /// The line color, in any web format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// The opacity, between 0 and 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<f64>,
+    /// The line style.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_style: Option<LineStyle>,
+}
+
+/// Style for text.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TextStyle {
+    /// The text color, in any web format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// The font.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font: Option<Font>,
+    /// The outline color, in any web format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke: Option<String>,
+    /// The outline width.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_width: Option<f64>,
+    /// The background color, in any web format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background_color: Option<String>,
+    /// The padding between the text and the edge of its background.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background_padding: Option<f64>,
+    /// The text alignment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub align: Option<TextAlign>,
+    /// The opacity, between 0 and 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<f64>,
+}
+
+/// A circle visual.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CircleVisual {
+    /// The X position of the center.
+    pub x: f64,
+    /// The Y position of the center.
+    pub y: f64,
+    /// The circle's style.
+    #[serde(rename = "s", default)]
+    pub style: ShapeStyle,
+}
+
+/// A line visual.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LineVisual {
+    /// The X position of the start.
+    pub x1: f64,
+    /// The Y position of the start.
+    pub y1: f64,
+    /// The X position of the end.
+    pub x2: f64,
+    /// The Y position of the end.
+    pub y2: f64,
+    /// The line's style.
+    #[serde(rename = "s", default)]
+    pub style: LineVisualStyle,
+}
+
+/// A rectangle visual.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RectVisual {
+    /// The X position of the top-left corner.
+    pub x: f64,
+    /// The Y position of the top-left corner.
+    pub y: f64,
+    /// The width.
+    #[serde(rename = "w")]
+    pub width: f64,
+    /// The height.
+    #[serde(rename = "h")]
+    pub height: f64,
+    /// The rectangle's style.
+    #[serde(rename = "s", default)]
+    pub style: ShapeStyle,
+}
+
+/// A polygon or polyline visual.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PolyVisual {
+    /// The `(x, y)` points of the polygon, in order.
+    pub points: Vec<(f64, f64)>,
+    /// The polygon's style.
+    #[serde(rename = "s", default)]
+    pub style: ShapeStyle,
+}
+
+/// A text visual.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TextVisual {
+    /// The text drawn.
+    pub text: String,
+    /// The X position of the text's baseline.
+    pub x: f64,
+    /// The Y position of the text's baseline.
+    pub y: f64,
+    /// The text's style.
+    #[serde(rename = "s", default)]
+    pub style: TextStyle,
+}
+
+/// A single shape drawn by a user's `RoomVisual` calls.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "t")]
+pub enum RoomVisual {
+    /// A circle.
+    #[serde(rename = "c")]
+    Circle(CircleVisual),
+    /// A line.
+    #[serde(rename = "l")]
+    Line(LineVisual),
+    /// A rectangle.
+    #[serde(rename = "r")]
+    Rect(RectVisual),
+    /// A polygon or polyline.
+    #[serde(rename = "p")]
+    Poly(PolyVisual),
+    /// A piece of text.
+    #[serde(rename = "t")]
+    Text(TextVisual),
+}
+
+impl RoomVisual {
+    /// Parses visuals from their wire format: JSON objects separated by `\n`.
+    ///
+    /// Entries which are not recognized or fail to parse are skipped.
+    pub fn parse_all(raw: &str) -> Vec<RoomVisual> {
+        raw.lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(visual) => Some(visual),
+                Err(e) => {
+                    debug!("skipping unrecognized room visual {:?}: {}", line, e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Serializes visuals into their wire format: JSON objects separated by `\n`.
+    pub fn encode_all<'a, I>(visuals: I) -> String
+    where
+        I: IntoIterator<Item = &'a RoomVisual>,
+    {
+        let mut result = String::new();
+        for visual in visuals {
+            let line = serde_json::to_string(visual)
+                .expect("expected serializing a RoomVisual to unfailingly succeed, but it failed.");
+            result.push_str(&line);
+            result.push('\n');
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SAMPLE: &str = concat!(
+        r##"{"t":"c","x":25,"y":25,"s":{"radius":0.5,"fill":"#ff0000","opacity":0.3}}"##,
+        "\n",
+        r##"{"t":"l","x1":10,"y1":10,"x2":20.5,"y2":20,"s":{"width":0.2,"color":"#ffffff","lineStyle":"dashed"}}"##,
+        "\n",
+        r#"{"t":"r","x":1,"y":2,"w":3,"h":4,"s":{"stroke":"blue","strokeWidth":0.1}}"#,
+        "\n",
+        r#"{"t":"p","points":[[1,1],[2,2],[3,1]],"s":{}}"#,
+        "\n",
+        r#"{"t":"x","future":"shape"}"#,
+        "\n",
+        r#"{"t":"t","text":"E: 300","x":10,"y":11,"s":{"font":0.6,"align":"left"}}"#,
+        "\n",
+        r#"{"t":"t","text":"hi","x":1,"y":1,"s":{"font":"bold 0.5 serif"}}"#,
+        "\n",
+    );
+
+    #[test]
+    fn parse_sample_visuals() {
+        let visuals = RoomVisual::parse_all(SAMPLE);
+
+        assert_eq!(visuals.len(), 6);
+        assert_eq!(
+            visuals[0],
+            RoomVisual::Circle(CircleVisual {
+                x: 25.0,
+                y: 25.0,
+                style: ShapeStyle {
+                    radius: Some(0.5),
+                    fill: Some("#ff0000".to_owned()),
+                    opacity: Some(0.3),
+                    ..ShapeStyle::default()
+                },
+            })
+        );
+        assert_eq!(
+            visuals[1],
+            RoomVisual::Line(LineVisual {
+                x1: 10.0,
+                y1: 10.0,
+                x2: 20.5,
+                y2: 20.0,
+                style: LineVisualStyle {
+                    width: Some(0.2),
+                    color: Some("#ffffff".to_owned()),
+                    opacity: None,
+                    line_style: Some(LineStyle::Dashed),
+                },
+            })
+        );
+        match visuals[2] {
+            RoomVisual::Rect(ref rect) => {
+                assert_eq!((rect.width, rect.height), (3.0, 4.0));
+                assert_eq!(rect.style.stroke_width, Some(0.1));
+            }
+            ref other => panic!("expected rect, found {:?}", other),
+        }
+        match visuals[3] {
+            RoomVisual::Poly(ref poly) => {
+                assert_eq!(poly.points, vec![(1.0, 1.0), (2.0, 2.0), (3.0, 1.0)]);
+            }
+            ref other => panic!("expected poly, found {:?}", other),
+        }
+        assert_eq!(
+            visuals[4],
+            RoomVisual::Text(TextVisual {
+                text: "E: 300".to_owned(),
+                x: 10.0,
+                y: 11.0,
+                style: TextStyle {
+                    font: Some(Font::Size(0.6)),
+                    align: Some(TextAlign::Left),
+                    ..TextStyle::default()
+                },
+            })
+        );
+        match visuals[5] {
+            RoomVisual::Text(ref text) => {
+                assert_eq!(
+                    text.style.font,
+                    Some(Font::Css("bold 0.5 serif".to_owned()))
+                );
+            }
+            ref other => panic!("expected text, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_malformed_visuals() {
+        let visuals =
+            RoomVisual::parse_all("not json\n{\"t\":\"c\"}\n\n{\"t\":\"c\",\"x\":1,\"y\":2}");
+
+        assert_eq!(
+            visuals,
+            vec![RoomVisual::Circle(CircleVisual {
+                x: 1.0,
+                y: 2.0,
+                style: ShapeStyle::default(),
+            })]
+        );
+    }
+
+    #[test]
+    fn visuals_round_trip() {
+        let visuals = RoomVisual::parse_all(SAMPLE);
+
+        let encoded = RoomVisual::encode_all(&visuals);
+
+        assert_eq!(RoomVisual::parse_all(&encoded), visuals);
+        assert!(encoded.starts_with(
+            r##"{"t":"c","x":25.0,"y":25.0,"s":{"radius":0.5,"fill":"#ff0000","opacity":0.3}}"##
+        ));
+    }
+}
@@ -6,7 +6,8 @@ use std::{
 
 use serde::{
     de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor},
-    {Deserialize, Deserializer},
+    ser::SerializeMap,
+    {Deserialize, Deserializer, Serialize, Serializer},
 };
 
 /// "Map view" room status update. This contains all entities in a given room,
@@ -29,6 +30,8 @@ pub struct RoomMapViewUpdate {
     pub controllers: Vec<(u32, u32)>,
     /// All keeper's lairs in the room.
     pub keeper_lairs: Vec<(u32, u32)>,
+    /// All deposits in the room. Only sent by servers which have deposits.
+    pub deposits: Vec<(u32, u32)>,
     /// All users who own objects in the room along with all owned objects for each user.
     ///
     /// This is a Vec of (user_id, owned_objects_of_that_user). The game does not provide
@@ -163,7 +166,8 @@ impl<'de> Visitor<'de> for RoomMapViewUpdateVisitor {
         let mut minerals = None;
         let mut controllers = None;
         let mut keeper_lairs = None;
-        // there are 8 expected keys, any extra are user ids
+        let mut deposits = None;
+        // there are 8 or 9 expected keys, any extra are user ids
         let mut users_objects =
             Vec::with_capacity(cmp::max(cmp::min(access.size_hint().unwrap_or(0), 4069), 8) - 8);
 
@@ -177,6 +181,7 @@ impl<'de> Visitor<'de> for RoomMapViewUpdateVisitor {
                 "m" => minerals = Some(value),
                 "c" => controllers = Some(value),
                 "k" => keeper_lairs = Some(value),
+                "d" => deposits = Some(value),
                 user_id => users_objects.push((user_id.to_owned(), value)),
             }
         }
@@ -190,6 +195,7 @@ impl<'de> Visitor<'de> for RoomMapViewUpdateVisitor {
             minerals: minerals.unwrap_or_default(),
             controllers: controllers.unwrap_or_default(),
             keeper_lairs: keeper_lairs.unwrap_or_default(),
+            deposits: deposits.unwrap_or_default(),
             users_objects: users_objects,
             _non_exhaustive: (),
         };
@@ -207,6 +213,28 @@ impl<'de> Deserialize<'de> for RoomMapViewUpdate {
     }
 }
 
+impl Serialize for RoomMapViewUpdate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(9 + self.users_objects.len()))?;
+        map.serialize_entry("w", &self.walls)?;
+        map.serialize_entry("r", &self.roads)?;
+        map.serialize_entry("pb", &self.power_or_power_bank)?;
+        map.serialize_entry("p", &self.portals)?;
+        map.serialize_entry("s", &self.sources)?;
+        map.serialize_entry("c", &self.controllers)?;
+        map.serialize_entry("m", &self.minerals)?;
+        map.serialize_entry("k", &self.keeper_lairs)?;
+        map.serialize_entry("d", &self.deposits)?;
+        for (user_id, objects) in &self.users_objects {
+            map.serialize_entry(user_id, objects)?;
+        }
+        map.end()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::RoomMapViewUpdate;
@@ -239,4 +267,16 @@ mod test {
         }"#,
         ).expect("expected edge case parsing to succeed");
     }
+
+    #[test]
+    fn parse_deposits() {
+        let update: RoomMapViewUpdate = serde_json::from_str(
+            r#"{"w":[],"r":[],"pb":[[10,12]],"p":[],"s":[],"c":[],"m":[],"k":[],"d":[[30,31]]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(update.power_or_power_bank, vec![(10, 12)]);
+        assert_eq!(update.deposits, vec![(30, 31)]);
+        assert!(update.users_objects.is_empty());
+    }
 }
@@ -0,0 +1,63 @@
+//! Update parsing for server-wide messages.
+use serde::{
+    de::{Deserialize, Deserializer},
+    ser::{Serialize, Serializer},
+};
+
+/// A message broadcast by the server to all connected clients, such as a maintenance notice.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct ServerMessageUpdate {
+    /// The message text.
+    pub message: String,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    _non_exhaustive: (),
+}
+
+// Servers send either the message text directly, or an object wrapping it.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum ServerMessageRepr {
+    Text(String),
+    Wrapped { message: String },
+}
+
+impl<'de> Deserialize<'de> for ServerMessageUpdate {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let message = match ServerMessageRepr::deserialize(deserializer)? {
+            ServerMessageRepr::Text(message) | ServerMessageRepr::Wrapped { message } => message,
+        };
+
+        Ok(ServerMessageUpdate {
+            message,
+            _non_exhaustive: (),
+        })
+    }
+}
+
+impl Serialize for ServerMessageUpdate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.message)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ServerMessageUpdate;
+
+    #[test]
+    fn parse_server_messages() {
+        let update: ServerMessageUpdate =
+            serde_json::from_value(json!("Server restart in 5 minutes")).unwrap();
+        assert_eq!(update.message, "Server restart in 5 minutes");
+
+        let update: ServerMessageUpdate =
+            serde_json::from_value(json!({ "message": "Server restart in 5 minutes" })).unwrap();
+        assert_eq!(update.message, "Server restart in 5 minutes");
+    }
+}
@@ -0,0 +1,42 @@
+//! Update parsing for active code branch updates.
+
+/// Which of a user's active branches changed.
+#[derive(serde::Serialize, serde::Deserialize, Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum ActiveBranchKind {
+    /// The branch running in the world.
+    #[serde(rename = "activeWorld")]
+    World,
+    /// The branch running in the simulation room.
+    #[serde(rename = "activeSim")]
+    Sim,
+}
+
+/// Update sent when a user sets a new active code branch.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct ActiveBranchUpdate {
+    /// Whether the world or simulation branch was changed.
+    #[serde(rename = "activeName")]
+    pub kind: ActiveBranchKind,
+    /// The name of the newly active branch.
+    pub branch: String,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    #[serde(skip)]
+    _non_exhaustive: (),
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ActiveBranchKind, ActiveBranchUpdate};
+
+    #[test]
+    fn parse_active_branch() {
+        let update: ActiveBranchUpdate = serde_json::from_value(json!({
+            "activeName": "activeWorld",
+            "branch": "default"
+        }))
+        .unwrap();
+
+        assert_eq!(update.kind, ActiveBranchKind::World);
+        assert_eq!(update.branch, "default");
+    }
+}
@@ -1,5 +1,8 @@
 //! Update parsing for console message updates.
-use serde::de::{Deserialize, Deserializer, Error};
+use serde::{
+    de::{Deserialize, Deserializer, Error},
+    ser::{Serialize, SerializeMap, Serializer},
+};
 
 /// Update for a user's log messages during the last tick.
 #[derive(Clone, Hash, Debug)]
@@ -50,6 +53,12 @@ struct InnerUpdateRepresentation {
     shard: Option<String>,
 }
 
+#[derive(serde::Serialize)]
+struct InnerUpdateInnerMessagesRef<'a> {
+    log: &'a [String],
+    results: &'a [String],
+}
+
 impl<'de> Deserialize<'de> for UserConsoleUpdate {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -88,3 +97,32 @@ impl<'de> Deserialize<'de> for UserConsoleUpdate {
         Ok(parsed)
     }
 }
+
+impl Serialize for UserConsoleUpdate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        match *self {
+            UserConsoleUpdate::Messages {
+                ref log_messages,
+                ref result_messages,
+                ..
+            } => map.serialize_entry(
+                "messages",
+                &InnerUpdateInnerMessagesRef {
+                    log: log_messages,
+                    results: result_messages,
+                },
+            )?,
+            UserConsoleUpdate::Error { ref message, .. } => {
+                map.serialize_entry("error", message)?
+            }
+        }
+        if let Some(shard) = self.shard() {
+            map.serialize_entry("shard", shard)?;
+        }
+        map.end()
+    }
+}
@@ -0,0 +1,105 @@
+//! Update parsing for memory path updates.
+use std::fmt;
+
+use serde::{
+    de::{Deserialize, Deserializer, Error, Visitor},
+    ser::{Serialize, Serializer},
+};
+
+/// Update for the value at a subscribed path in a user's memory.
+///
+/// The server sends the value stringified as JSON, or `"undefined"` when nothing is stored at the
+/// path. Values which are not valid JSON are kept as a plain string.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserMemoryPathUpdate {
+    /// The value currently at this memory path, or `None` if the path is undefined.
+    pub value: Option<serde_json::Value>,
+    /// Phantom data in order to allow adding any additional fields in the future.
+    _non_exhaustive: (),
+}
+
+impl UserMemoryPathUpdate {
+    fn from_stringified(raw: &str) -> Self {
+        let value = match raw {
+            "undefined" => None,
+            _ => Some(
+                serde_json::from_str(raw)
+                    .unwrap_or_else(|_| serde_json::Value::String(raw.to_owned())),
+            ),
+        };
+
+        UserMemoryPathUpdate {
+            value,
+            _non_exhaustive: (),
+        }
+    }
+}
+
+struct UserMemoryPathUpdateVisitor;
+
+impl<'de> Visitor<'de> for UserMemoryPathUpdateVisitor {
+    type Value = UserMemoryPathUpdate;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a stringified JSON value")
+    }
+
+    #[inline]
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok(UserMemoryPathUpdate {
+            value: None,
+            _non_exhaustive: (),
+        })
+    }
+
+    #[inline]
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok(UserMemoryPathUpdate::from_stringified(v))
+    }
+}
+
+impl<'de> Deserialize<'de> for UserMemoryPathUpdate {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(UserMemoryPathUpdateVisitor)
+    }
+}
+
+impl Serialize for UserMemoryPathUpdate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.value {
+            Some(ref value) => serializer.serialize_str(&value.to_string()),
+            None => serializer.serialize_str("undefined"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::UserMemoryPathUpdate;
+
+    #[test]
+    fn parse_memory_path_values() {
+        let update: UserMemoryPathUpdate =
+            serde_json::from_value(json!("{\"level\":3,\"rooms\":[\"E1N1\"]}")).unwrap();
+        assert_eq!(update.value, Some(json!({"level": 3, "rooms": ["E1N1"]})));
+
+        let update: UserMemoryPathUpdate = serde_json::from_value(json!("undefined")).unwrap();
+        assert_eq!(update.value, None);
+
+        let update: UserMemoryPathUpdate =
+            serde_json::from_value(json!("Incorrect memory path")).unwrap();
+        assert_eq!(update.value, Some(json!("Incorrect memory path")));
+    }
+}
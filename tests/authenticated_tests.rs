@@ -1,8 +1,37 @@
 #![cfg(feature = "sync")]
+//! Tests of authenticated calls.
+//!
+//! These call the official server with the token in `SCREEPS_API_TOKEN`. With the `testing`
+//! feature, they call an in-process mock server instead, set up with the rooms, users and
+//! leaderboards the tests expect.
+use std::ops::{Deref, DerefMut};
+
 use screeps_api::error::{ApiError, ErrorKind};
-use screeps_api::SyncApi;
+use screeps_api::{RecentPvpArgs, SyncApi};
+
+/// An API logged in to the server being tested. Keeps the mock server running while in use.
+struct Session {
+    api: SyncApi,
+    #[cfg(feature = "testing")]
+    _server: screeps_api::testing::MockServer,
+}
+
+impl Deref for Session {
+    type Target = SyncApi;
+
+    fn deref(&self) -> &SyncApi {
+        &self.api
+    }
+}
+
+impl DerefMut for Session {
+    fn deref_mut(&mut self) -> &mut SyncApi {
+        &mut self.api
+    }
+}
 
 /// Set up dotenv and retrieve a specific variable, informatively panicking if it does not exist.
+#[cfg(not(feature = "testing"))]
 fn env(var: &str) -> String {
     dotenv::dotenv().ok();
     match ::std::env::var(var) {
@@ -11,9 +40,80 @@ fn env(var: &str) -> String {
     }
 }
 
-fn logged_in() -> SyncApi {
+#[cfg(not(feature = "testing"))]
+fn logged_in() -> Session {
     let token = env("SCREEPS_API_TOKEN");
-    SyncApi::new().unwrap().with_token(token)
+    Session {
+        api: SyncApi::new().unwrap().with_token(token),
+    }
+}
+
+/// Starts a mock server with what the tests expect from the official server, and logs in to it.
+#[cfg(feature = "testing")]
+fn logged_in() -> Session {
+    use screeps_api::{testing::World, LeaderboardType};
+
+    let score = |season: &str| (LeaderboardType::GlobalControl, season.to_owned());
+
+    let mut world = World::new();
+    world.set_time(1000);
+    world.add_shard("shard0");
+    world.add_shard("shard2");
+    world.add_season("2017-01");
+    world.add_season("2017-02");
+
+    let token = {
+        let user = world.add_user("daboross", "password");
+        user.start_shard = Some("shard0".to_owned());
+        user.start_room = "W1N1".to_owned();
+        user.leaderboard_scores.insert(score("2017-01"), 1_500_000);
+        user.leaderboard_scores.insert(score("2017-02"), 2_000_000);
+        user.add_power_creep("operator1").level = 2;
+        user.token.clone()
+    };
+    world
+        .add_user("dissi", "password")
+        .leaderboard_scores
+        .insert(score("2017-02"), 3_000_000);
+
+    for &room_name in &["W1N1", "W3N9", "W0N0"] {
+        world.add_room(Some("shard0"), room_name);
+    }
+    {
+        let room = world.room_mut(Some("shard0"), "W3N9").unwrap();
+        room.owner = Some("dissi".to_owned());
+        room.level = 8;
+        room.last_pvp_time = Some(990);
+        room.objects.insert(
+            "5829cc2bba9a9a487c236088".to_owned(),
+            serde_json::json!({
+                "_id": "5829cc2bba9a9a487c236088",
+                "type": "road",
+                "room": "W3N9",
+                "x": 38,
+                "y": 37,
+                "hits": 5000,
+                "hitsMax": 5000,
+                "nextDecayTime": 1500,
+                "notifyWhenAttacked": true
+            }),
+        );
+    }
+    for &room_name in &["W13S21", "W12S20", "E32N29", "E50N35"] {
+        world.add_room(Some("shard2"), room_name);
+    }
+
+    let server = screeps_api::testing::MockServer::start_with(world).unwrap();
+    let api = SyncApi::new()
+        .unwrap()
+        .with_url(server.url())
+        .unwrap()
+        .with_token(token);
+
+    Session {
+        api,
+        _server: server,
+    }
 }
 
 #[test]
@@ -194,6 +294,53 @@ fn test_auth_leaderboard_limit_parameter_error() {
     }
 }
 
+#[test]
+fn test_auth_power_creeps() {
+    let mut api = logged_in();
+
+    for power_creep in api.power_creeps().unwrap() {
+        assert!(!power_creep.name.is_empty());
+    }
+}
+
+#[test]
+fn test_auth_recent_pvp() {
+    let mut api = logged_in();
+
+    let result = api.recent_pvp(RecentPvpArgs::within(500)).unwrap();
+
+    assert!(!result.shards.is_empty());
+}
+
+#[test]
+fn test_auth_room_history() {
+    let mut api = logged_in();
+
+    let pvp = api.recent_pvp(RecentPvpArgs::within(500)).unwrap();
+    let (shard, room_name, time) = pvp
+        .shards
+        .iter()
+        .flat_map(|(shard, info)| {
+            info.rooms
+                .iter()
+                .map(move |&(room_name, time)| (shard.clone(), room_name, time))
+        })
+        .next()
+        .expect("expected PvP to have happened in at least one room");
+
+    // use the chunk before the one PvP happened in, as the newest chunk may not be saved yet.
+    let tick = time - time % 20 - 20;
+    let history = api
+        .room_history(room_name.to_string(), Some(shard), tick)
+        .unwrap();
+
+    assert_eq!(history.room_name, room_name);
+    assert_eq!(history.base_tick, tick);
+    for state in history.states() {
+        state.unwrap();
+    }
+}
+
 #[test]
 #[cfg(feature = "destructive-tests")]
 fn test_memory_segment() {